@@ -1,55 +1,200 @@
 use std::{
     collections::HashMap,
     env,
-    fs::{read_to_string, File},
+    fs::{self, read_to_string},
+    future::Future,
     path::Path,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
 };
 
 use elasticsearch::{
     auth::Credentials,
-    cat::CatIndicesParts,
+    cat::{CatIndicesParts, CatSegmentsParts, CatShardsParts},
+    cert::{Certificate, CertificateValidation},
+    cluster::{
+        ClusterDeleteComponentTemplateParts, ClusterGetComponentTemplateParts, ClusterHealthParts,
+        ClusterPutComponentTemplateParts, ClusterStateParts, ClusterStatsParts,
+    },
+    enrich::{
+        EnrichDeletePolicyParts, EnrichExecutePolicyParts, EnrichGetPolicyParts,
+        EnrichPutPolicyParts,
+    },
     http::{
+        response::Response,
         transport::{SingleNodeConnectionPool, TransportBuilder},
         StatusCode, Url,
     },
-    indices::{IndicesCreateParts, IndicesDeleteParts},
-    params::{ExpandWildcards, Refresh},
-    BulkOperation, BulkParts, Elasticsearch, SearchParts,
+    indices::{
+        IndicesAddBlockParts, IndicesClearCacheParts, IndicesCloneParts, IndicesCreateParts,
+        IndicesDeleteIndexTemplateParts, IndicesDeleteParts, IndicesExistsParts,
+        IndicesFieldUsageStatsParts, IndicesFlushParts, IndicesForcemergeParts,
+        IndicesGetAliasParts, IndicesGetIndexTemplateParts, IndicesGetMappingParts,
+        IndicesGetSettingsParts, IndicesPutIndexTemplateParts, IndicesPutMappingParts,
+        IndicesPutSettingsParts, IndicesRecoveryParts, IndicesRefreshParts, IndicesRolloverParts,
+        IndicesShrinkParts, IndicesSplitParts, IndicesStatsParts,
+    },
+    migration::MigrationDeprecationsParts,
+    nodes::NodesStatsParts,
+    params::{Bytes, ExpandWildcards, GroupBy, Refresh, WaitForStatus},
+    security::{
+        SecurityChangePasswordParts, SecurityDeleteRoleParts, SecurityDeleteUserParts,
+        SecurityGetRoleParts, SecurityGetUserParts, SecurityPutRoleParts, SecurityPutUserParts,
+    },
+    slm::{
+        SlmDeleteLifecycleParts, SlmExecuteLifecycleParts, SlmGetLifecycleParts,
+        SlmPutLifecycleParts,
+    },
+    snapshot::{
+        SnapshotCreateParts, SnapshotDeleteParts, SnapshotGetParts, SnapshotRestoreParts,
+        SnapshotStatusParts,
+    },
+    tasks::{TasksCancelParts, TasksGetParts},
+    transform::{
+        TransformGetTransformParts, TransformGetTransformStatsParts,
+        TransformPreviewTransformParts, TransformPutTransformParts, TransformStartTransformParts,
+        TransformStopTransformParts,
+    },
+    watcher::{
+        WatcherAckWatchParts, WatcherDeleteWatchParts, WatcherExecuteWatchParts,
+        WatcherPutWatchParts,
+    },
+    BulkOperation, BulkParts, ClearScrollParts, CountParts, DeleteScriptParts, Elasticsearch,
+    GetScriptParts, PutScriptParts, ScrollParts, SearchParts,
+};
+use serde::{
+    de::{DeserializeOwned, Error as _},
+    Deserialize, Deserializer, Serialize,
 };
-use serde::Deserialize;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    observe::{self, Observe},
+    profile::{self, Profile},
+    source,
+    throttle::{backoff_delay, Throttle},
+};
+
+pub type Document = HashMap<String, Value>;
+
+/// How long to wait for an auth command to print credentials before giving
+/// up on it, so that a hung credential helper cannot stall a run.
+const AUTH_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// How long the server should keep a scroll context alive between requests.
+const SCROLL_KEEP_ALIVE: &str = "1m";
+
+/// Whether responses which only decode by tolerating differences from the
+/// expected schema should be treated as errors instead.
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Differences from the expected schema tolerated while decoding responses,
+/// such as missing fields or integers given as strings.
+static DRIFT: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+/// Makes responses that differ from the expected schema fail to decode,
+/// rather than being tolerated.
+///
+pub fn enable_strict() {
+    STRICT.store(true, Ordering::Relaxed);
+}
+
+#[derive(Clone)]
 pub struct SimpleClient {
     url: Url,
-    elasticsearch: Elasticsearch,
+    elasticsearch: Arc<RwLock<Elasticsearch>>,
+    auth_command: Option<String>,
+    tls: Tls,
+}
+
+/// How the server certificate is verified: against a specific CA
+/// certificate (held as PEM), not at all, or by default against the
+/// system trust store.
+///
+#[derive(Clone, Default)]
+struct Tls {
+    ca_cert: Option<Vec<u8>>,
+    insecure: bool,
+}
+
+impl Tls {
+    fn for_profile(name: &str, profile: &Profile) -> Result<Self, Error> {
+        let ca_cert = match &profile.ca_cert {
+            Some(path) => {
+                let pem = fs::read(path).map_err(|e| {
+                    Error::new(
+                        ErrorType::ConfigurationError,
+                        format!("failed to read CA certificate {path} for profile {name} ({e})"),
+                    )
+                })?;
+                if let Err(e) = Certificate::from_pem(&pem) {
+                    return Err(Error::new(
+                        ErrorType::ConfigurationError,
+                        format!("failed to parse CA certificate {path} for profile {name} ({e})"),
+                    ));
+                }
+                Some(pem)
+            }
+            None => None,
+        };
+        Ok(Self {
+            ca_cert,
+            insecure: profile.insecure,
+        })
+    }
 }
 
 impl SimpleClient {
     /// Creates a new client with the given URL and credentials.
     ///
-    pub fn new(url: Url, auth: Credentials) -> Self {
-        Self {
+    pub fn new(url: Url, auth: Credentials) -> Result<Self, Error> {
+        Self::with_tls(url, auth, Tls::default())
+    }
+
+    fn with_tls(url: Url, auth: Credentials, tls: Tls) -> Result<Self, Error> {
+        Ok(Self {
             url: url.clone(),
-            elasticsearch: Elasticsearch::new(
-                TransportBuilder::new(SingleNodeConnectionPool::new(url))
-                    .auth(auth)
-                    .build()
-                    .expect("Failed to create transport"),
-            ),
-        }
+            elasticsearch: Arc::new(RwLock::new(connect(url, auth, &tls)?)),
+            auth_command: None,
+            tls,
+        })
     }
 
-    /// Creates a new client by first checking environment variables, then
-    /// sniffing for a _start-local_ `.env` file, if these are not found.
-    /// Overall, the sequence of checks is as follows:
+    /// Creates a new client which obtains its credentials by running a
+    /// shell command, such as a lookup in a secrets manager. The command is
+    /// run once up front, and again whenever the server rejects the current
+    /// credentials, so that short-lived tokens can be refreshed mid-run.
     ///
-    /// 1. Check for `ESCLI_URL` and `ESCLI_API_KEY` env vars
-    /// 2. Check for `ESCLI_URL` and `ESCLI_USER`/`ESCLI_PASSWORD` env vars
-    /// 3. Check for `.env` file in current directory
-    /// 4. Check for `.env` file in `elastic-start-local` subdirectory
-    /// 5. Give up and fail
+    pub fn with_auth_command(url: Url, command: &str) -> Result<Self, Error> {
+        let auth = run_auth_command_blocking(command)?;
+        Ok(Self {
+            auth_command: Some(command.to_string()),
+            ..Self::new(url, auth)?
+        })
+    }
+
+    /// Creates a new client by first checking for a selected profile, then
+    /// environment variables, then sniffing for a _start-local_ `.env`
+    /// file, if these are not found. Overall, the sequence of checks is as
+    /// follows:
+    ///
+    /// 1. Check for a profile chosen by `--profile`, `ESCLI_PROFILE` or
+    ///    as the default in the configuration file
+    /// 2. Check for `ESCLI_URL` and `ESCLI_API_KEY` env vars
+    /// 3. Check for `ESCLI_URL` and `ESCLI_USER`/`ESCLI_PASSWORD` env vars
+    /// 4. Check for `.env` file in current directory
+    /// 5. Check for `.env` file in `elastic-start-local` subdirectory
+    /// 6. Give up and fail
     ///
     pub fn default() -> Result<Self, Error> {
+        if let Some((name, profile)) = profile::active() {
+            return Self::from_profile(name, profile);
+        }
         match Self::from_env_vars() {
             Ok(client) => Ok(client),
             Err(_) => {
@@ -76,37 +221,19 @@ impl SimpleClient {
     /// - `ESCLI_USER` - user name for authentication (default `elastic`)
     /// - `ESCLI_PASSWORD` - password for authentication
     /// - `ESCLI_API_KEY` - API key for authentication
+    /// - `ESCLI_AUTH_CMD` - shell command which prints an API key or token
+    /// - `ESCLI_FALLBACK_URL` - URL of a secondary cluster for reads (see
+    ///   [`SimpleClient::fallback_from_env_vars`])
     ///
     /// A URL is required, but it is not necessary to provide values for all
-    /// authentication variables. Either `ESCLI_USER`/`ESCLI_PASSWORD` or
-    /// `ESCLI_API_KEY` may be supplied.
+    /// authentication variables. Either `ESCLI_USER`/`ESCLI_PASSWORD`,
+    /// `ESCLI_API_KEY` or `ESCLI_AUTH_CMD` may be supplied, with the command
+    /// taking precedence if set.
     ///
     pub fn from_env_vars() -> Result<Self, Error> {
         match env::var("ESCLI_URL") {
             Ok(url) => match Url::parse(url.as_str()) {
-                Ok(url) => {
-                    let auth;
-                    match env::var("ESCLI_API_KEY") {
-                        Ok(api_key) => {
-                            auth = Credentials::EncodedApiKey(api_key);
-                        }
-                        Err(_) => match env::var("ESCLI_PASSWORD") {
-                            Ok(password) => {
-                                auth = Credentials::Basic(
-                                    env::var("ESCLI_USER").unwrap_or(String::from("elastic")),
-                                    password,
-                                );
-                            }
-                            Err(e) => {
-                                return Err(Error::new(
-                                    ErrorType::ConfigurationError,
-                                    format!("failed to load Elasticsearch credentials from either ESCLI_API_KEY or ESCLI_USER/ESCLI_PASSWORD ({e})")
-                                ));
-                            }
-                        },
-                    }
-                    Ok(Self::new(url, auth))
-                }
+                Ok(url) => Self::with_env_credentials(url),
                 Err(e) => Err(Error::new(
                     ErrorType::ConfigurationError,
                     format!("failed to parse ESCLI_URL ({e})"),
@@ -119,6 +246,112 @@ impl SimpleClient {
         }
     }
 
+    /// Creates a new client from a named profile in the configuration file.
+    /// As with environment variables, an auth command takes precedence over
+    /// an API key, which takes precedence over a user name and password.
+    ///
+    pub fn from_profile(name: &str, profile: &Profile) -> Result<Self, Error> {
+        match Url::parse(profile.url.as_str()) {
+            Ok(url) => Self::with_profile_credentials(url, name, profile),
+            Err(e) => Err(Error::new(
+                ErrorType::ConfigurationError,
+                format!("failed to parse URL for profile {name} ({e})"),
+            )),
+        }
+    }
+
+    /// Creates a client for the secondary cluster, if one is configured,
+    /// from the selected profile or otherwise from `ESCLI_FALLBACK_URL`.
+    ///
+    pub fn fallback() -> Option<Result<Self, Error>> {
+        match profile::active() {
+            Some((name, profile)) => Self::fallback_from_profile(name, profile),
+            None => Self::fallback_from_env_vars(),
+        }
+    }
+
+    /// Creates a client for the secondary cluster given by a profile's
+    /// `fallback_url`, using the same credentials and TLS options as the
+    /// primary. Returns `None` if no fallback is configured.
+    ///
+    pub fn fallback_from_profile(name: &str, profile: &Profile) -> Option<Result<Self, Error>> {
+        let url = profile.fallback_url.as_ref()?;
+        Some(match Url::parse(url.as_str()) {
+            Ok(url) => Self::with_profile_credentials(url, name, profile),
+            Err(e) => Err(Error::new(
+                ErrorType::ConfigurationError,
+                format!("failed to parse fallback URL for profile {name} ({e})"),
+            )),
+        })
+    }
+
+    fn with_profile_credentials(url: Url, name: &str, profile: &Profile) -> Result<Self, Error> {
+        let tls = Tls::for_profile(name, profile)?;
+        if let Some(command) = &profile.auth_cmd {
+            let auth = run_auth_command_blocking(command)?;
+            return Ok(Self {
+                auth_command: Some(command.clone()),
+                ..Self::with_tls(url, auth, tls)?
+            });
+        }
+        let auth = match (&profile.api_key, &profile.password) {
+            (Some(api_key), _) => Credentials::EncodedApiKey(api_key.clone()),
+            (None, Some(password)) => Credentials::Basic(
+                profile.user.clone().unwrap_or(String::from("elastic")),
+                password.clone(),
+            ),
+            (None, None) => {
+                return Err(Error::new(
+                    ErrorType::ConfigurationError,
+                    format!("profile {name} has no auth_cmd, api_key or password"),
+                ));
+            }
+        };
+        Self::with_tls(url, auth, tls)
+    }
+
+    /// Creates a client for the secondary cluster given by
+    /// `ESCLI_FALLBACK_URL`, using the same credentials as the primary.
+    /// Returns `None` if no fallback is configured.
+    ///
+    pub fn fallback_from_env_vars() -> Option<Result<Self, Error>> {
+        let url = env::var("ESCLI_FALLBACK_URL").ok()?;
+        Some(match Url::parse(url.as_str()) {
+            Ok(url) => Self::with_env_credentials(url),
+            Err(e) => Err(Error::new(
+                ErrorType::ConfigurationError,
+                format!("failed to parse ESCLI_FALLBACK_URL ({e})"),
+            )),
+        })
+    }
+
+    fn with_env_credentials(url: Url) -> Result<Self, Error> {
+        if let Ok(command) = env::var("ESCLI_AUTH_CMD") {
+            return Self::with_auth_command(url, &command);
+        }
+        let auth;
+        match env::var("ESCLI_API_KEY") {
+            Ok(api_key) => {
+                auth = Credentials::EncodedApiKey(api_key);
+            }
+            Err(_) => match env::var("ESCLI_PASSWORD") {
+                Ok(password) => {
+                    auth = Credentials::Basic(
+                        env::var("ESCLI_USER").unwrap_or(String::from("elastic")),
+                        password,
+                    );
+                }
+                Err(e) => {
+                    return Err(Error::new(
+                        ErrorType::ConfigurationError,
+                        format!("failed to load Elasticsearch credentials from either ESCLI_API_KEY or ESCLI_USER/ESCLI_PASSWORD ({e})")
+                    ));
+                }
+            },
+        }
+        Self::new(url, auth)
+    }
+
     pub fn for_start_local(path: &Path) -> Result<Self, Error> {
         match read_to_string(path.join(".env")) {
             Ok(string) => {
@@ -154,7 +387,7 @@ impl SimpleClient {
                         ));
                     }
                 };
-                Ok(Self::new(url, auth))
+                Self::new(url, auth)
             }
             Err(e) => Err(Error::new(
                 ErrorType::ConfigurationError,
@@ -167,15 +400,50 @@ impl SimpleClient {
         &self.url
     }
 
+    /// Sends a request built by the given function, observing the response.
+    /// If the server rejects the credentials and an auth command is in use,
+    /// the command is run again for fresh credentials and the request is
+    /// retried once.
+    ///
+    async fn send<F, Fut>(&self, request: F) -> Result<Response, elasticsearch::Error>
+    where
+        F: Fn(Elasticsearch) -> Fut,
+        Fut: Future<Output = Result<Response, elasticsearch::Error>>,
+    {
+        let result = request(self.es()).observed().await;
+        let unauthorized =
+            matches!(&result, Ok(response) if response.status_code().as_u16() == 401);
+        match &self.auth_command {
+            Some(command) if unauthorized => match run_auth_command(command)
+                .await
+                .and_then(|auth| connect(self.url.clone(), auth, &self.tls))
+            {
+                Ok(elasticsearch) => {
+                    *self.elasticsearch.write().unwrap() = elasticsearch;
+                    request(self.es()).observed().await
+                }
+                Err(e) => {
+                    eprintln!("Failed to refresh credentials: {e}");
+                    result
+                }
+            },
+            _ => result,
+        }
+    }
+
+    fn es(&self) -> Elasticsearch {
+        self.elasticsearch.read().unwrap().clone()
+    }
+
     pub async fn ping(&self) -> Result<StatusCode, Error> {
-        match self.elasticsearch.ping().send().await {
+        match self.send(|es| async move { es.ping().send().await }).await {
             Ok(response) => Ok(response.status_code()),
             Err(e) => Err(Error::from_client_error(&e)),
         }
     }
 
     pub async fn info(&self) -> Result<RawInfo, Error> {
-        match self.elasticsearch.info().send().await {
+        match self.send(|es| async move { es.info().send().await }).await {
             Ok(response) => match response.json::<RawInfo>().await {
                 Ok(info) => Ok(info),
                 Err(e) => Err(Error::from_client_error(&e)),
@@ -192,33 +460,48 @@ impl SimpleClient {
         closed: bool,
     ) -> Result<Vec<IndexDetail>, Error> {
         match self
-            .elasticsearch
-            .cat()
-            .indices(CatIndicesParts::Index(patterns))
-            .format("json")
-            .bytes(elasticsearch::params::Bytes::B)
-            .expand_wildcards(if all && open && closed {
-                &[
-                    ExpandWildcards::Open,
-                    ExpandWildcards::Closed,
-                    ExpandWildcards::Hidden,
-                ]
-            } else if all && open {
-                &[ExpandWildcards::Open, ExpandWildcards::Hidden]
-            } else if all && closed {
-                &[ExpandWildcards::Closed, ExpandWildcards::Hidden]
-            } else if open && closed {
-                &[ExpandWildcards::Open, ExpandWildcards::Closed]
-            } else if all {
-                &[ExpandWildcards::All]
-            } else if open {
-                &[ExpandWildcards::Open]
-            } else if closed {
-                &[ExpandWildcards::Closed]
-            } else {
-                &[ExpandWildcards::Open]
+            .send(|es| async move {
+                es.cat()
+                    .indices(CatIndicesParts::Index(patterns))
+                    .format("json")
+                    .h(&[
+                        "health",
+                        "status",
+                        "index",
+                        "uuid",
+                        "pri",
+                        "rep",
+                        "docs.count",
+                        "docs.deleted",
+                        "store.size",
+                        "dataset.size",
+                        "creation.date.string",
+                    ])
+                    .bytes(elasticsearch::params::Bytes::B)
+                    .expand_wildcards(if all && open && closed {
+                        &[
+                            ExpandWildcards::Open,
+                            ExpandWildcards::Closed,
+                            ExpandWildcards::Hidden,
+                        ]
+                    } else if all && open {
+                        &[ExpandWildcards::Open, ExpandWildcards::Hidden]
+                    } else if all && closed {
+                        &[ExpandWildcards::Closed, ExpandWildcards::Hidden]
+                    } else if open && closed {
+                        &[ExpandWildcards::Open, ExpandWildcards::Closed]
+                    } else if all {
+                        &[ExpandWildcards::All]
+                    } else if open {
+                        &[ExpandWildcards::Open]
+                    } else if closed {
+                        &[ExpandWildcards::Closed]
+                    } else {
+                        &[ExpandWildcards::Open]
+                    })
+                    .send()
+                    .await
             })
-            .send()
             .await
         {
             Ok(response) => match response.status_code().as_u16() {
@@ -230,34 +513,19 @@ impl SimpleClient {
                             status: entry["status"].as_str().unwrap_or("unknown").to_string(),
                             name: entry["index"].as_str().unwrap_or("unknown").to_string(),
                             uuid: entry["uuid"].as_str().unwrap_or("unknown").to_string(),
-                            docs_count: match entry["docs.count"].as_str() {
-                                Some(string_value) => match string_value.parse::<u64>() {
-                                    Ok(value) => Some(value),
-                                    Err(_) => None,
-                                },
-                                None => None,
-                            },
-                            docs_deleted: match entry["docs.deleted"].as_str() {
-                                Some(string_value) => match string_value.parse::<u64>() {
-                                    Ok(value) => Some(value),
-                                    Err(_) => None,
-                                },
-                                None => None,
-                            },
-                            store_size: match entry["store.size"].as_str() {
-                                Some(string_value) => match string_value.parse::<u64>() {
-                                    Ok(value) => Some(value),
-                                    Err(_) => None,
-                                },
-                                None => None,
-                            },
-                            dataset_size: match entry["dataset.size"].as_str() {
-                                Some(string_value) => match string_value.parse::<u64>() {
-                                    Ok(value) => Some(value),
-                                    Err(_) => None,
-                                },
-                                None => None,
-                            },
+                            docs_count: entry["docs.count"].as_str().and_then(|x| x.parse().ok()),
+                            docs_deleted: entry["docs.deleted"]
+                                .as_str()
+                                .and_then(|x| x.parse().ok()),
+                            store_size: entry["store.size"].as_str().and_then(|x| x.parse().ok()),
+                            dataset_size: entry["dataset.size"]
+                                .as_str()
+                                .and_then(|x| x.parse().ok()),
+                            primaries: entry["pri"].as_str().and_then(|x| x.parse().ok()),
+                            replicas: entry["rep"].as_str().and_then(|x| x.parse().ok()),
+                            creation_date: entry["creation.date.string"]
+                                .as_str()
+                                .map(|x| x.to_string()),
                         })
                         .collect(),
                     Err(e) => {
@@ -283,29 +551,1532 @@ impl SimpleClient {
         }
     }
 
+    pub async fn index_exists(&self, index: &str) -> Result<bool, Error> {
+        match self
+            .send(|es| async move {
+                es.indices()
+                    .exists(IndicesExistsParts::Index(&[index]))
+                    .send()
+                    .await
+            })
+            .await
+        {
+            Ok(response) => match response.status_code().as_u16() {
+                200..=299 => Ok(true),
+                404 => Ok(false),
+                status => Err(Error::new(
+                    ErrorType::ServerError(status),
+                    format!("failed to check existence of index {index}"),
+                )),
+            },
+            Err(e) => Err(Error::from_client_error(&e)),
+        }
+    }
+
+    pub async fn get_index_stats(
+        &self,
+        patterns: &[&str],
+        metrics: &[&str],
+    ) -> Result<RawIndicesStats, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .stats(IndicesStatsParts::IndexMetric(patterns, metrics))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Lists the shards of the matching indexes, with the given columns
+    /// (or the default columns, if none are given).
+    ///
+    pub async fn get_shard_list(
+        &self,
+        patterns: &[&str],
+        columns: &[&str],
+    ) -> Result<Vec<HashMap<String, Value>>, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let cat = es.cat();
+                let mut request = cat
+                    .shards(CatShardsParts::Index(patterns))
+                    .format("json")
+                    .bytes(Bytes::B);
+                if !columns.is_empty() {
+                    request = request.h(columns);
+                }
+                request.send().await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Explains why a shard is unassigned, or why it is allocated where it
+    /// is. If no shard is given, the first unassigned shard is explained.
+    ///
+    pub async fn explain_allocation(
+        &self,
+        shard: Option<(&str, u32, bool)>,
+    ) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let cluster = es.cluster();
+                let request = cluster.allocation_explain();
+                match shard {
+                    Some((index, number, primary)) => {
+                        request
+                            .body(json!({"index": index, "shard": number, "primary": primary}))
+                            .send()
+                            .await
+                    }
+                    None => request.send().await,
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Lists the Lucene segments of each shard of the matching indexes,
+    /// with the given columns only.
+    ///
+    pub async fn get_segment_list(
+        &self,
+        patterns: &[&str],
+        columns: &[&str],
+    ) -> Result<Vec<HashMap<String, Value>>, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.cat()
+                    .segments(CatSegmentsParts::Index(patterns))
+                    .format("json")
+                    .bytes(Bytes::B)
+                    .h(columns)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the progress of shard recoveries for the matching indexes,
+    /// optionally including only those still in progress.
+    ///
+    pub async fn get_recovery(&self, patterns: &[&str], active_only: bool) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .recovery(IndicesRecoveryParts::Index(patterns))
+                    .active_only(active_only)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn get_node_list(
+        &self,
+        columns: &[&str],
+    ) -> Result<Vec<HashMap<String, Value>>, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.cat()
+                    .nodes()
+                    .format("json")
+                    .bytes(Bytes::B)
+                    .full_id(true)
+                    .h(columns)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches statistics for the nodes matching a node ID, name or other
+    /// node filter, restricted to the given metrics (such as `jvm` or `fs`).
+    ///
+    pub async fn get_node_stats(&self, node: &str, metrics: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.nodes()
+                    .stats(NodesStatsParts::NodeIdMetric(&[node], metrics))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn get_cluster_health(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(
+                |es| async move { es.cluster().health(ClusterHealthParts::None).send().await },
+            )
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches cluster health, first waiting up to the given timeout for the
+    /// cluster to reach a status, if one is given. If the wait times out,
+    /// the health is still returned, with `timed_out` set.
+    ///
+    pub async fn wait_for_cluster_health(
+        &self,
+        status: Option<WaitForStatus>,
+        timeout: &str,
+    ) -> Result<Value, Error> {
+        let result = self
+            .send(|es| async move {
+                let cluster = es.cluster();
+                let mut request = cluster.health(ClusterHealthParts::None).timeout(timeout);
+                if let Some(x) = status {
+                    request = request.wait_for_status(x);
+                }
+                request.send().await
+            })
+            .await;
+        match result {
+            // a timed out wait is reported with 408, but the body is the usual health
+            Ok(response) if response.status_code().as_u16() == 408 => response
+                .json::<Value>()
+                .await
+                .map_err(|e| Error::from_client_error(&e)),
+            _ => parse_response(result).await,
+        }
+    }
+
+    /// Lists cluster-level changes (such as index creation or mapping
+    /// updates) that have not yet been executed by the master.
+    ///
+    pub async fn get_pending_tasks(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move { es.cluster().pending_tasks().send().await })
+                .await,
+        )
+        .await
+    }
+
+    /// Fetches the health of a single index.
+    ///
+    pub async fn get_index_health(&self, index: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.cluster()
+                    .health(ClusterHealthParts::Index(&[index]))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches cluster-wide statistics, reduced to the given paths only (or
+    /// in full, if none are given) to keep the response small.
+    ///
+    pub async fn get_cluster_stats(&self, filter_path: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let cluster = es.cluster();
+                let mut request = cluster.stats(ClusterStatsParts::None);
+                if !filter_path.is_empty() {
+                    request = request.filter_path(filter_path);
+                }
+                request.send().await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the coordination metadata from the cluster state, which
+    /// includes the committed voting configuration and any voting
+    /// configuration exclusions.
+    ///
+    pub async fn get_cluster_coordination(&self) -> Result<Value, Error> {
+        let state: Value = parse_response(
+            self.send(|es| async move {
+                es.cluster()
+                    .state(ClusterStateParts::Metric(&["metadata"]))
+                    .filter_path(&["metadata.cluster_coordination"])
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await?;
+        Ok(state["metadata"]["cluster_coordination"].to_owned())
+    }
+
+    /// Excludes nodes from the voting configuration, so that they can be
+    /// safely removed from the cluster. If no node names are given, all
+    /// existing exclusions are cleared instead.
+    ///
+    pub async fn set_voting_config_exclusions(&self, node_names: &[&str]) -> Result<(), Error> {
+        let result = self
+            .send(|es| async move {
+                let cluster = es.cluster();
+                if node_names.is_empty() {
+                    cluster.delete_voting_config_exclusions().send().await
+                } else {
+                    cluster
+                        .post_voting_config_exclusions()
+                        .node_names(&node_names.join(","))
+                        .send()
+                        .await
+                }
+            })
+            .await;
+        parse_empty_response(result).await
+    }
+
+    /// Flushes the given indexes (or all indexes, if none are given), so
+    /// that as little as possible needs to be recovered from the translog
+    /// after a node restart.
+    ///
+    pub async fn flush(&self, patterns: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let indices = es.indices();
+                let request = if patterns.is_empty() {
+                    indices.flush(IndicesFlushParts::None)
+                } else {
+                    indices.flush(IndicesFlushParts::Index(patterns))
+                };
+                request.send().await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Refreshes the given indexes, making recent changes visible to search.
+    ///
+    pub async fn refresh(&self, patterns: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .refresh(IndicesRefreshParts::Index(patterns))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Clears all caches of the given indexes.
+    ///
+    pub async fn clear_cache(&self, patterns: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .clear_cache(IndicesClearCacheParts::Index(patterns))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Starts a force merge of the given indexes as a background task,
+    /// returning the response containing the task ID.
+    ///
+    pub async fn start_forcemerge(
+        &self,
+        patterns: &[&str],
+        max_num_segments: Option<i64>,
+    ) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let indices = es.indices();
+                let mut request = indices
+                    .forcemerge(IndicesForcemergeParts::Index(patterns))
+                    .wait_for_completion(false);
+                if let Some(x) = max_num_segments {
+                    request = request.max_num_segments(x);
+                }
+                request.send().await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the status of a task, by ID.
+    ///
+    pub async fn get_task(&self, task_id: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(
+                |es| async move { es.tasks().get(TasksGetParts::TaskId(task_id)).send().await },
+            )
+            .await,
+        )
+        .await
+    }
+
+    /// Starts a snapshot of the indexes matching a pattern (or of all
+    /// indexes, if none is given), without waiting for it to complete.
+    ///
+    pub async fn create_snapshot(
+        &self,
+        repository: &str,
+        snapshot: &str,
+        indices: Option<&str>,
+    ) -> Result<Value, Error> {
+        let mut body = json!({});
+        if let Some(x) = indices {
+            body["indices"] = json!(x);
+        }
+        let body = &body;
+        parse_response(
+            self.send(|es| async move {
+                es.snapshot()
+                    .create(SnapshotCreateParts::RepositorySnapshot(
+                        repository, snapshot,
+                    ))
+                    .wait_for_completion(false)
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Lists all snapshots in a repository.
+    ///
+    pub async fn list_snapshots(&self, repository: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.snapshot()
+                    .get(SnapshotGetParts::RepositorySnapshot(repository, &["_all"]))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the detailed status of a snapshot, including shard and byte
+    /// counts, or of all snapshots currently running in a repository if
+    /// none is given.
+    ///
+    pub async fn get_snapshot_status(
+        &self,
+        repository: &str,
+        snapshot: Option<&str>,
+    ) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let snapshots = es.snapshot();
+                match snapshot {
+                    Some(x) => {
+                        snapshots
+                            .status(SnapshotStatusParts::RepositorySnapshot(repository, &[x]))
+                            .send()
+                            .await
+                    }
+                    None => {
+                        snapshots
+                            .status(SnapshotStatusParts::Repository(repository))
+                            .send()
+                            .await
+                    }
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Restores indexes from a snapshot, given a body that may select the
+    /// indexes and rename them. With `wait`, this returns only once the
+    /// restore is complete, with the number of shards restored.
+    ///
+    pub async fn restore_snapshot(
+        &self,
+        repository: &str,
+        snapshot: &str,
+        body: &Value,
+        wait: bool,
+    ) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.snapshot()
+                    .restore(SnapshotRestoreParts::RepositorySnapshot(
+                        repository, snapshot,
+                    ))
+                    .wait_for_completion(wait)
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn delete_snapshot(
+        &self,
+        repository: &str,
+        snapshot: &str,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.snapshot()
+                    .delete(SnapshotDeleteParts::RepositorySnapshot(
+                        repository,
+                        &[snapshot],
+                    ))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches snapshot lifecycle policies, with the outcome of their last
+    /// runs, by name (or all policies, if none are given).
+    ///
+    pub async fn get_slm_policies(&self, names: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let slm = es.slm();
+                if names.is_empty() {
+                    slm.get_lifecycle(SlmGetLifecycleParts::None).send().await
+                } else {
+                    slm.get_lifecycle(SlmGetLifecycleParts::PolicyId(names))
+                        .send()
+                        .await
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn put_slm_policy(&self, name: &str, body: &Value) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.slm()
+                    .put_lifecycle(SlmPutLifecycleParts::PolicyId(name))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn delete_slm_policy(&self, name: &str) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.slm()
+                    .delete_lifecycle(SlmDeleteLifecycleParts::PolicyId(name))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Runs a snapshot lifecycle policy immediately, returning the name of
+    /// the snapshot started.
+    ///
+    pub async fn execute_slm_policy(&self, name: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.slm()
+                    .execute_lifecycle(SlmExecuteLifecycleParts::PolicyId(name))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the operation mode of snapshot lifecycle management.
+    ///
+    pub async fn get_slm_status(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move { es.slm().get_status().send().await })
+                .await,
+        )
+        .await
+    }
+
+    pub async fn get_license(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move { es.license().get().send().await })
+                .await,
+        )
+        .await
+    }
+
+    /// Fetches the build, license and feature information of the cluster.
+    ///
+    pub async fn get_xpack_info(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move { es.xpack().info().send().await })
+                .await,
+        )
+        .await
+    }
+
+    /// Fetches the configuration of a transform (or of all transforms, if
+    /// none is given).
+    ///
+    pub async fn get_transforms(&self, id: Option<&str>) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let transform = es.transform();
+                match id {
+                    Some(x) => {
+                        transform
+                            .get_transform(TransformGetTransformParts::TransformId(x))
+                            .send()
+                            .await
+                    }
+                    None => {
+                        transform
+                            .get_transform(TransformGetTransformParts::None)
+                            .send()
+                            .await
+                    }
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the state, checkpoint and document counts of a transform,
+    /// or of all transforms given `_all`.
+    ///
+    pub async fn get_transform_stats(&self, id: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.transform()
+                    .get_transform_stats(TransformGetTransformStatsParts::TransformId(id))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn put_transform(&self, id: &str, body: &Value) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.transform()
+                    .put_transform(TransformPutTransformParts::TransformId(id))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn start_transform(&self, id: &str) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.transform()
+                    .start_transform(TransformStartTransformParts::TransformId(id))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Stops a transform, optionally waiting until it has stopped.
+    ///
+    pub async fn stop_transform(&self, id: &str, wait: bool) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.transform()
+                    .stop_transform(TransformStopTransformParts::TransformId(id))
+                    .wait_for_completion(wait)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Previews the documents that a transform would produce, given either
+    /// the ID of an existing transform or the body of a new one.
+    ///
+    pub async fn preview_transform(&self, id: Option<&str>, body: &Value) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let transform = es.transform();
+                match id {
+                    Some(x) => {
+                        transform
+                            .preview_transform(TransformPreviewTransformParts::TransformId(x))
+                            .send()
+                            .await
+                    }
+                    None => {
+                        transform
+                            .preview_transform(TransformPreviewTransformParts::None)
+                            .body(body)
+                            .send()
+                            .await
+                    }
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Lists watches, with their definitions and status. Watches are held in
+    /// an index, so this is capped at the default maximum result window.
+    ///
+    pub async fn query_watches(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.watcher()
+                    .query_watches()
+                    .body(json!({"size": 10000}))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn put_watch(&self, id: &str, body: &Value) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.watcher()
+                    .put_watch(WatcherPutWatchParts::Id(id))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Deletes a watch. A missing watch is reported as `found: false`
+    /// rather than as an error.
+    ///
+    pub async fn delete_watch(&self, id: &str) -> Result<Value, Error> {
+        parse_missing_response(
+            self.send(|es| async move {
+                es.watcher()
+                    .delete_watch(WatcherDeleteWatchParts::Id(id))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Runs a watch immediately, ignoring its schedule. In a dry run, every
+    /// action is simulated and the execution is not recorded in the watch
+    /// history.
+    ///
+    pub async fn execute_watch(&self, id: &str, dry_run: bool) -> Result<Value, Error> {
+        let body = if dry_run {
+            json!({"action_modes": {"_all": "simulate"}, "record_execution": false})
+        } else {
+            json!({"record_execution": true})
+        };
+        let body = &body;
+        parse_response(
+            self.send(|es| async move {
+                es.watcher()
+                    .execute_watch(WatcherExecuteWatchParts::Id(id))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Acknowledges the actions of a watch (or all of them, if none are
+    /// given), throttling them until the watch condition is next not met.
+    ///
+    pub async fn ack_watch(&self, id: &str, actions: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let watcher = es.watcher();
+                if actions.is_empty() {
+                    watcher
+                        .ack_watch(WatcherAckWatchParts::WatchId(id))
+                        .send()
+                        .await
+                } else {
+                    watcher
+                        .ack_watch(WatcherAckWatchParts::WatchIdActionId(id, actions))
+                        .send()
+                        .await
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the identity that the client is authenticated as.
+    ///
+    pub async fn authenticate(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move { es.security().authenticate().send().await })
+                .await,
+        )
+        .await
+    }
+
+    /// Fetches the cluster and index privileges of the authenticated user.
+    ///
+    pub async fn get_user_privileges(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move { es.security().get_user_privileges().send().await })
+                .await,
+        )
+        .await
+    }
+
+    /// Fetches users of the native realm by name, keyed by name (or all
+    /// users, if no names are given).
+    ///
+    pub async fn get_users(&self, names: &[&str]) -> Result<Value, Error> {
+        parse_missing_response(
+            self.send(|es| async move {
+                let security = es.security();
+                if names.is_empty() {
+                    security.get_user(SecurityGetUserParts::None).send().await
+                } else {
+                    security
+                        .get_user(SecurityGetUserParts::Username(names))
+                        .send()
+                        .await
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Creates or updates a user of the native realm. The body may include
+    /// a `password`, which is required for new users.
+    ///
+    pub async fn put_user(&self, name: &str, body: &Value) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.security()
+                    .put_user(SecurityPutUserParts::Username(name))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn delete_user(&self, name: &str) -> Result<Value, Error> {
+        parse_missing_response(
+            self.send(|es| async move {
+                es.security()
+                    .delete_user(SecurityDeleteUserParts::Username(name))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn change_password(&self, name: &str, password: &str) -> Result<(), Error> {
+        let body = json!({ "password": password });
+        let body = &body;
+        parse_empty_response(
+            self.send(|es| async move {
+                es.security()
+                    .change_password(SecurityChangePasswordParts::Username(name))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches roles by name, keyed by name (or all roles, including the
+    /// built-in ones, if no names are given).
+    ///
+    pub async fn get_roles(&self, names: &[&str]) -> Result<Value, Error> {
+        parse_missing_response(
+            self.send(|es| async move {
+                let security = es.security();
+                if names.is_empty() {
+                    security.get_role(SecurityGetRoleParts::None).send().await
+                } else {
+                    security
+                        .get_role(SecurityGetRoleParts::Name(names))
+                        .send()
+                        .await
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn put_role(&self, name: &str, body: &Value) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.security()
+                    .put_role(SecurityPutRoleParts::Name(name))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn delete_role(&self, name: &str) -> Result<Value, Error> {
+        parse_missing_response(
+            self.send(|es| async move {
+                es.security()
+                    .delete_role(SecurityDeleteRoleParts::Name(name))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Lists all stored scripts, keyed by ID. There is no API for this, so
+    /// scripts are read from the cluster state metadata.
+    ///
+    pub async fn list_scripts(&self) -> Result<Value, Error> {
+        let state: Value = parse_response(
+            self.send(|es| async move {
+                es.cluster()
+                    .state(ClusterStateParts::Metric(&["metadata"]))
+                    .filter_path(&["metadata.stored_scripts"])
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await?;
+        Ok(state["metadata"]["stored_scripts"].to_owned())
+    }
+
+    /// Fetches a stored script, returning `None` if it does not exist.
+    ///
+    pub async fn get_script(&self, id: &str) -> Result<Option<Value>, Error> {
+        let result = self
+            .send(|es| async move { es.get_script(GetScriptParts::Id(id)).send().await })
+            .await;
+        match result {
+            Ok(response) if response.status_code().as_u16() == 404 => Ok(None),
+            _ => {
+                let found: Value = parse_response(result).await?;
+                Ok(found.get("script").map(|x| x.to_owned()))
+            }
+        }
+    }
+
+    pub async fn put_script(
+        &self,
+        id: &str,
+        lang: &str,
+        source: &str,
+    ) -> Result<RawAcknowledged, Error> {
+        let body = json!({"script": {"lang": lang, "source": source}});
+        let body = &body;
+        parse_response(
+            self.send(|es| async move {
+                es.put_script(PutScriptParts::Id(id))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn delete_script(&self, id: &str) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move { es.delete_script(DeleteScriptParts::Id(id)).send().await })
+                .await,
+        )
+        .await
+    }
+
+    /// Fetches all enrich policies.
+    ///
+    pub async fn get_enrich_policies(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.enrich()
+                    .get_policy(EnrichGetPolicyParts::None)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn put_enrich_policy(
+        &self,
+        name: &str,
+        body: &Value,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.enrich()
+                    .put_policy(EnrichPutPolicyParts::Name(name))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn delete_enrich_policy(&self, name: &str) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.enrich()
+                    .delete_policy(EnrichDeletePolicyParts::Name(name))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Executes an enrich policy, creating a new enrich index from the
+    /// current contents of its source indexes. This waits for completion.
+    ///
+    pub async fn execute_enrich_policy(&self, name: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.enrich()
+                    .execute_policy(EnrichExecutePolicyParts::Name(name))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Lists the tasks currently running in the cluster, optionally only
+    /// those whose action matches one of the given patterns. Detailed
+    /// listings include each task's description and status.
+    ///
+    pub async fn list_tasks(&self, actions: &[&str], detailed: bool) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let tasks = es.tasks();
+                let mut request = tasks.list().detailed(detailed).group_by(GroupBy::None);
+                if !actions.is_empty() {
+                    request = request.actions(actions);
+                }
+                request.send().await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Requests cancellation of a task. Cancellation is cooperative, so the
+    /// task may take a while to stop after this returns.
+    ///
+    pub async fn cancel_task(&self, task_id: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.tasks()
+                    .cancel(TasksCancelParts::TaskId(task_id))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches persistent and transient cluster settings, optionally along
+    /// with the default values of all other settings in a `defaults` section.
+    ///
+    pub async fn get_cluster_settings(&self, include_defaults: bool) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.cluster()
+                    .get_settings()
+                    .flat_settings(true)
+                    .include_defaults(include_defaults)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Updates cluster settings, given a body with `persistent` and/or
+    /// `transient` sections.
+    ///
+    pub async fn put_cluster_settings(&self, body: &Value) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.cluster()
+                    .put_settings()
+                    .flat_settings(true)
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the configured remote clusters, keyed by alias, with their
+    /// connection mode and status.
+    ///
+    pub async fn get_remote_info(&self) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move { es.cluster().remote_info().send().await })
+                .await,
+        )
+        .await
+    }
+
+    /// Fetches the settings of one or more indexes, optionally including
+    /// default values for settings that have not been set explicitly, which
+    /// are returned in a separate `defaults` section for each index.
+    ///
+    pub async fn get_index_settings(
+        &self,
+        index: &str,
+        include_defaults: bool,
+    ) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .get_settings(IndicesGetSettingsParts::Index(&[index]))
+                    .flat_settings(true)
+                    .include_defaults(include_defaults)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Updates dynamic settings of one or more indexes.
+    ///
+    pub async fn put_index_settings(
+        &self,
+        index: &str,
+        settings: &Value,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .put_settings(IndicesPutSettingsParts::Index(&[index]))
+                    .body(settings)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Adds a block (such as `write` or `read_only`) to one or more indexes,
+    /// using the index block API.
+    ///
+    pub async fn add_index_block(
+        &self,
+        patterns: &[&str],
+        block: &str,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .add_block(IndicesAddBlockParts::IndexBlock(patterns, block))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Rolls an alias or data stream over to a new index, if any of the
+    /// given conditions are met (or unconditionally, if there are none). A
+    /// dry run reports which conditions were met without rolling over.
+    ///
+    pub async fn rollover(
+        &self,
+        target: &str,
+        conditions: &Value,
+        dry_run: bool,
+    ) -> Result<RawRollover, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .rollover(IndicesRolloverParts::Alias(target))
+                    .dry_run(dry_run)
+                    .body(json!({ "conditions": conditions }))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Creates a new index from an existing one using the shrink, split or
+    /// clone API. The source index must already be blocked for writes.
+    ///
+    pub async fn resize_index(
+        &self,
+        resize: Resize,
+        source: &str,
+        target: &str,
+        body: &Value,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let indices = es.indices();
+                match resize {
+                    Resize::Shrink => {
+                        indices
+                            .shrink(IndicesShrinkParts::IndexTarget(source, target))
+                            .body(body)
+                            .send()
+                            .await
+                    }
+                    Resize::Split => {
+                        indices
+                            .split(IndicesSplitParts::IndexTarget(source, target))
+                            .body(body)
+                            .send()
+                            .await
+                    }
+                    Resize::Clone => {
+                        indices
+                            .clone(IndicesCloneParts::IndexTarget(source, target))
+                            .body(body)
+                            .send()
+                            .await
+                    }
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn get_settings(&self, patterns: &[&str], names: &[&str]) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .get_settings(if names.is_empty() {
+                        IndicesGetSettingsParts::Index(patterns)
+                    } else {
+                        IndicesGetSettingsParts::IndexName(patterns, names)
+                    })
+                    .flat_settings(true)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn get_index_template(&self, name: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .get_index_template(IndicesGetIndexTemplateParts::Name(name))
+                    .flat_settings(true)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches the body of a composable index template, or `None` if no
+    /// template of that name exists.
+    ///
+    pub async fn find_index_template(&self, name: &str) -> Result<Option<Value>, Error> {
+        let result = self
+            .send(|es| async move {
+                es.indices()
+                    .get_index_template(IndicesGetIndexTemplateParts::Name(name))
+                    .flat_settings(true)
+                    .send()
+                    .await
+            })
+            .await;
+        match result {
+            Ok(response) if response.status_code().as_u16() == 404 => Ok(None),
+            _ => {
+                let found: Value = parse_response(result).await?;
+                Ok(found["index_templates"][0]
+                    .get("index_template")
+                    .map(|x| x.to_owned()))
+            }
+        }
+    }
+
+    /// Lists the index templates (or component templates) whose names match
+    /// a pattern, as `(name, body)` pairs. An empty list is returned if none
+    /// match.
+    ///
+    pub async fn list_templates(
+        &self,
+        component: bool,
+        pattern: &str,
+    ) -> Result<Vec<(String, Value)>, Error> {
+        let result = self
+            .send(|es| async move {
+                if component {
+                    es.cluster()
+                        .get_component_template(ClusterGetComponentTemplateParts::Name(&[pattern]))
+                        .send()
+                        .await
+                } else {
+                    es.indices()
+                        .get_index_template(IndicesGetIndexTemplateParts::Name(pattern))
+                        .flat_settings(true)
+                        .send()
+                        .await
+                }
+            })
+            .await;
+        if let Ok(response) = &result {
+            if response.status_code().as_u16() == 404 {
+                return Ok(vec![]);
+            }
+        }
+        let found: Value = parse_response(result).await?;
+        let (list_key, body_key) = if component {
+            ("component_templates", "component_template")
+        } else {
+            ("index_templates", "index_template")
+        };
+        Ok(found[list_key]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|x| {
+                (
+                    x["name"].as_str().unwrap_or("").to_string(),
+                    x[body_key].to_owned(),
+                )
+            })
+            .collect())
+    }
+
+    /// Creates or replaces a component template.
+    ///
+    pub async fn put_component_template(
+        &self,
+        name: &str,
+        body: &Value,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.cluster()
+                    .put_component_template(ClusterPutComponentTemplateParts::Name(name))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Deletes an index template (or component template).
+    ///
+    pub async fn delete_template(
+        &self,
+        component: bool,
+        name: &str,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                if component {
+                    es.cluster()
+                        .delete_component_template(ClusterDeleteComponentTemplateParts::Name(name))
+                        .send()
+                        .await
+                } else {
+                    es.indices()
+                        .delete_index_template(IndicesDeleteIndexTemplateParts::Name(name))
+                        .send()
+                        .await
+                }
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Creates or replaces a composable index template.
+    ///
+    pub async fn put_index_template(
+        &self,
+        name: &str,
+        body: &Value,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .put_index_template(IndicesPutIndexTemplateParts::Name(name))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Adds fields to the mapping of an existing index. Existing fields
+    /// cannot generally be changed, only added to.
+    ///
+    pub async fn put_mapping(
+        &self,
+        index: &str,
+        mappings: &Value,
+    ) -> Result<RawAcknowledged, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .put_mapping(IndicesPutMappingParts::Index(&[index]))
+                    .body(mappings)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn get_mapping(&self, index: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .get_mapping(IndicesGetMappingParts::Index(&[index]))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Fetches deprecation warnings relating to the next major version, for
+    /// the cluster, nodes and indexes or, given an index pattern, for the
+    /// matching indexes only.
+    ///
+    pub async fn get_deprecations(&self, index: Option<&str>) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                let parts = match index {
+                    Some(x) => MigrationDeprecationsParts::Index(x),
+                    None => MigrationDeprecationsParts::None,
+                };
+                es.migration().deprecations(parts).send().await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn get_field_usage_stats(&self, index: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .field_usage_stats(IndicesFieldUsageStatsParts::Index(index))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
     pub async fn create_index(
         &self,
         index: &str,
-        mappings: &[String],
+        body: &Value,
+        wait_for_active_shards: &Option<String>,
+        timeout: &Option<String>,
     ) -> Result<RawCreated, Error> {
-        let mut body = json!({
-            "mappings": {
-                "properties": {
+        let result = self
+            .send(|es| async move {
+                let indices = es.indices();
+                let mut request = indices.create(IndicesCreateParts::Index(index)).body(body);
+                if let Some(x) = wait_for_active_shards {
+                    request = request.wait_for_active_shards(x);
                 }
-            }
-        });
-        for mapping in mappings.iter() {
-            let bits: Vec<&str> = mapping.split(':').collect();
-            body["mappings"]["properties"][bits[0]] = json!({"type": bits[1]});
-        }
-        match self
-            .elasticsearch
-            .indices()
-            .create(IndicesCreateParts::Index(index))
-            .body(body)
-            .send()
-            .await
-        {
+                if let Some(x) = timeout {
+                    request = request.timeout(x);
+                }
+                request.send().await
+            })
+            .await;
+        match result {
             Ok(response) => match response.status_code().as_u16() {
                 200..=299 => Ok(match response.json::<RawCreated>().await {
                     Ok(raw) => raw,
@@ -327,10 +2098,12 @@ impl SimpleClient {
         index: &str,
     ) -> Result<RawDeleted, Box<dyn std::error::Error>> {
         match self
-            .elasticsearch
-            .indices()
-            .delete(IndicesDeleteParts::Index(&[index]))
-            .send()
+            .send(|es| async move {
+                es.indices()
+                    .delete(IndicesDeleteParts::Index(&[index]))
+                    .send()
+                    .await
+            })
             .await
         {
             Ok(response) => match response.status_code().as_u16() {
@@ -343,33 +2116,334 @@ impl SimpleClient {
         }
     }
 
+    /// Fetches the indexes to which an alias points, along with the alias
+    /// properties for each. An empty object is returned if the alias does
+    /// not exist.
+    ///
+    pub async fn get_alias(&self, alias: &str) -> Result<Value, Error> {
+        let result = self
+            .send(|es| async move {
+                es.indices()
+                    .get_alias(IndicesGetAliasParts::Name(&[alias]))
+                    .send()
+                    .await
+            })
+            .await;
+        match result {
+            Ok(response) if response.status_code().as_u16() == 404 => Ok(json!({})),
+            _ => parse_response(result).await,
+        }
+    }
+
+    /// Applies a list of alias actions atomically.
+    ///
+    pub async fn update_aliases(&self, actions: Value) -> Result<RawAcknowledged, Error> {
+        let actions = &actions;
+        parse_response(
+            self.send(|es| async move {
+                es.indices()
+                    .update_aliases()
+                    .body(json!({ "actions": actions }))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn count(&self, index: &str) -> Result<u64, Error> {
+        let counted: RawCount = parse_response(
+            self.send(|es| async move { es.count(CountParts::Index(&[index])).send().await })
+                .await,
+        )
+        .await?;
+        Ok(counted.count)
+    }
+
+    /// Loads documents from one or more CSV or Excel sources into an index,
+    /// sending them in batches using the bulk API. The `interrupted` flag is
+    /// checked between records; once set, no further records are read, but
+    /// the batch in progress is still sent before returning.
+    ///
     pub async fn load(
         &self,
         index: &str,
-        csv_filenames: &[String],
-    ) -> Result<RawBulkSummary, Box<dyn std::error::Error>> {
-        type Document = HashMap<String, Value>;
-        let mut documents: Vec<Document> = Vec::new();
-        for filename in csv_filenames.iter() {
-            let file = File::open(filename)?;
-            let mut reader = csv::Reader::from_reader(file);
-            for result in reader.deserialize() {
-                let document: Document = result?;
-                documents.push(document);
+        inputs: &[LoadSource],
+        options: &LoadOptions,
+        interrupted: &AtomicBool,
+    ) -> Result<LoadSummary, Box<dyn std::error::Error>> {
+        let mut summary = LoadSummary::default();
+        let mut throttle = Throttle::new(options.max_docs_per_sec, options.max_bytes_per_sec);
+        for input in inputs.iter() {
+            let (location, records) = self.read_records(input, &options.headers).await?;
+            let mut batch: Vec<BulkDocument> = Vec::new();
+            let mut line: u64 = 0;
+            for result in records {
+                if interrupted.load(Ordering::SeqCst) {
+                    summary.interrupted = true;
+                    break;
+                }
+                let mut document;
+                (document, line) = result?;
+                for geo_point in options.geo_points.iter() {
+                    geo_point.apply(&mut document, options.geo_points_as_strings);
+                }
+                let routing = options
+                    .routing_field
+                    .as_ref()
+                    .and_then(|field| document.get(field))
+                    .and_then(routing_value);
+                batch.push(BulkDocument {
+                    id: options.dedupe.as_ref().map(|dedupe| dedupe.hash(&document)),
+                    routing,
+                    create: options.dedupe.is_some(),
+                    source: document,
+                });
+                if batch.len() >= options.batch_size {
+                    self.send_batch(index, &batch, options, &mut throttle, &mut summary)
+                        .await?;
+                    summary.last_position = Some(format!("{location}:{line}"));
+                    batch.clear();
+                }
+            }
+            if !batch.is_empty() {
+                self.send_batch(index, &batch, options, &mut throttle, &mut summary)
+                    .await?;
+                summary.last_position = Some(format!("{location}:{line}"));
+            }
+            if summary.interrupted {
+                break;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Opens a load source, returning its location along with an iterator
+    /// over the documents it contains, each paired with its line (or row)
+    /// number.
+    ///
+    async fn read_records<'a>(
+        &self,
+        input: &'a LoadSource,
+        headers: &[String],
+    ) -> Result<(&'a str, Records), Box<dyn std::error::Error>> {
+        match input {
+            LoadSource::Csv(filename) => {
+                let mut reader = csv::Reader::from_reader(source::open(filename, headers).await?);
+                let columns = reader.headers()?.clone();
+                let records = reader.into_records().map(move |result| {
+                    let record = result?;
+                    let line = record.position().map_or(0, |position| position.line());
+                    Ok((record.deserialize(Some(&columns))?, line))
+                });
+                Ok((filename, Box::new(records)))
+            }
+            LoadSource::Xlsx { location, sheet } => {
+                let documents = source::read_xlsx(location, sheet.as_deref(), headers).await?;
+                // Data starts on the second row, below the header
+                Ok((location, Box::new(documents.into_iter().zip(2..).map(Ok))))
             }
         }
-        let mut body: Vec<BulkOperation<_>> = vec![];
-        for document in documents.iter() {
-            body.push(BulkOperation::index(json!(document)).into());
+    }
+
+    /// Sends a batch of documents, retrying any that are rejected by the
+    /// cluster due to back-pressure, with exponential backoff between
+    /// attempts.
+    ///
+    async fn send_batch(
+        &self,
+        index: &str,
+        batch: &[BulkDocument],
+        options: &LoadOptions,
+        throttle: &mut Throttle,
+        summary: &mut LoadSummary,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let size: usize = batch
+            .iter()
+            .map(|document| json!(document.source).to_string().len())
+            .sum();
+        throttle.wait(batch.len(), size).await;
+        let mut pending: Vec<BulkDocument> = batch.to_vec();
+        let mut attempt: u32 = 0;
+        loop {
+            let can_retry = attempt < options.retries;
+            match self.bulk_index(index, &pending).await {
+                Ok(response) => {
+                    // Items are returned in the same order as the documents
+                    // were sent, so pair them up to find those to resend
+                    let mut rejected: Vec<BulkDocument> = vec![];
+                    for (document, item) in pending.into_iter().zip(response.items) {
+                        if can_retry && item.values().any(|action| action.is_rejection()) {
+                            rejected.push(document);
+                        } else {
+                            summary.items.push(item);
+                        }
+                    }
+                    if rejected.is_empty() {
+                        return Ok(());
+                    }
+                    pending = rejected;
+                }
+                Err(e) if can_retry && e.is_rejection() => {}
+                Err(e) => return Err(Box::from(e)),
+            }
+            let delay = backoff_delay(options.retry_backoff, attempt);
+            eprintln!(
+                "Cluster rejected {} documents, retrying in {:.1?}",
+                pending.len(),
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
-        let response = self
-            .elasticsearch
-            .bulk(BulkParts::Index(index))
-            .body(body)
-            .refresh(Refresh::WaitFor)
-            .send()
+    }
+
+    pub async fn bulk_index(
+        &self,
+        index: &str,
+        documents: &[BulkDocument],
+    ) -> Result<RawBulkSummary, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.bulk(BulkParts::Index(index))
+                    .body(bulk_operations(documents))
+                    .refresh(Refresh::WaitFor)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Copies documents matching an optional query from an index into
+    /// another index, possibly on a different cluster. Documents are read
+    /// using the scroll API and written in batches through the bulk API, all
+    /// via this client rather than using server-side reindexing.
+    ///
+    pub async fn copy(
+        &self,
+        source_index: &str,
+        destination: &SimpleClient,
+        destination_index: &str,
+        query: &Option<String>,
+        batch_size: usize,
+        interrupted: &AtomicBool,
+    ) -> Result<LoadSummary, Box<dyn std::error::Error>> {
+        let mut summary = LoadSummary::default();
+        let mut result = self
+            .scroll_start(source_index, query, batch_size, None)
             .await?;
-        Ok(response.json::<RawBulkSummary>().await?)
+        let mut copied: usize = 0;
+        loop {
+            if result.hits.hits.is_empty() {
+                break;
+            }
+            let batch: Vec<BulkDocument> = result
+                .hits
+                .hits
+                .into_iter()
+                .map(|hit| BulkDocument {
+                    id: Some(hit._id),
+                    routing: hit.other.get("_routing").and_then(routing_value),
+                    create: false,
+                    source: hit._source,
+                })
+                .collect();
+            let response = destination.bulk_index(destination_index, &batch).await?;
+            copied += batch.len();
+            summary.items.extend(response.items);
+            summary.last_position = Some(format!("{source_index}:{copied}"));
+            if interrupted.load(Ordering::SeqCst) {
+                summary.interrupted = true;
+                break;
+            }
+            result = match &result._scroll_id {
+                Some(scroll_id) => self.scroll_next(scroll_id).await?,
+                None => break,
+            };
+        }
+        if let Some(scroll_id) = &result._scroll_id {
+            self.clear_scroll(scroll_id).await?;
+        }
+        Ok(summary)
+    }
+
+    /// Runs a search with a full request body, returning the raw response.
+    /// This allows for aggregations and other features not otherwise
+    /// exposed by this client.
+    ///
+    pub async fn search_body(&self, index: &str, body: &Value) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.search(SearchParts::Index(&[index]))
+                    .body(body)
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    /// Starts a scrolling search, returning the first page of hits. If a
+    /// slice is given as an `(id, max)` pair, only that slice of the results
+    /// is scrolled, allowing several scrolls to run in parallel.
+    ///
+    pub async fn scroll_start(
+        &self,
+        index: &str,
+        query: &Option<String>,
+        size: usize,
+        slice: Option<(usize, usize)>,
+    ) -> Result<RawSearchResult, Error> {
+        let target = &[index];
+        let mut body = json!({});
+        if let Some((id, max)) = slice {
+            body["slice"] = json!({"id": id, "max": max});
+        }
+        let body = &body;
+        parse_response(
+            self.send(|es| async move {
+                let mut request = es
+                    .search(SearchParts::Index(target))
+                    .scroll(SCROLL_KEEP_ALIVE)
+                    .size(size as i64);
+                if let Some(x) = query {
+                    request = request.q(x);
+                }
+                request.body(body).send().await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn scroll_next(&self, scroll_id: &str) -> Result<RawSearchResult, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.scroll(ScrollParts::None)
+                    .body(json!({"scroll": SCROLL_KEEP_ALIVE, "scroll_id": scroll_id}))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
+    }
+
+    pub async fn clear_scroll(&self, scroll_id: &str) -> Result<Value, Error> {
+        parse_response(
+            self.send(|es| async move {
+                es.clear_scroll(ClearScrollParts::None)
+                    .body(json!({"scroll_id": [scroll_id]}))
+                    .send()
+                    .await
+            })
+            .await,
+        )
+        .await
     }
 
     pub async fn search(
@@ -378,23 +2452,37 @@ impl SimpleClient {
         query: &Option<String>,
         order_by: &Option<String>,
         limit: &Option<u16>,
+        routing: &Option<String>,
     ) -> Result<RawSearchResult, Error> {
         let target = &[index];
-        let mut request = self.elasticsearch.search(SearchParts::Index(target));
-        let mut order_by_pairs = Vec::new();
+        let routing_values: Vec<&str> = routing.iter().map(|x| x.as_str()).collect();
+        let routing_values = &routing_values;
+        let order_by_pairs: Vec<&str> = order_by.iter().map(|x| x.as_str()).collect();
+        let order_by_pairs = &order_by_pairs;
         let mut body = json!({});
-        match query {
-            Some(x) => request = request.q(x),
-            _ => body["query"] = json!({"match_all": {}}),
-        }
-        if let Some(x) = order_by {
-            order_by_pairs.push(x.as_str());
-            request = request.sort(order_by_pairs.as_slice())
+        if query.is_none() {
+            body["query"] = json!({"match_all": {}});
         }
         if let Some(x) = limit {
             body["size"] = json!(x);
         }
-        match request.body(body).send().await {
+        let body = &body;
+        let result = self
+            .send(|es| async move {
+                let mut request = es.search(SearchParts::Index(target));
+                if !routing_values.is_empty() {
+                    request = request.routing(routing_values);
+                }
+                if let Some(x) = query {
+                    request = request.q(x);
+                }
+                if !order_by_pairs.is_empty() {
+                    request = request.sort(order_by_pairs)
+                }
+                request.body(body).send().await
+            })
+            .await;
+        match result {
             Ok(response) => match response.status_code().as_u16() {
                 200..=299 => Ok(match response.json::<RawSearchResult>().await {
                     Ok(data) => data,
@@ -412,7 +2500,213 @@ impl SimpleClient {
     }
 }
 
+/// The ways in which an index can be copied into a new index with a
+/// different number of primary shards (or, for a clone, the same number).
+///
+#[derive(Clone, Copy, Debug)]
+pub enum Resize {
+    Shrink,
+    Split,
+    Clone,
+}
+
+impl std::fmt::Display for Resize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Resize::Shrink => "shrink",
+            Resize::Split => "split",
+            Resize::Clone => "clone",
+        })
+    }
+}
+
+/// Builds the bulk operations with which to index a batch of documents.
+/// These are rebuilt for each attempt, since they are consumed on sending.
+///
+fn bulk_operations(documents: &[BulkDocument]) -> Vec<BulkOperation<Value>> {
+    let mut body: Vec<BulkOperation<Value>> = vec![];
+    for document in documents.iter() {
+        let source = json!(document.source);
+        match (&document.id, document.create) {
+            (Some(id), true) => {
+                let mut operation = BulkOperation::create(source).id(id);
+                if let Some(routing) = &document.routing {
+                    operation = operation.routing(routing);
+                }
+                body.push(operation.into());
+            }
+            _ => {
+                let mut operation = BulkOperation::index(source);
+                if let Some(id) = &document.id {
+                    operation = operation.id(id);
+                }
+                if let Some(routing) = &document.routing {
+                    operation = operation.routing(routing);
+                }
+                body.push(operation.into());
+            }
+        }
+    }
+    body
+}
+
+fn connect(url: Url, auth: Credentials, tls: &Tls) -> Result<Elasticsearch, Error> {
+    let mut builder = TransportBuilder::new(SingleNodeConnectionPool::new(url)).auth(auth);
+    if tls.insecure {
+        builder = builder.cert_validation(CertificateValidation::None);
+    } else if let Some(pem) = &tls.ca_cert {
+        let certificate = Certificate::from_pem(pem).map_err(|e| {
+            Error::new(
+                ErrorType::ConfigurationError,
+                format!("failed to parse CA certificate ({e})"),
+            )
+        })?;
+        builder = builder.cert_validation(CertificateValidation::Full(certificate));
+    }
+    match builder.build() {
+        Ok(transport) => Ok(Elasticsearch::new(transport)),
+        Err(e) => Err(Error::new(
+            ErrorType::ConfigurationError,
+            format!("failed to create transport ({e})"),
+        )),
+    }
+}
+
+/// Runs an auth command and reads credentials from its standard output.
+/// Output beginning `Bearer ` is taken as a bearer token, and anything else
+/// as an encoded API key.
+///
+async fn run_auth_command(command: &str) -> Result<Credentials, Error> {
+    let output = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(AUTH_COMMAND_TIMEOUT, output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            return Err(Error::new(
+                ErrorType::ConfigurationError,
+                format!("failed to run auth command ({e})"),
+            ))
+        }
+        Err(_) => {
+            return Err(Error::new(
+                ErrorType::ConfigurationError,
+                format!(
+                    "auth command printed no credentials within {}s",
+                    AUTH_COMMAND_TIMEOUT.as_secs()
+                ),
+            ))
+        }
+    };
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorType::ConfigurationError,
+            format!("auth command failed ({})", output.status),
+        ));
+    }
+    let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if secret.is_empty() {
+        return Err(Error::new(
+            ErrorType::ConfigurationError,
+            "auth command printed no credentials".to_string(),
+        ));
+    }
+    Ok(match secret.strip_prefix("Bearer ") {
+        Some(token) => Credentials::Bearer(token.trim().to_string()),
+        None => Credentials::EncodedApiKey(secret),
+    })
+}
+
+/// Runs an auth command from code that is not async, such as when a client
+/// is created, without stalling other tasks on the runtime.
+///
+fn run_auth_command_blocking(command: &str) -> Result<Credentials, Error> {
+    tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(run_auth_command(command))
+    })
+}
+
+/// Decodes the body of a response into the given type if a 2xx status code
+/// was received, or into an [`Error`] otherwise.
+///
+async fn parse_response<T: DeserializeOwned>(
+    result: Result<Response, elasticsearch::Error>,
+) -> Result<T, Error> {
+    match result {
+        Ok(response) => match response.status_code().as_u16() {
+            200..=299 => {
+                let started = Instant::now();
+                let decoded = read_json::<T>(response).await;
+                observe::report_body(started);
+                let drift: Vec<String> = match DRIFT.lock() {
+                    Ok(mut it) => it.drain(..).collect(),
+                    Err(_) => vec![],
+                };
+                match decoded {
+                    Ok(_) if STRICT.load(Ordering::Relaxed) && !drift.is_empty() => {
+                        Err(Error::new(
+                            ErrorType::ClientError,
+                            format!(
+                                "response did not match the expected schema ({})",
+                                drift.join("; ")
+                            ),
+                        ))
+                    }
+                    Ok(data) => Ok(data),
+                    Err(e) => Err(Error::from_client_error(&e)), // failed to decode response body
+                }
+            }
+            _ => match read_json::<RawError>(response).await {
+                Ok(data) => Err(Error::from_server_error(&data)),
+                Err(e) => Err(Error::from_client_error(&e)), // failed to decode error response body
+            },
+        },
+        Err(e) => Err(Error::from_client_error(&e)), // failed to send
+    }
+}
+
+/// Parses a response from an API that reports a missing resource with a
+/// 404 status but a regular body (such as `{"found": false}`, or an empty
+/// object) rather than an error.
+///
+async fn parse_missing_response(
+    result: Result<Response, elasticsearch::Error>,
+) -> Result<Value, Error> {
+    match result {
+        Ok(response) if response.status_code().as_u16() == 404 => Ok(read_json::<Value>(response)
+            .await
+            .unwrap_or_else(|_| json!({}))),
+        _ => parse_response(result).await,
+    }
+}
+
+/// Decodes a response body as JSON, first tracing it if bodies are being
+/// traced.
+///
+async fn read_json<T: DeserializeOwned>(response: Response) -> Result<T, elasticsearch::Error> {
+    if !observe::tracing_bodies() {
+        return response.json::<T>().await;
+    }
+    let text = response.text().await?;
+    observe::trace_body(&text);
+    Ok(serde_json::from_str::<T>(&text)?)
+}
+
+/// Checks the status of a response that carries no body on success.
+///
+async fn parse_empty_response(result: Result<Response, elasticsearch::Error>) -> Result<(), Error> {
+    match &result {
+        Ok(response) if response.status_code().is_success() => Ok(()),
+        _ => parse_response::<Value>(result).await.map(|_| ()),
+    }
+}
+
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ErrorType {
     ConfigurationError,
     ClientError,
@@ -422,6 +2716,7 @@ pub enum ErrorType {
 #[derive(Debug)]
 pub struct Error {
     subtype: ErrorType,
+    type_code: Option<String>,
     description: String,
 }
 
@@ -429,13 +2724,40 @@ impl Error {
     pub fn new(subtype: ErrorType, description: String) -> Self {
         Error {
             subtype,
+            type_code: None,
             description,
         }
     }
 
+    pub fn error_type(&self) -> &ErrorType {
+        &self.subtype
+    }
+
+    /// Checks whether this error signals back-pressure from the cluster
+    /// (`429 Too Many Requests` or `503 Service Unavailable`).
+    ///
+    pub fn is_rejection(&self) -> bool {
+        matches!(self.subtype, ErrorType::ServerError(429 | 503))
+    }
+
+    /// Checks whether this error signals that the requested resource, such
+    /// as an index, does not exist.
+    ///
+    pub fn is_not_found(&self) -> bool {
+        matches!(self.subtype, ErrorType::ServerError(404))
+    }
+
+    /// Checks whether this error signals that the resource being created
+    /// already exists (`resource_already_exists_exception`).
+    ///
+    pub fn is_already_exists(&self) -> bool {
+        self.type_code.as_deref() == Some("resource_already_exists_exception")
+    }
+
     pub fn from_client_error(error: &elasticsearch::Error) -> Self {
         Error {
             subtype: ErrorType::ClientError,
+            type_code: None,
             description: error.to_string(),
         }
     }
@@ -453,6 +2775,7 @@ impl Error {
         };
         Error {
             subtype: ErrorType::ServerError(raw_error.status),
+            type_code: Some(detail.type_code.clone()),
             description: detail
                 .reason
                 .as_ref()
@@ -495,7 +2818,7 @@ impl std::fmt::Display for RawError {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct RawInfo {
     pub name: String,
     pub cluster_name: String,
@@ -504,7 +2827,7 @@ pub struct RawInfo {
     pub tagline: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct RawInfoVersion {
     pub number: String,
     pub build_flavor: String,
@@ -520,6 +2843,8 @@ pub struct RawInfoVersion {
 #[derive(Deserialize)]
 pub struct RawCreated {
     pub acknowledged: bool,
+    #[serde(default)]
+    pub shards_acknowledged: bool,
     pub index: String,
 }
 
@@ -528,36 +2853,255 @@ pub struct RawDeleted {
     pub acknowledged: bool,
 }
 
+#[derive(Deserialize)]
+pub struct RawAcknowledged {
+    pub acknowledged: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RawRollover {
+    pub old_index: String,
+    pub new_index: String,
+    pub rolled_over: bool,
+    pub dry_run: bool,
+    #[serde(default)]
+    pub conditions: HashMap<String, bool>,
+}
+
+#[derive(Deserialize)]
+pub struct RawCount {
+    pub count: u64,
+}
+
 #[derive(Deserialize)]
 pub struct RawBulkSummary {
     pub items: Vec<HashMap<String, RawBulkSummaryAction>>,
 }
 
+/// A source of documents to be read by [`SimpleClient::load`].
+pub enum LoadSource {
+    Csv(String),
+    Xlsx {
+        location: String,
+        sheet: Option<String>,
+    },
+}
+
+type Records = Box<dyn Iterator<Item = Result<(Document, u64), Box<dyn std::error::Error>>>>;
+
+/// Options controlling how documents are read and sent by [`SimpleClient::load`].
+pub struct LoadOptions {
+    pub batch_size: usize,
+    pub headers: Vec<String>,
+    pub max_docs_per_sec: Option<f64>,
+    pub max_bytes_per_sec: Option<f64>,
+    pub retries: u32,
+    pub retry_backoff: f64,
+    pub geo_points: Vec<GeoPoint>,
+    pub geo_points_as_strings: bool,
+    pub routing_field: Option<String>,
+    pub dedupe: Option<Dedupe>,
+}
+
+/// Selects the fields hashed to generate a stable `_id` for each document,
+/// so that documents already loaded are skipped rather than duplicated.
+pub enum Dedupe {
+    Fields(Vec<String>),
+    AllFields,
+}
+
+impl Dedupe {
+    /// Computes a SHA-256 hash of the selected field names and values,
+    /// returned in hexadecimal form.
+    ///
+    pub fn hash(&self, document: &Document) -> String {
+        let pairs: Vec<(&String, &Value)> = match self {
+            Dedupe::Fields(names) => names
+                .iter()
+                .map(|name| (name, document.get(name).unwrap_or(&Value::Null)))
+                .collect(),
+            Dedupe::AllFields => {
+                let mut pairs: Vec<(&String, &Value)> = document.iter().collect();
+                pairs.sort_by_key(|(name, _)| *name);
+                pairs
+            }
+        };
+        format!("{:x}", Sha256::digest(json!(pairs).to_string()))
+    }
+}
+
+/// A `geo_point` field to be built from a pair of latitude and longitude
+/// columns, which are removed from the document in the process.
+#[derive(Clone, Debug)]
+pub struct GeoPoint {
+    pub field: String,
+    pub lat: String,
+    pub lon: String,
+}
+
+impl GeoPoint {
+    /// Replaces the latitude and longitude values in a document with a
+    /// single geo point, either as a `{"lat": ..., "lon": ...}` object or as
+    /// a `"lat,lon"` string. If either value is missing or not a number, no
+    /// geo point is added.
+    ///
+    pub fn apply(&self, document: &mut Document, as_string: bool) {
+        let lat = document.remove(&self.lat).and_then(|x| coordinate(&x));
+        let lon = document.remove(&self.lon).and_then(|x| coordinate(&x));
+        if let (Some(lat), Some(lon)) = (lat, lon) {
+            let point = if as_string {
+                json!(format!("{lat},{lon}"))
+            } else {
+                json!({"lat": lat, "lon": lon})
+            };
+            document.insert(self.field.to_owned(), point);
+        }
+    }
+}
+
+fn coordinate(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(x) => x.as_f64(),
+        Value::String(x) => x.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+/// A document to be sent in a bulk request, along with optional metadata.
+#[derive(Clone)]
+pub struct BulkDocument {
+    pub id: Option<String>,
+    pub routing: Option<String>,
+    pub create: bool,
+    pub source: Document,
+}
+
+/// Converts a field value into a routing value, if it is a non-empty string
+/// or a number.
+///
+fn routing_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(x) if !x.is_empty() => Some(x.to_owned()),
+        Value::Number(x) => Some(x.to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+pub struct LoadSummary {
+    pub items: Vec<HashMap<String, RawBulkSummaryAction>>,
+    pub interrupted: bool,
+    pub last_position: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct RawBulkSummaryAction {
+    #[serde(default = "missing_index")]
     pub _index: String,
-    pub _id: String,
-    pub _version: i32,
-    pub result: String,
-    pub _seq_no: i32,
+    pub _id: Option<String>,
+    #[serde(default, deserialize_with = "lenient_integer")]
+    pub _version: Option<i64>,
+    pub result: Option<String>,
+    #[serde(default, deserialize_with = "lenient_integer")]
+    pub _seq_no: Option<i64>,
+    pub status: u16,
+    pub error: Option<RawErrorDetail>,
+}
+
+impl RawBulkSummaryAction {
+    /// Checks whether this action failed because the cluster was too busy
+    /// to accept it, and so could succeed if retried later.
+    ///
+    pub fn is_rejection(&self) -> bool {
+        self.status == 429
+            || self
+                .error
+                .as_ref()
+                .is_some_and(|e| e.type_code == "es_rejected_execution_exception")
+    }
 }
 
 #[derive(Deserialize)]
 pub struct RawSearchResult {
+    pub _scroll_id: Option<String>,
+    #[serde(default = "missing_hits")]
     pub hits: RawSearchResultHits,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub struct RawSearchResultHits {
+    #[serde(default = "missing_hits_list")]
     pub hits: Vec<RawSearchResultHitsHit>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct RawSearchResultHitsHit {
+    #[serde(default = "missing_index")]
     pub _index: String,
+    #[serde(default = "missing_id")]
     pub _id: String,
     pub _score: Option<f64>,
+    #[serde(default = "missing_source")]
     pub _source: HashMap<String, Value>,
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+// The functions below stand in for missing fields, noting the drift so that
+// it can be reported in strict mode.
+
+fn note_drift(description: String) {
+    if let Ok(mut drift) = DRIFT.lock() {
+        drift.push(description);
+    }
+}
+
+fn missing_index() -> String {
+    note_drift("missing _index".to_string());
+    String::new()
+}
+
+fn missing_id() -> String {
+    note_drift("missing _id".to_string());
+    String::new()
+}
+
+fn missing_source() -> HashMap<String, Value> {
+    note_drift("missing _source".to_string());
+    HashMap::new()
+}
+
+fn missing_hits() -> RawSearchResultHits {
+    note_drift("missing hits".to_string());
+    RawSearchResultHits::default()
+}
+
+fn missing_hits_list() -> Vec<RawSearchResultHitsHit> {
+    note_drift("missing hits.hits".to_string());
+    vec![]
+}
+
+/// Deserializes an optional integer which may also be given as a numeric
+/// string, as some server versions do.
+///
+fn lenient_integer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Number(number)) => match number.as_i64() {
+            Some(x) => Ok(Some(x)),
+            None => Err(D::Error::custom(format!("{number} is not an integer"))),
+        },
+        Some(Value::String(string)) => match string.parse::<i64>() {
+            Ok(x) => {
+                note_drift(format!("integer given as string \"{string}\""));
+                Ok(Some(x))
+            }
+            Err(_) => Err(D::Error::custom(format!("\"{string}\" is not an integer"))),
+        },
+        Some(other) => Err(D::Error::custom(format!(
+            "expected an integer, found {other}"
+        ))),
+    }
 }
 
 pub struct IndexDetail {
@@ -569,4 +3113,74 @@ pub struct IndexDetail {
     pub docs_deleted: Option<u64>,
     pub store_size: Option<u64>,
     pub dataset_size: Option<u64>,
+    pub primaries: Option<u32>,
+    pub replicas: Option<u32>,
+    pub creation_date: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RawIndicesStats {
+    #[serde(default)]
+    pub indices: HashMap<String, RawIndexStats>,
+}
+
+#[derive(Deserialize)]
+pub struct RawIndexStats {
+    pub total: RawIndexStatsGroup,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawIndexStatsGroup {
+    pub docs: RawDocsStats,
+    pub store: RawStoreStats,
+    pub indexing: RawIndexingStats,
+    pub search: RawSearchStats,
+    pub merges: RawMergesStats,
+    pub segments: RawSegmentsStats,
+    pub fielddata: RawMemoryStats,
+    pub query_cache: RawMemoryStats,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawDocsStats {
+    pub count: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawStoreStats {
+    pub size_in_bytes: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawMergesStats {
+    pub current: u64,
+    pub total: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawSegmentsStats {
+    pub count: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawMemoryStats {
+    pub memory_size_in_bytes: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawIndexingStats {
+    pub index_total: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RawSearchStats {
+    pub query_total: u64,
 }
@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{Display, Formatter},
+    fs::read_to_string,
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use elasticsearch::http::Url;
+
+use crate::{
+    output::OutputFormat,
+    profile::{self, ConfigFile},
+};
+
+/// Environment variables understood by escli.
+///
+pub const KNOWN_VARS: &[&str] = &[
+    "ESCLI_URL",
+    "ESCLI_API_KEY",
+    "ESCLI_USER",
+    "ESCLI_PASSWORD",
+    "ESCLI_AUTH_CMD",
+    "ESCLI_FALLBACK_URL",
+    "ESCLI_PRODUCTION",
+    "ESCLI_PROFILE",
+];
+
+/// How long to wait for a TCP connection when checking that a URL is
+/// reachable.
+///
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in the configuration, located by its source (a file or
+/// the environment) and, for files, a line number.
+///
+pub struct Problem {
+    pub severity: Severity,
+    pub source: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    fn error(source: &str, line: Option<usize>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            source: source.to_string(),
+            line,
+            message,
+        }
+    }
+
+    fn warning(source: &str, line: Option<usize>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            source: source.to_string(),
+            line,
+            message,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "{}:{line}: {severity}: {}", self.source, self.message),
+            None => write!(f, "{}: {severity}: {}", self.source, self.message),
+        }
+    }
+}
+
+/// Checks the `ESCLI_*` environment variables for unknown names, malformed
+/// values and conflicting authentication settings.
+///
+pub fn check_env_vars() -> Vec<Problem> {
+    const SOURCE: &str = "environment";
+    let vars: HashMap<String, String> = env::vars()
+        .filter(|(name, _)| name.starts_with("ESCLI_"))
+        .collect();
+    let mut problems = vec![];
+    let mut names: Vec<&String> = vars.keys().collect();
+    names.sort();
+    for name in names {
+        if !KNOWN_VARS.contains(&name.as_str()) {
+            let message = match closest(name, KNOWN_VARS) {
+                Some(known) => format!("unknown variable {name} (did you mean {known}?)"),
+                None => format!("unknown variable {name}"),
+            };
+            problems.push(Problem::warning(SOURCE, None, message));
+        }
+    }
+    for name in ["ESCLI_URL", "ESCLI_FALLBACK_URL"] {
+        if let Some(value) = vars.get(name) {
+            if let Err(e) = parse_url(value) {
+                problems.push(Problem::error(SOURCE, None, format!("{name}: {e}")));
+            }
+        }
+    }
+    let set = |name: &str| vars.contains_key(name);
+    if set("ESCLI_AUTH_CMD") && (set("ESCLI_API_KEY") || set("ESCLI_PASSWORD")) {
+        problems.push(Problem::warning(
+            SOURCE,
+            None,
+            "ESCLI_AUTH_CMD is set, so ESCLI_API_KEY and ESCLI_PASSWORD are ignored".to_string(),
+        ));
+    } else if set("ESCLI_API_KEY") && set("ESCLI_PASSWORD") {
+        problems.push(Problem::warning(
+            SOURCE,
+            None,
+            "both ESCLI_API_KEY and ESCLI_PASSWORD are set, so ESCLI_PASSWORD is ignored"
+                .to_string(),
+        ));
+    }
+    if set("ESCLI_USER") && !set("ESCLI_PASSWORD") {
+        problems.push(Problem::error(
+            SOURCE,
+            None,
+            "ESCLI_USER is set without ESCLI_PASSWORD".to_string(),
+        ));
+    }
+    if set("ESCLI_URL") && !(set("ESCLI_AUTH_CMD") || set("ESCLI_API_KEY") || set("ESCLI_PASSWORD"))
+    {
+        problems.push(Problem::error(
+            SOURCE,
+            None,
+            "ESCLI_URL is set without ESCLI_AUTH_CMD, ESCLI_API_KEY or ESCLI_PASSWORD".to_string(),
+        ));
+    }
+    if let Some(value) = vars.get("ESCLI_PRODUCTION") {
+        if !matches!(
+            value.to_lowercase().as_str(),
+            "true" | "yes" | "1" | "false" | "no" | "0" | ""
+        ) {
+            problems.push(Problem::warning(
+                SOURCE,
+                None,
+                format!("ESCLI_PRODUCTION={value} is not recognised, so is treated as false"),
+            ));
+        }
+    }
+    if let Some(name) = vars.get("ESCLI_PROFILE").filter(|x| !x.is_empty()) {
+        // A file that cannot be read is reported by `check_config_file`
+        if ConfigFile::load().is_ok_and(|x| !x.profiles.contains_key(name)) {
+            problems.push(Problem::error(
+                SOURCE,
+                None,
+                format!("ESCLI_PROFILE names profile {name}, which is not defined"),
+            ));
+        }
+    }
+    problems
+}
+
+/// Checks a _start-local_ `.env` file line by line, for malformed lines,
+/// duplicate keys and missing or invalid values.
+///
+pub fn check_start_local(path: &Path) -> Vec<Problem> {
+    let source = path.display().to_string();
+    let text = match read_to_string(path) {
+        Ok(it) => it,
+        Err(e) => return vec![Problem::error(&source, None, e.to_string())],
+    };
+    let mut problems = vec![];
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let (name, value) = match trimmed.split_once('=') {
+            Some(it) => it,
+            None => {
+                problems.push(Problem::error(
+                    &source,
+                    Some(number),
+                    format!("expected NAME=VALUE, found '{trimmed}'"),
+                ));
+                continue;
+            }
+        };
+        if let Some(first) = seen.insert(name, number) {
+            problems.push(Problem::warning(
+                &source,
+                Some(number),
+                format!("{name} is already set on line {first}, and this value is used"),
+            ));
+        }
+        if name == "ES_LOCAL_PORT" && value.parse::<u16>().is_err() {
+            problems.push(Problem::error(
+                &source,
+                Some(number),
+                format!("ES_LOCAL_PORT must be a port number, found '{value}'"),
+            ));
+        }
+    }
+    if !seen.contains_key("ES_LOCAL_API_KEY") {
+        problems.push(Problem::error(
+            &source,
+            None,
+            "ES_LOCAL_API_KEY is not set".to_string(),
+        ));
+    }
+    problems
+}
+
+/// Checks the configuration file for syntax errors, unknown keys and, in
+/// each profile, invalid URLs, missing credentials, missing CA certificate
+/// files and unknown output formats.
+///
+pub fn check_config_file(path: &Path) -> Vec<Problem> {
+    let source = path.display().to_string();
+    let text = match read_to_string(path) {
+        Ok(it) => it,
+        Err(e) => return vec![Problem::error(&source, None, e.to_string())],
+    };
+    let config: ConfigFile = match toml::from_str(&text) {
+        Ok(it) => it,
+        Err(e) => {
+            let line = e.span().map(|x| text[..x.start].matches('\n').count() + 1);
+            return vec![Problem::error(&source, line, e.message().to_string())];
+        }
+    };
+    let mut problems = vec![];
+    if let Some(default) = &config.default {
+        if !config.profiles.contains_key(default) {
+            problems.push(Problem::error(
+                &source,
+                None,
+                format!("default profile {default} is not defined"),
+            ));
+        }
+    }
+    for (name, profile) in config.profiles.iter() {
+        let line = header_line(&text, name);
+        let mut error = |message: String| problems.push(Problem::error(&source, line, message));
+        if let Err(e) = parse_url(&profile.url) {
+            error(format!("profile {name}: url: {e}"));
+        }
+        if let Some(Err(e)) = profile.fallback_url.as_deref().map(parse_url) {
+            error(format!("profile {name}: fallback_url: {e}"));
+        }
+        if profile.auth_cmd.is_none() && profile.api_key.is_none() && profile.password.is_none() {
+            error(format!(
+                "profile {name} has no auth_cmd, api_key or password"
+            ));
+        }
+        if profile.user.is_some() && profile.password.is_none() {
+            error(format!("profile {name} has a user without a password"));
+        }
+        if let Some(ca_cert) = &profile.ca_cert {
+            if !Path::new(ca_cert).is_file() {
+                error(format!(
+                    "profile {name}: CA certificate {ca_cert} not found"
+                ));
+            }
+        }
+        if let Some(output) = &profile.output {
+            if OutputFormat::from_str(output, true).is_err() {
+                error(format!("profile {name}: unknown output format '{output}'"));
+            }
+        }
+        if profile.auth_cmd.is_some() && (profile.api_key.is_some() || profile.password.is_some()) {
+            problems.push(Problem::warning(
+                &source,
+                line,
+                format!("profile {name} has auth_cmd, so api_key and password are ignored"),
+            ));
+        } else if profile.api_key.is_some() && profile.password.is_some() {
+            problems.push(Problem::warning(
+                &source,
+                line,
+                format!("profile {name} has both api_key and password, so password is ignored"),
+            ));
+        }
+        if profile.insecure {
+            problems.push(Problem::warning(
+                &source,
+                line,
+                format!("profile {name} skips verification of the server certificate"),
+            ));
+        }
+    }
+    problems
+}
+
+/// Checks that the current context, if set, names a profile that exists
+/// and is not overridden by `ESCLI_URL`.
+///
+pub fn check_current_context() -> Vec<Problem> {
+    let (Some(name), Some(path)) = (profile::current_context(), profile::context_path()) else {
+        return vec![];
+    };
+    let source = path.display().to_string();
+    let mut problems = vec![];
+    // A file that cannot be read is reported by `check_config_file`
+    if ConfigFile::load().is_ok_and(|x| !x.profiles.contains_key(&name)) {
+        problems.push(Problem::error(
+            &source,
+            None,
+            format!("current context {name} is not a profile"),
+        ));
+    }
+    if env::var("ESCLI_URL").is_ok() {
+        problems.push(Problem::warning(
+            &source,
+            None,
+            format!("ESCLI_URL is set, so current context {name} is not used"),
+        ));
+    }
+    problems
+}
+
+/// Finds the line of the table header that starts a profile, if it is
+/// written as one.
+///
+fn header_line(text: &str, name: &str) -> Option<usize> {
+    let headers = [
+        format!("[profiles.{name}]"),
+        format!("[profiles.\"{name}\"]"),
+    ];
+    text.lines()
+        .position(|x| headers.iter().any(|header| x.trim() == header))
+        .map(|i| i + 1)
+}
+
+/// Checks that a TCP connection can be made to the host and port of a URL.
+///
+pub fn check_reachable(source: &str, name: &str, url: &Url) -> Option<Problem> {
+    let host = url.host_str().unwrap_or("localhost");
+    let port = url.port_or_known_default().unwrap_or(9200);
+    let address = match (host, port).to_socket_addrs().map(|mut x| x.next()) {
+        Ok(Some(it)) => it,
+        Ok(None) => {
+            return Some(Problem::error(
+                source,
+                None,
+                format!("{name} {url} is unreachable (no addresses found for {host})"),
+            ))
+        }
+        Err(e) => {
+            return Some(Problem::error(
+                source,
+                None,
+                format!("{name} {url} is unreachable ({e})"),
+            ))
+        }
+    };
+    match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+        Ok(_) => None,
+        Err(e) => Some(Problem::error(
+            source,
+            None,
+            format!("{name} {url} is unreachable ({e})"),
+        )),
+    }
+}
+
+pub fn parse_url(value: &str) -> Result<Url, String> {
+    match Url::parse(value) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(url),
+        Ok(url) => Err(format!("unsupported scheme '{}' in {value}", url.scheme())),
+        Err(e) => Err(format!("invalid URL {value} ({e})")),
+    }
+}
+
+/// Finds the known name closest to a misspelt one, if any is close enough
+/// to be a plausible typo.
+///
+fn closest<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|x| (edit_distance(name, x), *x))
+        .filter(|(distance, _)| *distance <= 3)
+        .min()
+        .map(|(_, x)| x)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != *y);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
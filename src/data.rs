@@ -1,11 +1,165 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::stdout};
 
-use serde_json::{json, Value};
+use clap::ValueEnum;
+use serde_json::Value;
 use tabled::{builder::Builder, settings::Style};
 
+use crate::output::{self, OutputFormat};
+
+/// Policy for flattening nested values into dotted column names.
+///
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlattenPolicy {
+    /// Flatten both objects (`user.name`) and arrays (`tags.0`)
+    Full,
+    /// Flatten objects only, leaving arrays as JSON
+    Objects,
+    /// Leave all nested values as JSON
+    None,
+}
+
+/// How nested objects and arrays in documents are shown in table cells.
+///
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NestedDisplay {
+    /// As JSON on a single line
+    Json,
+    /// As dotted columns for the fields of objects (`user.name`), leaving
+    /// arrays as JSON
+    Flatten,
+    /// As indented JSON, over several lines
+    Pretty,
+}
+
+/// Flattens a document into a list of column names and scalar values,
+/// according to the given policy. Keys are visited in sorted order, so that
+/// the same document always produces the same columns.
+///
+pub fn flatten(document: &HashMap<String, Value>, policy: FlattenPolicy) -> Vec<(String, Value)> {
+    let mut keys: Vec<&String> = document.keys().collect();
+    keys.sort();
+    let mut columns = vec![];
+    for key in keys {
+        flatten_value(key.to_owned(), &document[key], policy, &mut columns);
+    }
+    columns
+}
+
+fn flatten_value(
+    prefix: String,
+    value: &Value,
+    policy: FlattenPolicy,
+    columns: &mut Vec<(String, Value)>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() && policy != FlattenPolicy::None => {
+            for (key, item) in map.iter() {
+                flatten_value(format!("{prefix}.{key}"), item, policy, columns);
+            }
+        }
+        Value::Array(items) if !items.is_empty() && policy == FlattenPolicy::Full => {
+            for (i, item) in items.iter().enumerate() {
+                flatten_value(format!("{prefix}.{i}"), item, policy, columns);
+            }
+        }
+        _ => columns.push((prefix, value.clone())),
+    }
+}
+
+/// Lists the columns that `flatten` produces for a document in full, each
+/// with the field it belongs to and the array indexes stepped through on
+/// the way there, so that `tags.0` belongs to `tags` with indexes `[0]`.
+///
+pub fn indexed_columns(document: &HashMap<String, Value>) -> Vec<(String, String, Vec<usize>)> {
+    let mut columns = vec![];
+    for (key, value) in document.iter() {
+        index_value(key.to_owned(), key.to_owned(), vec![], value, &mut columns);
+    }
+    columns
+}
+
+fn index_value(
+    column: String,
+    field: String,
+    indexes: Vec<usize>,
+    value: &Value,
+    columns: &mut Vec<(String, String, Vec<usize>)>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map.iter() {
+                let (column, field) = (format!("{column}.{key}"), format!("{field}.{key}"));
+                index_value(column, field, indexes.clone(), item, columns);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, item) in items.iter().enumerate() {
+                let mut indexes = indexes.clone();
+                indexes.push(i);
+                index_value(
+                    format!("{column}.{i}"),
+                    field.clone(),
+                    indexes,
+                    item,
+                    columns,
+                );
+            }
+        }
+        _ => columns.push((column, field, indexes)),
+    }
+}
+
+/// Finds the value in a document for a dotted column name, as produced by
+/// `flatten`, stepping into objects by key and arrays by index. Keys that
+/// themselves contain dots are matched as well.
+///
+pub fn lookup<'a>(document: &'a HashMap<String, Value>, column: &str) -> Option<&'a Value> {
+    if let Some(value) = document.get(column) {
+        return Some(value);
+    }
+    let mut split = column.match_indices('.').map(|(i, _)| i);
+    split.find_map(|i| lookup_value(document.get(&column[..i])?, &column[i + 1..]))
+}
+
+fn lookup_value<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => {
+            if let Some(value) = map.get(path) {
+                return Some(value);
+            }
+            let mut split = path.match_indices('.').map(|(i, _)| i);
+            split.find_map(|i| lookup_value(map.get(&path[..i])?, &path[i + 1..]))
+        }
+        Value::Array(items) => {
+            let (index, rest) = match path.split_once('.') {
+                Some((index, rest)) => (index, Some(rest)),
+                None => (path, None),
+            };
+            let item = items.get(index.parse::<usize>().ok()?)?;
+            match rest {
+                Some(rest) => lookup_value(item, rest),
+                None => Some(item),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Renders a value as the text of a single cell: strings without quotes,
+/// nulls as empty, and anything else as JSON.
+///
+pub fn to_cell(value: &Value) -> String {
+    match value {
+        Value::String(string_value) => string_value.to_string(),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
 pub struct Table {
     column_names: Vec<String>,
     rows: Vec<Vec<String>>,
+    nested: NestedDisplay,
 }
 
 impl Table {
@@ -13,22 +167,59 @@ impl Table {
         Self {
             column_names: vec![],
             rows: vec![],
+            nested: NestedDisplay::Json,
         }
     }
 
-    pub fn push_document(&mut self, row: &HashMap<String, Value>) {
-        for (key, _value) in row.iter() {
-            if !self.column_names.contains(key) {
-                self.column_names.push(key.to_owned());
-            }
+    pub fn with_column_names(column_names: Vec<String>) -> Self {
+        Self {
+            column_names,
+            rows: vec![],
+            nested: NestedDisplay::Json,
         }
+    }
+
+    /// Sets how nested values are shown in documents pushed from now on.
+    ///
+    pub fn set_nested(&mut self, nested: NestedDisplay) {
+        self.nested = nested;
+    }
+
+    pub fn push_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// Adds a document as a row, adding a column for each field not seen
+    /// before. New fields are added in sorted order, so that columns follow
+    /// the order in which fields are first seen but are otherwise stable
+    /// from run to run.
+    ///
+    pub fn push_document(&mut self, row: &HashMap<String, Value>) {
+        let flattened: HashMap<String, Value>;
+        let row = if self.nested == NestedDisplay::Flatten {
+            flattened = flatten(row, FlattenPolicy::Objects).into_iter().collect();
+            &flattened
+        } else {
+            row
+        };
+        let mut new_keys: Vec<&String> = row
+            .keys()
+            .filter(|key| !self.column_names.contains(key))
+            .collect();
+        new_keys.sort();
+        self.column_names.extend(new_keys.into_iter().cloned());
         let mut string_values: Vec<String> = vec![];
         for column_name in self.column_names.iter() {
-            let value = row.get(column_name).unwrap_or_else(|| &json!(null));
+            let value = row.get(column_name).unwrap_or(&Value::Null);
             match value {
                 Value::String(string_value) => {
                     string_values.push(string_value.to_string());
                 }
+                Value::Object(_) | Value::Array(_) if self.nested == NestedDisplay::Pretty => {
+                    string_values.push(
+                        serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string()),
+                    );
+                }
                 _ => {
                     string_values.push(value.to_string());
                 }
@@ -41,16 +232,140 @@ impl Table {
         self.rows.len()
     }
 
-    fn to_tabled_table(&self) -> tabled::Table {
+    fn to_builder(&self) -> Builder {
         let mut builder = Builder::default();
         builder.push_record(self.column_names.clone());
         for row in self.rows.iter() {
             builder.push_record(row)
         }
-        builder.build()
+        builder
+    }
+
+    /// Writes the table to stdout as delimited text (CSV or TSV), with a
+    /// header row and fields quoted as described in RFC 4180. Rows pushed
+    /// before later columns were added are padded with empty fields.
+    ///
+    pub fn write_delimited(&self, delimiter: u8) -> Result<(), csv::Error> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(stdout());
+        writer.write_record(&self.column_names)?;
+        for row in self.rows.iter() {
+            writer.write_record(
+                (0..self.column_names.len()).map(|i| row.get(i).map_or("", |x| x.as_str())),
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
     }
 
+    /// Prints each row as a record of `field: value` lines, with field names
+    /// aligned, as MySQL does with `\G`. This suits documents with many
+    /// fields better than a table does. Fields not present in a row are left
+    /// out of its record.
+    ///
+    pub fn print_vertical(&self) {
+        let width = self
+            .column_names
+            .iter()
+            .map(|x| x.chars().count())
+            .max()
+            .unwrap_or(0);
+        let marker = "*".repeat(27);
+        for (i, row) in self.rows.iter().enumerate() {
+            println!("{marker} {}. row {marker}", i + 1);
+            for (name, value) in self.column_names.iter().zip(row.iter()) {
+                let value = value.replace('\n', &format!("\n{:width$}  ", ""));
+                println!("{name:>width$}: {value}");
+            }
+        }
+    }
+
+    /// Prints the table with borders or, if another output format has been
+    /// chosen, as rows in that format.
+    ///
     pub fn print(&self) {
-        println!("{}", self.to_tabled_table().with(Style::sharp()));
+        if output::format() != OutputFormat::Table {
+            let rows: Vec<Vec<Value>> = self
+                .rows
+                .iter()
+                .map(|row| row.iter().cloned().map(Value::String).collect())
+                .collect();
+            output::print_rows(&self.column_names, &rows);
+            return;
+        }
+        println!(
+            "{}",
+            output::build_table(self.to_builder()).with(Style::sharp())
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn names(columns: Vec<(String, Value)>) -> Vec<String> {
+        columns.into_iter().map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn flatten_follows_the_policy() {
+        let doc = document(json!({"user": {"name": "a"}, "tags": ["x", "y"], "n": 1}));
+        assert_eq!(
+            names(flatten(&doc, FlattenPolicy::Full)),
+            ["n", "tags.0", "tags.1", "user.name"]
+        );
+        assert_eq!(
+            names(flatten(&doc, FlattenPolicy::Objects)),
+            ["n", "tags", "user.name"]
+        );
+        assert_eq!(
+            names(flatten(&doc, FlattenPolicy::None)),
+            ["n", "tags", "user"]
+        );
+    }
+
+    #[test]
+    fn indexed_columns_record_field_and_indexes() {
+        let doc = document(json!({"users": [{"name": "a"}, {"name": "b"}]}));
+        let mut columns = indexed_columns(&doc);
+        columns.sort();
+        assert_eq!(
+            columns,
+            [
+                (
+                    "users.0.name".to_string(),
+                    "users.name".to_string(),
+                    vec![0]
+                ),
+                (
+                    "users.1.name".to_string(),
+                    "users.name".to_string(),
+                    vec![1]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lookup_steps_into_objects_and_arrays() {
+        let doc = document(json!({
+            "user": {"name": "a", "first.last": "b"},
+            "tags": ["x", {"k": "y"}],
+            "dotted.key": 1,
+        }));
+        assert_eq!(lookup(&doc, "user.name"), Some(&json!("a")));
+        assert_eq!(lookup(&doc, "user.first.last"), Some(&json!("b")));
+        assert_eq!(lookup(&doc, "tags.0"), Some(&json!("x")));
+        assert_eq!(lookup(&doc, "tags.1.k"), Some(&json!("y")));
+        assert_eq!(lookup(&doc, "dotted.key"), Some(&json!(1)));
+        assert_eq!(lookup(&doc, "tags.2"), None);
+        assert_eq!(lookup(&doc, "user.missing"), None);
     }
 }
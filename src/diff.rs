@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{data, output};
+
+const REMOVED: &str = "\x1b[31m";
+const ADDED: &str = "\x1b[32m";
+const CHANGED: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// A single difference between two flattened JSON structures.
+///
+pub enum Change {
+    Removed(String, Value),
+    Added(String, Value),
+    Changed(String, Value, Value),
+}
+
+/// Flattens a JSON object into a sorted map of dotted paths to scalar values.
+///
+pub fn flatten(value: &Value) -> BTreeMap<String, Value> {
+    match value.as_object() {
+        Some(map) => data::flatten(
+            &map.iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect(),
+            data::FlattenPolicy::Full,
+        )
+        .into_iter()
+        .collect(),
+        None => BTreeMap::new(),
+    }
+}
+
+/// Compares two flattened structures, returning the changes needed to turn
+/// the first into the second, in path order.
+///
+pub fn compare(a: &BTreeMap<String, Value>, b: &BTreeMap<String, Value>) -> Vec<Change> {
+    let mut changes = vec![];
+    for (key, a_value) in a.iter() {
+        match b.get(key) {
+            Some(b_value) if b_value != a_value => changes.push(Change::Changed(
+                key.to_owned(),
+                a_value.to_owned(),
+                b_value.to_owned(),
+            )),
+            Some(_) => {}
+            None => changes.push(Change::Removed(key.to_owned(), a_value.to_owned())),
+        }
+    }
+    for (key, b_value) in b.iter() {
+        if !a.contains_key(key) {
+            changes.push(Change::Added(key.to_owned(), b_value.to_owned()));
+        }
+    }
+    changes.sort_by(|x, y| x.key().cmp(y.key()));
+    changes
+}
+
+impl Change {
+    fn key(&self) -> &str {
+        match self {
+            Change::Removed(key, _) | Change::Added(key, _) | Change::Changed(key, _, _) => key,
+        }
+    }
+}
+
+/// Prints a list of changes, one per line, in colour if enabled.
+///
+pub fn print(changes: &[Change]) {
+    let colour = output::colour_stdout();
+    let paint = |style: &str, text: String| {
+        if colour {
+            format!("{style}{text}{RESET}")
+        } else {
+            text
+        }
+    };
+    for change in changes.iter() {
+        println!(
+            "{}",
+            match change {
+                Change::Removed(key, value) => paint(REMOVED, format!("- {key}: {value}")),
+                Change::Added(key, value) => paint(ADDED, format!("+ {key}: {value}")),
+                Change::Changed(key, a, b) => paint(CHANGED, format!("~ {key}: {a} -> {b}")),
+            }
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    process::ExitCode,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    client::{Error, ErrorType},
+    output,
+};
+
+// Exit statuses, as listed in the README.
+
+/// A failure not covered by a more specific status
+pub const FAILURE: u8 = 1;
+/// Invalid command line arguments, as reported by clap, or an input file
+/// named on the command line that cannot be read
+pub const USAGE: u8 = 2;
+/// Invalid or missing configuration, such as the URL, credentials or profile
+pub const CONFIGURATION: u8 = 3;
+/// No response from the cluster, or a response that could not be read
+pub const CONNECTION: u8 = 4;
+/// A request rejected by the cluster (4xx), or a resource that does not exist
+pub const REQUEST: u8 = 5;
+/// An error within the cluster (5xx)
+pub const SERVER: u8 = 6;
+/// An operation that succeeded for some items but failed for others
+pub const PARTIAL: u8 = 7;
+/// Warnings were reported, and `--fail-on warn` was given
+pub const WARNING: u8 = 8;
+/// The run was interrupted by Ctrl-C
+pub const INTERRUPTED: u8 = 130;
+
+/// What counts as a failure, as chosen by the global `--fail-on` option.
+///
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FailOn {
+    /// Fail only on errors
+    Error,
+    /// Also fail on warnings, such as deprecation warnings or a yellow cluster
+    Warn,
+}
+
+static FAIL_ON_WARN: AtomicBool = AtomicBool::new(false);
+
+pub fn set_fail_on(fail_on: FailOn) {
+    FAIL_ON_WARN.store(fail_on == FailOn::Warn, Ordering::Relaxed);
+}
+
+pub fn fail_on_warn() -> bool {
+    FAIL_ON_WARN.load(Ordering::Relaxed)
+}
+
+/// Prints an error, returning the exit status that describes it.
+///
+pub fn fail(e: &Error) -> ExitCode {
+    output::print_error(e);
+    ExitCode::from(of(e))
+}
+
+/// Prints an error from a command that may fail in other ways than a
+/// request, such as reading a file, returning the exit status that
+/// describes it. Client errors, including those from the transport, get
+/// the same status as with `fail`, and anything else a general failure.
+///
+pub fn fail_any(e: &(dyn std::error::Error + 'static)) -> ExitCode {
+    output::print_error(e);
+    if let Some(e) = e.downcast_ref::<Error>() {
+        ExitCode::from(of(e))
+    } else if let Some(e) = e.downcast_ref::<elasticsearch::Error>() {
+        ExitCode::from(of(&Error::from_client_error(e)))
+    } else {
+        ExitCode::from(FAILURE)
+    }
+}
+
+/// Returns the exit status that describes an error.
+///
+pub fn of(e: &Error) -> u8 {
+    match e.error_type() {
+        ErrorType::ConfigurationError => CONFIGURATION,
+        ErrorType::ClientError => CONNECTION,
+        ErrorType::ServerError(400..=499) => REQUEST,
+        ErrorType::ServerError(500..=599) => SERVER,
+        ErrorType::ServerError(_) => FAILURE,
+    }
+}
+
+/// Returns success, or the warning status if warnings were reported and
+/// `--fail-on warn` was given.
+///
+pub fn success_unless_warned(warned: bool) -> ExitCode {
+    if warned && fail_on_warn() {
+        ExitCode::from(WARNING)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,211 @@
+use std::{iter::Peekable, str::Chars};
+
+use serde_json::Value;
+
+/// A filter applied to JSON output, as given by the global `--jq` option.
+/// This supports the subset of jq most used in scripts: paths such as
+/// `.version.number`, `.hits.hits[0]` and `."key with spaces"`, iteration
+/// with `[]`, pipes, and the `keys` and `length` functions.
+///
+#[derive(Clone, Debug)]
+pub struct Filter {
+    stages: Vec<Stage>,
+}
+
+#[derive(Clone, Debug)]
+enum Stage {
+    Path(Vec<Step>),
+    Keys,
+    Length,
+}
+
+#[derive(Clone, Debug)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Iterate,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let stages = expression
+            .split('|')
+            .map(|x| parse_stage(x.trim()))
+            .collect::<Result<Vec<Stage>, String>>()?;
+        Ok(Self { stages })
+    }
+
+    /// Applies the filter to a value, returning every value it produces.
+    ///
+    pub fn apply(&self, value: &Value) -> Result<Vec<Value>, String> {
+        let mut values = vec![value.clone()];
+        for stage in self.stages.iter() {
+            let mut next = vec![];
+            for value in values.iter() {
+                match stage {
+                    Stage::Path(steps) => next.extend(follow(value, steps)?),
+                    Stage::Keys => next.push(keys(value)?),
+                    Stage::Length => next.push(length(value)?),
+                }
+            }
+            values = next;
+        }
+        Ok(values)
+    }
+}
+
+fn parse_stage(text: &str) -> Result<Stage, String> {
+    match text {
+        "keys" => return Ok(Stage::Keys),
+        "length" => return Ok(Stage::Length),
+        _ => {}
+    }
+    let mut chars = text.chars().peekable();
+    let mut steps = vec![];
+    if chars.next() != Some('.') {
+        return Err(format!("filter stage must start with '.': {text}"));
+    }
+    loop {
+        match chars.peek() {
+            None => break,
+            Some('[') => {
+                chars.next();
+                steps.push(parse_bracket(&mut chars)?);
+            }
+            Some('"') => {
+                chars.next();
+                steps.push(Step::Field(parse_quoted(&mut chars)?));
+            }
+            Some(_) => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                if name.is_empty() {
+                    return Err(format!("unexpected character in filter: {text}"));
+                }
+                steps.push(Step::Field(name));
+            }
+        }
+        match chars.peek() {
+            None => break,
+            Some('.') => {
+                chars.next();
+            }
+            Some('[') => {}
+            Some(_) => return Err(format!("unexpected character in filter: {text}")),
+        }
+    }
+    Ok(Stage::Path(steps))
+}
+
+fn parse_bracket(chars: &mut Peekable<Chars>) -> Result<Step, String> {
+    let mut inner = String::new();
+    if chars.next_if_eq(&'"').is_some() {
+        inner = parse_quoted(chars)?;
+        return match chars.next() {
+            Some(']') => Ok(Step::Field(inner)),
+            _ => Err("missing ']' in filter".to_string()),
+        };
+    }
+    loop {
+        match chars.next() {
+            Some(']') => break,
+            Some(c) => inner.push(c),
+            None => return Err("missing ']' in filter".to_string()),
+        }
+    }
+    let inner = inner.trim();
+    if inner.is_empty() {
+        return Ok(Step::Iterate);
+    }
+    inner
+        .parse::<i64>()
+        .map(Step::Index)
+        .map_err(|_| format!("invalid index in filter: [{inner}]"))
+}
+
+fn parse_quoted(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some(c) => text.push(c),
+                None => break,
+            },
+            Some(c) => text.push(c),
+            None => break,
+        }
+    }
+    Err("unterminated string in filter".to_string())
+}
+
+/// Follows a path through a value. As in jq, missing fields and indexes
+/// out of range give null, while indexing the wrong type of value is an
+/// error.
+///
+fn follow(value: &Value, steps: &[Step]) -> Result<Vec<Value>, String> {
+    let Some((step, rest)) = steps.split_first() else {
+        return Ok(vec![value.clone()]);
+    };
+    let found = match (step, value) {
+        (_, Value::Null) if !matches!(step, Step::Iterate) => vec![Value::Null],
+        (Step::Field(name), Value::Object(map)) => {
+            vec![map.get(name).cloned().unwrap_or(Value::Null)]
+        }
+        (Step::Index(i), Value::Array(items)) => {
+            let i = if *i < 0 { items.len() as i64 + i } else { *i };
+            vec![usize::try_from(i)
+                .ok()
+                .and_then(|i| items.get(i))
+                .cloned()
+                .unwrap_or(Value::Null)]
+        }
+        (Step::Iterate, Value::Array(items)) => items.clone(),
+        (Step::Iterate, Value::Object(map)) => map.values().cloned().collect(),
+        (Step::Field(name), _) => {
+            return Err(format!("cannot index {} with \"{name}\"", type_name(value)))
+        }
+        (Step::Index(i), _) => return Err(format!("cannot index {} with {i}", type_name(value))),
+        (Step::Iterate, _) => return Err(format!("cannot iterate over {}", type_name(value))),
+    };
+    let mut results = vec![];
+    for item in found.iter() {
+        results.extend(follow(item, rest)?);
+    }
+    Ok(results)
+}
+
+fn keys(value: &Value) -> Result<Value, String> {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Ok(Value::from(keys.into_iter().cloned().collect::<Vec<_>>()))
+        }
+        Value::Array(items) => Ok(Value::from((0..items.len()).collect::<Vec<_>>())),
+        _ => Err(format!("{} has no keys", type_name(value))),
+    }
+}
+
+fn length(value: &Value) -> Result<Value, String> {
+    match value {
+        Value::Object(map) => Ok(Value::from(map.len())),
+        Value::Array(items) => Ok(Value::from(items.len())),
+        Value::String(text) => Ok(Value::from(text.chars().count())),
+        Value::Null => Ok(Value::from(0)),
+        _ => Err(format!("{} has no length", type_name(value))),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
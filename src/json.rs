@@ -0,0 +1,73 @@
+use serde_json::Value;
+
+use crate::output;
+
+const KEY: &str = "\x1b[34;1m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[36m";
+const LITERAL: &str = "\x1b[35m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders a JSON value for display. Compact output is emitted on a single
+/// line, while non-compact output is indented and, when colour is enabled,
+/// syntax highlighted.
+///
+pub fn render(value: &Value, compact: bool) -> String {
+    if compact {
+        value.to_string()
+    } else {
+        let mut out = String::new();
+        write_pretty(&mut out, value, 0, output::colour_stdout());
+        out
+    }
+}
+
+fn write_pretty(out: &mut String, value: &Value, depth: usize, colour: bool) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push('{');
+            for (i, (key, item)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                paint(out, KEY, &Value::String(key.to_owned()).to_string(), colour);
+                out.push_str(": ");
+                write_pretty(out, item, depth + 1, colour);
+            }
+            newline(out, depth);
+            out.push('}');
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                write_pretty(out, item, depth + 1, colour);
+            }
+            newline(out, depth);
+            out.push(']');
+        }
+        Value::String(_) => paint(out, STRING, &value.to_string(), colour),
+        Value::Number(_) => paint(out, NUMBER, &value.to_string(), colour),
+        Value::Bool(_) | Value::Null => paint(out, LITERAL, &value.to_string(), colour),
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+fn newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(depth));
+}
+
+fn paint(out: &mut String, style: &str, text: &str, colour: bool) {
+    if colour {
+        out.push_str(style);
+        out.push_str(text);
+        out.push_str(RESET);
+    } else {
+        out.push_str(text);
+    }
+}
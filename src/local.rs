@@ -0,0 +1,145 @@
+use rusqlite::{params_from_iter, types::Value as SqlValue, types::ValueRef, Connection};
+use serde_json::Value;
+
+use crate::{
+    client::RawSearchResultHitsHit,
+    data::{self, FlattenPolicy},
+};
+
+/// Appends search hits to a table in a local SQLite database, named after
+/// the index they came from. The table is created if it does not already
+/// exist, and columns are added as new fields are encountered, with their
+/// types inferred from the values in the hits. Returns the number of rows
+/// inserted.
+///
+pub fn save_hits(
+    path: &str,
+    table: &str,
+    hits: &[RawSearchResultHitsHit],
+) -> Result<usize, rusqlite::Error> {
+    let mut connection = Connection::open(path)?;
+    let rows: Vec<Vec<(String, Value)>> = hits
+        .iter()
+        .map(|hit| {
+            let mut row = vec![
+                ("_index".to_string(), Value::from(hit._index.as_str())),
+                ("_id".to_string(), Value::from(hit._id.as_str())),
+                ("_score".to_string(), Value::from(hit._score)),
+            ];
+            row.extend(data::flatten(&hit._source, FlattenPolicy::Objects));
+            row
+        })
+        .collect();
+    let transaction = connection.transaction()?;
+    transaction.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (_index TEXT, _id TEXT, _score REAL)",
+            quote(table)
+        ),
+        [],
+    )?;
+    let mut existing: Vec<String> = vec![];
+    {
+        let mut statement = transaction.prepare(&format!("PRAGMA table_info({})", quote(table)))?;
+        let names = statement.query_map([], |row| row.get::<_, String>(1))?;
+        for name in names {
+            existing.push(name?);
+        }
+    }
+    for row in rows.iter() {
+        for (name, _value) in row.iter() {
+            if !existing.contains(name) {
+                transaction.execute(
+                    &format!(
+                        "ALTER TABLE {} ADD COLUMN {} {}",
+                        quote(table),
+                        quote(name),
+                        infer_column_type(name, &rows)
+                    ),
+                    [],
+                )?;
+                existing.push(name.to_owned());
+            }
+        }
+    }
+    for row in rows.iter() {
+        let names: Vec<String> = row.iter().map(|(name, _)| quote(name)).collect();
+        let placeholders: Vec<&str> = row.iter().map(|_| "?").collect();
+        transaction.execute(
+            &format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                quote(table),
+                names.join(", "),
+                placeholders.join(", ")
+            ),
+            params_from_iter(row.iter().map(|(_, value)| to_sql_value(value))),
+        )?;
+    }
+    transaction.commit()?;
+    Ok(rows.len())
+}
+
+/// Runs an SQL query against a local SQLite database, returning the column
+/// names and the rows of the result as display strings.
+///
+pub fn query(path: &str, sql: &str) -> Result<(Vec<String>, Vec<Vec<String>>), rusqlite::Error> {
+    let connection = Connection::open(path)?;
+    let mut statement = connection.prepare(sql)?;
+    let column_names: Vec<String> = statement
+        .column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let mut rows = statement.query([])?;
+    let mut results = vec![];
+    while let Some(row) = rows.next()? {
+        let mut cells = vec![];
+        for i in 0..column_names.len() {
+            cells.push(match row.get_ref(i)? {
+                ValueRef::Null => String::new(),
+                ValueRef::Integer(x) => x.to_string(),
+                ValueRef::Real(x) => x.to_string(),
+                ValueRef::Text(x) => String::from_utf8_lossy(x).to_string(),
+                ValueRef::Blob(x) => format!("<{} bytes>", x.len()),
+            });
+        }
+        results.push(cells);
+    }
+    Ok((column_names, results))
+}
+
+fn infer_column_type(name: &str, rows: &[Vec<(String, Value)>]) -> &'static str {
+    let values: Vec<&Value> = rows
+        .iter()
+        .flat_map(|row| row.iter().filter(|(n, _)| n == name).map(|(_, v)| v))
+        .filter(|value| !value.is_null())
+        .collect();
+    if !values.is_empty()
+        && values
+            .iter()
+            .all(|x| x.is_i64() || x.is_u64() || x.is_boolean())
+    {
+        "INTEGER"
+    } else if !values.is_empty() && values.iter().all(|x| x.is_number()) {
+        "REAL"
+    } else {
+        "TEXT"
+    }
+}
+
+fn to_sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(x) => SqlValue::Integer(*x as i64),
+        Value::Number(x) => match x.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(x.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(x) => SqlValue::Text(x.to_owned()),
+        _ => SqlValue::Text(value.to_string()),
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
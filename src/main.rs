@@ -1,19 +1,64 @@
 mod client;
+mod config;
 mod data;
+mod diff;
+mod exit_status;
+mod filter;
+mod json;
+mod local;
+mod mapping;
+mod observe;
+mod outfile;
+mod output;
+mod pager;
+mod ping;
+mod profile;
+mod quickstart;
+mod screen;
+mod shell;
+mod source;
+mod template;
+mod throttle;
+mod timing;
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    fs::{self, File},
+    io::{stdout, IsTerminal, Write},
+    num::NonZeroUsize,
     process::{exit, ExitCode},
-    thread::sleep,
-    time::{Duration, SystemTime},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use byte_unit::{Byte, UnitType};
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use elasticsearch::{auth::Credentials, http::Url};
 
-use client::{RawBulkSummary, RawSearchResult, SimpleClient};
-use data::Table;
+use client::{
+    Dedupe, Document, GeoPoint, IndexDetail, LoadOptions, LoadSource, LoadSummary, RawIndicesStats,
+    RawRollover, RawSearchResult, RawSearchResultHitsHit, Resize, SimpleClient,
+};
+use data::{FlattenPolicy, NestedDisplay, Table};
+use exit_status::{fail, FailOn};
+use filter::Filter;
+use mapping::FieldSpec;
+use output::{ColourChoice, OutputFormat, TableLayout};
+use ping::{PingOptions, PingRun};
+use rustyline::{error::ReadlineError, history::DefaultHistory, Editor};
+use screen::Screen;
+use serde_json::{json, Map, Value};
+use shell::{Session, ShellHelper};
 use tabled::settings::{object::Columns, Alignment, Padding, Style};
+use tokio::{
+    sync::mpsc::{channel, Receiver, Sender},
+    task::JoinSet,
+    time::MissedTickBehavior,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -21,6 +66,83 @@ use tabled::settings::{object::Columns, Alignment, Padding, Style};
 struct CommandLine {
     #[command(subcommand)]
     command: Commands,
+    #[arg(long = "timings", global = true)]
+    #[arg(help = "Report a timing breakdown of the connection and of each request sent")]
+    timings: bool,
+    #[arg(long = "show-headers", global = true)]
+    #[arg(help = "Print the headers of each response, including any deprecation warnings")]
+    show_headers: bool,
+    #[arg(short = 'y', long = "yes", global = true)]
+    #[arg(
+        help = "Allow commands that change data or settings to run against a production cluster"
+    )]
+    yes: bool,
+    #[arg(long = "no-warnings", global = true)]
+    #[arg(help = "Do not print a summary of deprecation warnings on exit")]
+    no_warnings: bool,
+    // Not --strict, which `load` uses for validation, as clap identifies
+    // arguments by name and the global would be shadowed there
+    #[arg(long = "strict-schema", global = true)]
+    #[arg(
+        help = "Fail on responses with missing fields or other differences from the expected schema"
+    )]
+    strict_schema: bool,
+    #[arg(short = 'o', long = "output", global = true, value_enum)]
+    #[arg(
+        help = "Output format; json, plain and csv are for use in scripts [default: table, or as set in the profile]"
+    )]
+    output: Option<OutputFormat>,
+    #[arg(long = "columns", global = true, value_delimiter = ',')]
+    #[arg(help = "Comma-separated list of columns to show in tables, or to export, in order")]
+    columns: Vec<String>,
+    #[arg(long = "max-col-width", global = true)]
+    #[arg(help = "Truncate table cells wider than this many characters, with an ellipsis")]
+    max_col_width: Option<usize>,
+    #[arg(long = "wrap", global = true, requires = "max_col_width")]
+    #[arg(help = "Wrap wide table cells onto several lines instead of truncating them")]
+    wrap: bool,
+    #[arg(long = "color", global = true)]
+    #[arg(help = "When to use colour (auto uses colour on a terminal, unless NO_COLOR is set)")]
+    #[arg(default_value_t = ColourChoice::Auto, value_enum)]
+    colour: ColourChoice,
+    #[arg(short = 'v', long = "verbose", global = true, action = clap::ArgAction::Count)]
+    #[arg(
+        help = "Trace each request to stderr (-vv to include response bodies, with secrets redacted)"
+    )]
+    verbose: u8,
+    #[arg(long = "curl", global = true)]
+    #[arg(
+        help = "Print an equivalent curl command for each request to stderr, with placeholders for credentials and bodies"
+    )]
+    curl: bool,
+    #[arg(long = "no-pager", global = true)]
+    #[arg(help = "Do not pipe output through $PAGER when writing to a terminal")]
+    no_pager: bool,
+    #[arg(long = "quiet", global = true, conflicts_with = "verbose")]
+    #[arg(help = "Suppress non-essential output, such as row counts and warning summaries")]
+    quiet: bool,
+    // No --query, as that is taken by `search --query`
+    #[arg(long = "jq", global = true, value_parser = Filter::parse)]
+    #[arg(
+        help = "Filter JSON output with a jq-style path, such as .version.number (implies --output json)"
+    )]
+    jq: Option<Filter>,
+    #[arg(long = "out", global = true, value_name = "FILE")]
+    #[arg(
+        help = "Write output to FILE instead of stdout, replacing it once complete (gzipped if the name ends in .gz)"
+    )]
+    out: Option<String>,
+    #[arg(long = "fail-on", global = true, value_enum, default_value_t = FailOn::Error)]
+    #[arg(help = "Whether warnings, as well as errors, give a failure exit status")]
+    fail_on: FailOn,
+    #[arg(long = "ascii", global = true)]
+    #[arg(help = "Show health and other markers as plain text rather than emoji")]
+    ascii: bool,
+    #[arg(long = "profile", global = true, value_name = "NAME")]
+    #[arg(
+        help = "Connect using the named profile from the configuration file (overrides ESCLI_PROFILE)"
+    )]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -34,18 +156,160 @@ enum Commands {
         #[arg(help = "Time to wait in seconds between requests (default 1s)")]
         #[arg(default_value_t = 1.0)]
         interval: f64,
+        #[arg(long = "compare")]
+        #[arg(
+            help = "Compare latency and loss with a baseline run saved in FILE (saving this run as the baseline if FILE does not exist)"
+        )]
+        compare: Option<String>,
+        #[arg(long = "save")]
+        #[arg(help = "Save the latencies of this run to FILE, for later comparison")]
+        save: Option<String>,
+        #[arg(short = 'u', long = "until-available")]
+        #[arg(help = "Stop at the first successful (2xx) response, failing if none is received")]
+        until_available: bool,
+        #[arg(short = 't', long = "timeout", value_parser = parse_duration)]
+        #[arg(requires = "until_available")]
+        #[arg(help = "With --until-available, give up after this long (e.g. 60s or 2m)")]
+        timeout: Option<Duration>,
+        #[arg(long = "concurrent", value_name = "N", default_value = "1")]
+        #[arg(
+            help = "Allow up to N requests in flight, sending each on schedule without waiting for earlier responses"
+        )]
+        concurrent: NonZeroUsize,
+        // Named so as not to clash with --timeout, which bounds the whole run
+        #[arg(long = "request-timeout", value_parser = parse_duration)]
+        #[arg(help = "Count a request as lost if no response arrives within this time (e.g. 5s)")]
+        request_timeout: Option<Duration>,
     },
 
     #[command(about = "Show information about the Elasticsearch service")]
     Info {},
 
+    #[command(about = "Take a guided tour of escli and Elasticsearch using a demo index")]
+    Quickstart {},
+
+    #[command(
+        about = "Start an interactive session, with history and tab completion, running each command over one connection"
+    )]
+    Shell {},
+
+    #[command(about = "Manage named connection profiles in the configuration file")]
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+
+    #[command(about = "Switch the cluster that subsequent commands target, by profile name")]
+    Context {
+        #[command(subcommand)]
+        command: ContextCommands,
+    },
+
+    #[command(about = "Show document, storage, activity, merge and cache statistics for indexes")]
+    Stats {
+        #[arg(help = "Name of the index (or pattern)")]
+        index: String,
+        #[arg(short = 'w', long = "watch", num_args = 0..=1, default_missing_value = "2")]
+        #[arg(help = "Refresh every SECONDS (default 2), showing indexing and search rates")]
+        watch: Option<f64>,
+    },
+
+    #[command(about = "List shards, with the reason for any that are unassigned")]
+    Shards {
+        #[arg(help = "Name of the index (or pattern)")]
+        index: Option<String>,
+    },
+
+    #[command(name = "allocation-explain")]
+    #[command(about = "Explain why a shard is unassigned, or allocated where it is")]
+    AllocationExplain {
+        #[arg(long = "index")]
+        #[arg(help = "Index of the shard to explain (the first unassigned shard if omitted)")]
+        index: Option<String>,
+        #[arg(long = "shard", requires = "index")]
+        #[arg(help = "Number of the shard to explain")]
+        #[arg(default_value_t = 0)]
+        shard: u32,
+        #[arg(long = "primary", requires = "index")]
+        #[arg(help = "Explain the primary, rather than a replica")]
+        primary: bool,
+    },
+
+    #[command(about = "List the Lucene segments of each shard of an index")]
+    Segments {
+        #[arg(help = "Name of the index (or pattern)")]
+        index: String,
+    },
+
+    #[command(about = "Show the progress of shard recoveries for an index")]
+    Recovery {
+        #[arg(help = "Name of the index (or pattern, default all)")]
+        index: Option<String>,
+        #[arg(short = 'a', long = "active-only")]
+        #[arg(help = "Only show recoveries still in progress")]
+        active_only: bool,
+        #[arg(short = 'w', long = "watch")]
+        #[arg(
+            help = "Refresh every SECONDS until interrupted, or until none remain with --active-only"
+        )]
+        watch: Option<f64>,
+    },
+
+    #[command(about = "Show cluster health, optionally waiting for a status")]
+    Health {
+        #[arg(long = "wait-for")]
+        #[arg(help = "Wait for the cluster to reach at least this status")]
+        #[arg(value_enum)]
+        wait_for: Option<HealthStatus>,
+        #[arg(short = 't', long = "timeout")]
+        #[arg(help = "Time to wait for the status before failing (e.g. 60s)")]
+        #[arg(default_value = "30s")]
+        timeout: String,
+        #[arg(short = 'w', long = "watch", num_args = 0..=1, default_missing_value = "2")]
+        #[arg(conflicts_with = "wait_for")]
+        #[arg(help = "Refresh every SECONDS (default 2), highlighting changes")]
+        watch: Option<f64>,
+    },
+
+    #[command(about = "List nodes, with their roles and resource usage")]
+    Nodes {},
+
+    #[command(name = "node-stats")]
+    #[command(about = "Show JVM, OS, filesystem or other statistics for a node")]
+    NodeStats {
+        #[arg(help = "Name or ID of the node (or pattern, or _all)")]
+        node: String,
+        #[arg(short = 'm', long = "metric")]
+        #[arg(help = "Comma-separated metrics to show (e.g. jvm,os,fs,thread_pool)")]
+        #[arg(value_delimiter = ',', default_value = "jvm,os,fs")]
+        metrics: Vec<String>,
+    },
+
+    #[command(name = "pending-tasks")]
+    #[command(about = "List cluster changes waiting to be executed by the master")]
+    PendingTasks {},
+
+    #[command(name = "cluster-stats")]
+    #[command(about = "Summarise node, index and shard counts, heap usage and versions")]
+    ClusterStats {},
+
+    #[command(about = "Show a summary of cluster status")]
+    Status {
+        #[arg(long = "terse")]
+        #[arg(help = "Print a single line, for use in shell prompts and status bars")]
+        terse: bool,
+        #[arg(long = "cache")]
+        #[arg(help = "Reuse a terse status line fetched within this many seconds")]
+        cache: Option<u64>,
+    },
+
     #[command(name = "ls")]
     #[command(about = "List available indexes")]
     ListIndexes {
         #[arg(short = 'a', long = "all")]
         #[arg(help = "Match any data stream or index, including hidden ones")]
         all: bool,
-        #[arg(short = 'o', long = "open")]
+        #[arg(long = "open")]
         #[arg(help = "Match open, non-hidden indices (also matches any non-hidden data stream)")]
         open: bool,
         #[arg(short = 'c', long = "closed")]
@@ -53,6 +317,12 @@ enum Commands {
         closed: bool,
         #[arg(help = "Index name or pattern to include in list")]
         index: Option<String>,
+        #[arg(short = 'l', long = "long")]
+        #[arg(help = "Also show primary and replica shard counts, and creation date")]
+        long: bool,
+        #[arg(short = 'w', long = "watch", num_args = 0..=1, default_missing_value = "2")]
+        #[arg(help = "Refresh every SECONDS (default 2), highlighting changes")]
+        watch: Option<f64>,
     },
 
     #[command(name = "mk")]
@@ -61,15 +331,56 @@ enum Commands {
         #[arg(help = "Name of the index to create")]
         index: String,
         #[arg(short = 'm', long = "mapping")]
-        #[arg(help = "Field mapping")]
-        mappings: Vec<String>,
+        #[arg(
+            help = "Field mapping, as NAME:TYPE[:OPTION=VALUE...] (e.g. title:text:analyzer=english)"
+        )]
+        mappings: Vec<FieldSpec>,
+        #[arg(short = 'M', long = "mappings-file")]
+        #[arg(help = "JSON or YAML file containing the mapping (fields given with -m are added)")]
+        mappings_file: Option<String>,
+        #[arg(long = "like")]
+        #[arg(help = "Copy the mappings and settings of an existing index (or template:NAME)")]
+        #[arg(conflicts_with = "mappings_file")]
+        like: Option<String>,
+        #[arg(long = "shards")]
+        #[arg(help = "Number of primary shards")]
+        shards: Option<u32>,
+        #[arg(long = "replicas")]
+        #[arg(help = "Number of replicas of each primary shard")]
+        replicas: Option<u32>,
+        #[arg(long = "refresh-interval")]
+        #[arg(
+            help = "How often to make new changes visible to search (e.g. 30s, or -1 to disable)"
+        )]
+        #[arg(allow_hyphen_values = true)]
+        refresh_interval: Option<String>,
+        #[arg(long = "setting")]
+        #[arg(help = "Other index setting, as KEY=VALUE (e.g. index.codec=best_compression)")]
+        #[arg(value_parser = parse_setting)]
+        settings: Vec<(String, String)>,
+        #[arg(short = 'i', long = "interactive")]
+        #[arg(help = "Build the field mapping interactively before creating the index")]
+        interactive: bool,
+        #[arg(long = "wait-for-active-shards")]
+        #[arg(help = "Number of shard copies that must be active before returning (or 'all')")]
+        wait_for_active_shards: Option<String>,
+        #[arg(short = 't', long = "timeout")]
+        #[arg(help = "Time to wait for the request to be acknowledged (e.g. 30s)")]
+        timeout: Option<String>,
+        #[arg(long = "if-not-exists")]
+        #[arg(help = "Succeed without changes if the index already exists")]
+        if_not_exists: bool,
     },
 
     #[command(name = "rm")]
     #[command(about = "Delete index")]
     DeleteIndex {
-        #[arg(help = "Name of the index to delete")]
-        index: String,
+        #[arg(help = "Names of the indexes to delete, or patterns such as 'tmp-*'")]
+        #[arg(required = true)]
+        indexes: Vec<String>,
+        #[arg(long = "if-exists")]
+        #[arg(help = "Skip names that do not match an existing index, rather than failing")]
+        if_exists: bool,
     },
 
     #[command(about = "Load data into an index")]
@@ -77,8 +388,139 @@ enum Commands {
         #[arg(help = "Name of the index to load into")]
         index: String,
         #[arg(short = 'c', long = "from-csv")]
-        #[arg(help = "Filename of CSV file to load from")]
+        #[arg(help = "Filename or HTTP(S) URL of CSV file to load from (may be gzipped)")]
         csv_filenames: Vec<String>,
+        #[arg(short = 'x', long = "from-xlsx")]
+        #[arg(help = "Filename or HTTP(S) URL of Excel workbook to load from")]
+        xlsx_filenames: Vec<String>,
+        #[arg(long = "sheet")]
+        #[arg(help = "Name of the worksheet to load from Excel workbooks (default first)")]
+        #[arg(requires = "xlsx_filenames")]
+        sheet: Option<String>,
+        #[arg(short = 'b', long = "batch-size")]
+        #[arg(help = "Number of documents to send in each bulk request (default 1000)")]
+        #[arg(default_value_t = 1000)]
+        batch_size: usize,
+        #[arg(short = 'H', long = "header")]
+        #[arg(
+            help = "Extra HTTP header to send when fetching from a URL (e.g. 'Authorization: Bearer TOKEN')"
+        )]
+        headers: Vec<String>,
+        #[arg(long = "max-docs-per-sec", value_parser = parse_rate)]
+        #[arg(help = "Limit the average rate at which documents are sent")]
+        max_docs_per_sec: Option<f64>,
+        #[arg(long = "max-bytes-per-sec")]
+        #[arg(help = "Limit the average rate at which document data is sent (e.g. 5MB)")]
+        #[arg(value_parser = parse_byte_rate)]
+        max_bytes_per_sec: Option<u64>,
+        #[arg(long = "retries")]
+        #[arg(help = "Number of times to retry documents rejected by a busy cluster (default 3)")]
+        #[arg(default_value_t = 3)]
+        retries: u32,
+        #[arg(long = "retry-backoff", value_parser = parse_backoff)]
+        #[arg(
+            help = "Initial time in seconds to wait before retrying, doubled on each attempt (default 1s)"
+        )]
+        #[arg(default_value_t = 1.0)]
+        retry_backoff: f64,
+        #[arg(long = "validate")]
+        #[arg(
+            help = "Check the data against the index mapping before loading, warning of problems"
+        )]
+        validate: bool,
+        #[arg(long = "strict")]
+        #[arg(help = "Do not load any data if validation finds problems (implies --validate)")]
+        strict: bool,
+        #[arg(long = "auto-create")]
+        #[arg(help = "Create the index with an inferred mapping if it does not exist")]
+        auto_create: bool,
+        #[arg(long = "sample-size")]
+        #[arg(help = "Number of records to sample when inferring a mapping (default 100)")]
+        #[arg(default_value_t = 100)]
+        sample_size: usize,
+        #[arg(long = "geo-point")]
+        #[arg(
+            help = "Combine latitude and longitude columns into a geo point (e.g. location=lat,lon)"
+        )]
+        #[arg(value_parser = parse_geo_point)]
+        geo_points: Vec<GeoPoint>,
+        #[arg(long = "geo-point-as-string")]
+        #[arg(help = "Build geo points as \"lat,lon\" strings rather than objects")]
+        #[arg(requires = "geo_points")]
+        geo_points_as_strings: bool,
+        #[arg(long = "routing-field")]
+        #[arg(help = "Column whose value is used to route each document to a shard")]
+        routing_field: Option<String>,
+        #[arg(long = "dedupe-by")]
+        #[arg(
+            help = "Comma-separated columns to hash into each document ID, skipping rows already loaded"
+        )]
+        #[arg(value_delimiter = ',')]
+        dedupe_by: Vec<String>,
+        #[arg(long = "dedupe-hash")]
+        #[arg(help = "Hash all columns into each document ID, skipping rows already loaded")]
+        #[arg(conflicts_with = "dedupe_by")]
+        dedupe_hash: bool,
+    },
+
+    #[command(about = "Copy documents from one index to another, via this client")]
+    Copy {
+        #[arg(help = "Name of the index to copy from")]
+        source: String,
+        #[arg(help = "Name of the index to copy into")]
+        destination: String,
+        #[arg(short = 'q', long = "query")]
+        #[arg(help = "Lucene query selecting the documents to copy (default all)")]
+        query: Option<String>,
+        #[arg(long = "dest-url")]
+        #[arg(help = "URL of the destination service, if different from the source")]
+        #[arg(requires = "dest_api_key")]
+        dest_url: Option<String>,
+        #[arg(long = "dest-api-key")]
+        #[arg(help = "API key for the destination service")]
+        #[arg(requires = "dest_url")]
+        dest_api_key: Option<String>,
+        #[arg(short = 'b', long = "batch-size")]
+        #[arg(help = "Number of documents to read and write in each request (default 1000)")]
+        #[arg(default_value_t = 1000)]
+        batch_size: usize,
+    },
+
+    #[command(about = "Export documents from an index to CSV")]
+    Export {
+        #[arg(help = "Name of the index to export")]
+        index: String,
+        #[arg(help = "Lucene query selecting the documents to export (default all)")]
+        query: Option<String>,
+        #[arg(short = 'O', long = "output-file")]
+        #[arg(help = "Filename to write CSV to (default standard output)")]
+        output_file: Option<String>,
+        #[arg(long = "flatten")]
+        #[arg(
+            help = "How to flatten nested objects and arrays into columns (with full, documents are read twice, first to find the array items present)"
+        )]
+        #[arg(default_value_t = FlattenPolicy::Full, value_enum)]
+        flatten: FlattenPolicy,
+        #[arg(short = 'b', long = "batch-size")]
+        #[arg(help = "Number of documents to read in each request (default 1000)")]
+        #[arg(default_value_t = 1000)]
+        batch_size: usize,
+        #[arg(short = 's', long = "slices")]
+        #[arg(help = "Number of slices to read concurrently (default 1)")]
+        #[arg(default_value_t = 1)]
+        #[arg(value_parser = clap::value_parser!(u16).range(1..))]
+        slices: u16,
+    },
+
+    #[command(name = "suggest-mapping")]
+    #[command(about = "Suggest a field mapping for the contents of a CSV file")]
+    SuggestMapping {
+        #[arg(help = "Filename of CSV file to sample")]
+        csv_filename: String,
+        #[arg(long = "sample-size")]
+        #[arg(help = "Number of records to sample (default 100)")]
+        #[arg(default_value_t = 100)]
+        sample_size: usize,
     },
 
     #[command(about = "Perform a search on an index")]
@@ -87,256 +529,7491 @@ enum Commands {
         index: String,
         #[arg(help = "Lucene search query")]
         query: Option<String>,
-        #[arg(short = 'o', long = "order-by")]
+        #[arg(short = 's', long = "order-by")]
         #[arg(help = "Comma-separated list of FIELD:DIRECTION pairs")]
         order_by: Option<String>,
         #[arg(short = 'l', long = "limit")]
         #[arg(help = "Maximum number of search hits to return (default 10)")]
         limit: Option<u16>,
+        #[arg(short = 'r', long = "routing")]
+        #[arg(help = "Routing value, limiting the search to the shard it selects")]
+        routing: Option<String>,
         #[arg(short = 'f', long = "format")]
         #[arg(help = "Output format for search results")]
         #[arg(default_value_t = SearchResultFormat::Table, value_enum)]
         format: SearchResultFormat,
+        #[arg(long = "compact")]
+        #[arg(help = "Print raw JSON on a single line, without indentation or highlighting")]
+        compact: bool,
+        #[arg(long = "nested")]
+        #[arg(help = "How to show nested objects and arrays in table, CSV and TSV output")]
+        #[arg(default_value_t = NestedDisplay::Json, value_enum)]
+        nested: NestedDisplay,
+        #[arg(long = "with-meta")]
+        #[arg(help = "Include the _index, _id and _score of each hit alongside its source")]
+        with_meta: bool,
+        #[arg(long = "save-to")]
+        #[arg(help = "Also append search hits to a table in this local SQLite database")]
+        save_to: Option<String>,
+        #[arg(long = "assert-schema")]
+        #[arg(help = "Check the source of each hit against a JSON Schema, failing on violations")]
+        assert_schema: Option<String>,
+    },
+
+    #[command(name = "field-usage")]
+    #[command(about = "Show how often each field of an index is accessed by queries")]
+    FieldUsage {
+        #[arg(help = "Name of the index to inspect")]
+        index: String,
+    },
+
+    #[command(about = "List and manage index aliases")]
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommands,
+    },
+
+    #[command(about = "Switch an alias to a new index, blue/green style")]
+    Deploy {
+        #[arg(help = "Name of the alias to switch")]
+        alias: String,
+        #[arg(short = 'n', long = "new-index")]
+        #[arg(help = "Name of the index to switch the alias to")]
+        new_index: String,
+        #[arg(long = "verify-count")]
+        #[arg(help = "Only switch if the new index has at least as many documents as the old")]
+        verify_count: bool,
+        #[arg(long = "keep")]
+        #[arg(
+            help = "Number of index versions to keep, deleting older ones with the same name prefix (requires a -NUMBER version suffix, e.g. products-7)"
+        )]
+        #[arg(value_parser = clap::value_parser!(u16).range(1..))]
+        keep: Option<u16>,
+    },
+
+    #[command(about = "Roll an alias or data stream over to a new index")]
+    Rollover {
+        #[arg(help = "Name of the alias or data stream to roll over")]
+        target: String,
+        #[arg(long = "max-docs")]
+        #[arg(help = "Only roll over once the index holds at least this many documents")]
+        max_docs: Option<u64>,
+        #[arg(long = "max-size")]
+        #[arg(help = "Only roll over once the primary shards reach this total size (e.g. 50gb)")]
+        max_size: Option<String>,
+        #[arg(long = "max-age")]
+        #[arg(help = "Only roll over once the index reaches this age (e.g. 7d)")]
+        max_age: Option<String>,
+        #[arg(long = "dry-run")]
+        #[arg(help = "Check the conditions without rolling over")]
+        dry_run: bool,
+    },
+
+    #[command(about = "Shrink an index into a new index with fewer primary shards")]
+    Shrink {
+        #[arg(help = "Name of the index to shrink")]
+        source: String,
+        #[arg(help = "Name of the new index")]
+        target: String,
+        #[arg(long = "shards")]
+        #[arg(help = "Number of primary shards in the new index (a factor of the current number)")]
+        #[arg(default_value_t = 1)]
+        shards: u32,
+    },
+
+    #[command(about = "Split an index into a new index with more primary shards")]
+    Split {
+        #[arg(help = "Name of the index to split")]
+        source: String,
+        #[arg(help = "Name of the new index")]
+        target: String,
+        #[arg(long = "shards")]
+        #[arg(
+            help = "Number of primary shards in the new index (a multiple of the current number)"
+        )]
+        shards: u32,
+    },
+
+    #[command(about = "Copy an index into a new index with the same shards")]
+    Clone {
+        #[arg(help = "Name of the index to clone")]
+        source: String,
+        #[arg(help = "Name of the new index")]
+        target: String,
+    },
+
+    #[command(about = "Block writes or other operations on indexes")]
+    Block {
+        #[arg(help = "Name of the index (or pattern, or comma-separated list)")]
+        index: String,
+        #[arg(help = "Kind of block to add")]
+        #[arg(default_value_t = IndexBlock::Write, value_enum)]
+        block: IndexBlock,
+    },
+
+    #[command(
+        about = "Remove blocks from indexes, such as those added at the flood-stage watermark"
+    )]
+    Unblock {
+        #[arg(help = "Name of the index (or pattern, or comma-separated list)")]
+        index: String,
+        #[arg(help = "Kind of block to remove (all kinds if omitted)")]
+        #[arg(value_enum)]
+        block: Option<IndexBlock>,
+    },
+
+    #[command(name = "settings-diff")]
+    #[command(about = "Compare the settings and mappings of two indexes or index templates")]
+    SettingsDiff {
+        #[arg(help = "Name of the first index, or template:NAME for an index template")]
+        a: String,
+        #[arg(help = "Name of the second index, or template:NAME for an index template")]
+        b: String,
+    },
+
+    #[command(about = "Show how data is distributed across data tiers")]
+    Tiers {},
+
+    #[command(about = "Move all shards off a node so that it can be safely stopped")]
+    Drain {
+        #[arg(help = "Name of the node to drain")]
+        node: String,
+        #[arg(short = 'i', long = "interval")]
+        #[arg(help = "Time in seconds between progress checks (default 5s)")]
+        #[arg(default_value_t = 5.0)]
+        interval: f64,
+    },
+
+    #[command(name = "rolling-restart")]
+    #[command(about = "Restart each node in turn, keeping the cluster available")]
+    RollingRestart {
+        #[arg(long = "pause-between")]
+        #[arg(help = "Time to wait between nodes (e.g. 30s, 2m)")]
+        #[arg(value_parser = parse_duration)]
+        #[arg(default_value = "0s")]
+        pause_between: Duration,
+        #[arg(long = "flush")]
+        #[arg(help = "Flush all indexes before each node is restarted")]
+        flush: bool,
+        #[arg(long = "hook")]
+        #[arg(
+            help = "Shell command that restarts a node, with {node} replaced by its name (default: wait for the operator)"
+        )]
+        hook: Option<String>,
+    },
+
+    #[command(about = "List deprecation warnings to resolve before the next major upgrade")]
+    Deprecations {
+        #[arg(help = "Index name or pattern, to check only matching indexes")]
+        index: Option<String>,
+    },
+
+    #[command(name = "preflight-upgrade")]
+    #[command(about = "Check indexes and settings for problems before a major upgrade")]
+    PreflightUpgrade {
+        #[arg(short = 't', long = "target")]
+        #[arg(help = "Version to be upgraded to (e.g. 9.x)")]
+        #[arg(value_parser = parse_major_version)]
+        target: u64,
+    },
+
+    #[command(about = "Inspect and manage cluster coordination")]
+    Cluster {
+        #[command(subcommand)]
+        command: ClusterCommands,
+    },
+
+    #[command(about = "Manage index and component templates, keeping a local history of changes")]
+    #[command(visible_alias = "template-index")]
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+
+    #[command(about = "Inspect escli configuration")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    #[command(about = "Run maintenance operations on indexes")]
+    Maint {
+        #[command(subcommand)]
+        command: MaintCommands,
+    },
+
+    #[command(about = "Create, list, check or delete snapshots")]
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
+    #[command(about = "Manage transforms")]
+    Transform {
+        #[command(subcommand)]
+        command: TransformCommands,
+    },
+
+    #[command(about = "Manage Watcher watches")]
+    Watch {
+        #[command(subcommand)]
+        command: WatchCommands,
+    },
+
+    #[command(about = "Show the type, status and expiry of the cluster license")]
+    License {},
+
+    #[command(about = "Show which features are available under the license, and enabled")]
+    Features {},
+
+    #[command(about = "Show the user or API key that escli is authenticated as")]
+    Whoami {
+        #[arg(short = 'p', long = "privileges")]
+        #[arg(help = "Also show the cluster and index privileges held")]
+        privileges: bool,
+    },
+
+    #[command(about = "Manage users of the native realm")]
+    User {
+        #[command(subcommand)]
+        command: UserCommands,
+    },
+
+    #[command(about = "Manage security roles")]
+    Role {
+        #[command(subcommand)]
+        command: RoleCommands,
+    },
+
+    #[command(about = "Manage stored scripts")]
+    Script {
+        #[command(subcommand)]
+        command: ScriptCommands,
+    },
+
+    #[command(about = "Manage enrich policies used by ingest pipelines")]
+    Enrich {
+        #[command(subcommand)]
+        command: EnrichCommands,
+    },
+
+    #[command(about = "Manage snapshot lifecycle policies")]
+    Slm {
+        #[command(subcommand)]
+        command: SlmCommands,
+    },
+
+    #[command(about = "Restore indexes from a snapshot")]
+    Restore {
+        #[arg(help = "Name of the snapshot repository")]
+        repository: String,
+        #[arg(help = "Name of the snapshot")]
+        snapshot: String,
+        #[arg(long = "indices")]
+        #[arg(help = "Only restore indexes matching this pattern (default all)")]
+        indices: Option<String>,
+        #[arg(long = "rename-pattern", requires = "rename_replacement")]
+        #[arg(help = "Regular expression matching the names of the indexes to rename")]
+        rename_pattern: Option<String>,
+        #[arg(long = "rename-replacement", requires = "rename_pattern")]
+        #[arg(help = "Replacement for renamed indexes, which may use groups such as $1")]
+        rename_replacement: Option<String>,
+        #[arg(short = 'w', long = "wait")]
+        #[arg(help = "Wait until the restore completes")]
+        wait: bool,
+    },
+
+    #[command(about = "List running tasks")]
+    Tasks {
+        #[arg(long = "actions")]
+        #[arg(help = "Only list tasks whose action matches this pattern (e.g. '*reindex*')")]
+        #[arg(value_delimiter = ',')]
+        actions: Vec<String>,
+        #[arg(short = 'd', long = "detailed")]
+        #[arg(help = "Include the description and progress of each task")]
+        detailed: bool,
+    },
+
+    #[command(about = "Manage a single task")]
+    Task {
+        #[command(subcommand)]
+        command: TaskCommands,
+    },
+
+    #[command(about = "Show or change the settings of an index")]
+    Settings {
+        #[command(subcommand)]
+        command: SettingsCommands,
+    },
+
+    #[command(name = "cluster-settings")]
+    #[command(about = "Show or change cluster settings")]
+    ClusterSettings {
+        #[command(subcommand)]
+        command: ClusterSettingsCommands,
+    },
+
+    #[command(about = "List, add or remove remote clusters for cross-cluster search")]
+    Remote {
+        #[command(subcommand)]
+        command: RemoteCommands,
+    },
+
+    #[command(about = "Show or extend the mapping of an index")]
+    Mapping {
+        #[command(subcommand)]
+        command: MappingCommands,
+    },
+
+    #[command(about = "Produce reports on the use of the Elasticsearch service")]
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+
+    #[command(about = "Work with search results saved to a local database")]
+    Local {
+        #[command(subcommand)]
+        command: LocalCommands,
     },
 }
 
-#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
-enum SearchResultFormat {
-    Raw,
-    Table,
+#[derive(Subcommand)]
+enum ReportCommands {
+    #[command(about = "Rank indexes by search and write activity over a sample interval")]
+    Usage {
+        #[arg(help = "Index name or pattern to include in report")]
+        index: Option<String>,
+        #[arg(short = 'i', long = "interval")]
+        #[arg(help = "Time in seconds over which to sample activity (default 60s)")]
+        #[arg(default_value_t = 60.0)]
+        interval: f64,
+    },
 }
 
-#[tokio::main]
-async fn main() -> ExitCode {
-    let args = CommandLine::parse();
-    match SimpleClient::default() {
-        Ok(es) => despatch(&args.command, &es).await,
-        Err(e) => {
-            eprintln!("{}", e);
-            ExitCode::FAILURE
-        }
-    }
+#[derive(Subcommand)]
+enum ClusterCommands {
+    #[command(about = "Show master-eligible nodes, the voting configuration and discovery status")]
+    Quorum {},
+    #[command(about = "Exclude nodes from the voting configuration before removing them")]
+    Exclude {
+        #[arg(help = "Names of the nodes to exclude")]
+        #[arg(required_unless_present = "clear")]
+        nodes: Vec<String>,
+        #[arg(long = "clear")]
+        #[arg(help = "Clear all existing voting configuration exclusions")]
+        #[arg(conflicts_with = "nodes")]
+        clear: bool,
+    },
 }
 
-async fn despatch(command: &Commands, es: &SimpleClient) -> ExitCode {
-    match command {
-        Commands::Ping { count, interval } => ping(es, count, interval).await,
-        Commands::Info {} => print_info(es).await,
-        Commands::ListIndexes {
-            index,
-            all,
-            open,
-            closed,
-        } => print_index_list(es, index, *all, *open, *closed).await,
-        Commands::CreateIndex { index, mappings } => {
-            match &es.create_index(index, mappings).await {
-                Ok(created) => {
-                    println!(
-                        "Created index {} ({}acknowledged)",
-                        created.index,
-                        if created.acknowledged { "" } else { "not " }
-                    );
-                }
-                Err(error) => {
-                    eprintln!("{}", error);
-                    exit(1);
-                }
-            };
-            ExitCode::SUCCESS
-        }
-        Commands::DeleteIndex { index } => {
-            match &es.delete_index(index).await {
-                Ok(deleted) => {
-                    println!(
-                        "Deleted index ({}acknowledged)",
-                        if deleted.acknowledged { "" } else { "not " }
-                    );
-                }
-                Err(error) => {
-                    eprintln!("{}", error);
-                    exit(1);
-                }
-            }
-            ExitCode::SUCCESS
-        }
-        Commands::Load {
-            index,
-            csv_filenames,
-        } => {
-            let summary = &match es.load(index, csv_filenames).await {
-                Ok(it) => it,
-                Err(e) => {
-                    eprintln!("{}", e);
-                    return ExitCode::FAILURE;
-                }
-            };
-            print_bulk_summary(summary);
-            ExitCode::SUCCESS
-        }
-        Commands::Search {
-            index,
-            query,
-            order_by,
-            limit,
-            format,
-        } => {
-            let result = &match es.search(index, query, order_by, limit).await {
-                Ok(it) => it,
-                Err(e) => {
-                    eprintln!("{}", e);
-                    exit(1);
-                }
-            };
-            print_search_result(result, format);
-            ExitCode::SUCCESS
-        }
-    }
+#[derive(Subcommand)]
+enum MappingCommands {
+    #[command(about = "Show the fields mapped in an index")]
+    Get {
+        #[arg(help = "Name of the index (or pattern)")]
+        index: String,
+        #[arg(short = 'f', long = "format")]
+        #[arg(help = "Output format for the mapping")]
+        #[arg(default_value_t = MappingFormat::Table, value_enum)]
+        format: MappingFormat,
+    },
+    #[command(about = "Add fields to the mapping of an existing index")]
+    Put {
+        #[arg(help = "Name of the index")]
+        index: String,
+        #[arg(short = 'm', long = "mapping")]
+        #[arg(
+            help = "Field mapping, as NAME:TYPE[:OPTION=VALUE...] (e.g. title:text:analyzer=english)"
+        )]
+        #[arg(required_unless_present = "mappings_file")]
+        mappings: Vec<FieldSpec>,
+        #[arg(short = 'M', long = "mappings-file")]
+        #[arg(help = "JSON or YAML file containing the mapping (fields given with -m are added)")]
+        mappings_file: Option<String>,
+    },
 }
 
-async fn ping(es: &SimpleClient, count: &Option<usize>, interval: &f64) -> ExitCode {
-    println!("HEAD {}", es.url());
-    let mut seq: usize = 0;
-    loop {
-        seq += 1;
-        let t0 = SystemTime::now();
-        let result = es.ping().await;
-        let elapsed = t0.elapsed().expect("System time error");
-        match result {
-            Ok(status_code) => {
-                println!("{status_code}: seq={seq} time={elapsed:?}");
-            }
-            Err(e) => {
-                println!("{e}: seq={seq} time={elapsed:?}");
-            }
-        }
-        if count.is_some_and(|x| seq >= x) {
-            break;
-        }
-        sleep(Duration::from_secs_f64(*interval));
-    }
-    ExitCode::SUCCESS
+#[derive(Subcommand)]
+enum AliasCommands {
+    #[command(about = "List aliases and the indexes they point to")]
+    Ls {
+        #[arg(help = "Alias name or pattern to include in list")]
+        alias: Option<String>,
+    },
+    #[command(about = "Add an alias to an index")]
+    Add {
+        #[arg(help = "Name of the alias")]
+        alias: String,
+        #[arg(help = "Name of the index")]
+        index: String,
+    },
+    #[command(about = "Remove an alias from an index")]
+    Rm {
+        #[arg(help = "Name of the alias")]
+        alias: String,
+        #[arg(help = "Name of the index")]
+        index: String,
+    },
+    #[command(about = "Atomically move an alias from its current indexes to another")]
+    Switch {
+        #[arg(help = "Name of the alias")]
+        alias: String,
+        #[arg(help = "Name of the index to switch the alias to")]
+        new_index: String,
+    },
 }
 
-async fn print_info(es: &SimpleClient) -> ExitCode {
-    match es.info().await {
-        Ok(info) => {
-            println!("Name: {}", info.name);
-            println!("Cluster Name: {}", info.cluster_name);
-            println!("Cluster UUID: {}", info.cluster_uuid);
-            println!("Version:");
-            println!("  Number: {}", info.version.number);
-            println!("  Build Flavor: {}", info.version.build_flavor);
-            println!("  Build Type: {}", info.version.build_type);
-            println!("  Build Hash: {}", info.version.build_hash);
-            println!("  Build Date: {}", info.version.build_date);
-            println!("  Build Snapshot: {}", info.version.build_snapshot);
-            println!("  Lucene Version: {}", info.version.lucene_version);
-            println!(
-                "  Minimum Wire Compatibility Version: {}",
-                info.version.minimum_wire_compatibility_version
-            );
-            println!(
-                "  Minimum Index Compatibility Version: {}",
-                info.version.minimum_index_compatibility_version
-            );
-            println!("Tagline: {}", info.tagline);
-            ExitCode::SUCCESS
-        }
-        Err(e) => {
-            eprintln!("{}", e);
-            ExitCode::FAILURE
-        }
-    }
+#[derive(Subcommand)]
+enum SettingsCommands {
+    #[command(about = "Show the settings of an index")]
+    Get {
+        #[arg(help = "Name of the index (or pattern)")]
+        index: String,
+        #[arg(long = "include-defaults")]
+        #[arg(help = "Also show default values of settings not explicitly set")]
+        include_defaults: bool,
+    },
+    #[command(about = "Change dynamic settings of an index")]
+    Put {
+        #[arg(help = "Name of the index (or pattern)")]
+        index: String,
+        #[arg(help = "Settings to change, as KEY=VALUE (use VALUE null to reset to the default)")]
+        #[arg(value_parser = parse_setting, required = true)]
+        settings: Vec<(String, String)>,
+    },
 }
 
-async fn print_index_list(
-    es: &SimpleClient,
-    index: &Option<String>,
-    all: bool,
+#[derive(Subcommand)]
+enum ClusterSettingsCommands {
+    #[command(about = "Show settings that differ from their defaults")]
+    Get {
+        #[arg(help = "Only show settings whose names contain this text (e.g. watermark)")]
+        filter: Option<String>,
+    },
+    #[command(about = "Change cluster settings")]
+    Set {
+        #[arg(help = "Settings to change, as KEY=VALUE (use VALUE null to reset to the default)")]
+        #[arg(value_parser = parse_setting, required = true)]
+        settings: Vec<(String, String)>,
+        #[arg(long = "transient", conflicts_with = "persistent")]
+        #[arg(help = "Apply the settings until the next full cluster restart only")]
+        transient: bool,
+        #[arg(long = "persistent")]
+        #[arg(help = "Apply the settings across restarts (the default)")]
+        persistent: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommands {
+    #[command(about = "List index templates, or component templates")]
+    Ls {
+        #[arg(help = "Name or pattern of the templates to list (default all)")]
+        pattern: Option<String>,
+        #[arg(short = 'c', long = "component")]
+        #[arg(help = "List component templates instead of index templates")]
+        component: bool,
+    },
+    #[command(about = "Show the body of an index template, or component template")]
+    Get {
+        #[arg(help = "Name of the template")]
+        name: String,
+        #[arg(short = 'c', long = "component")]
+        #[arg(help = "Show a component template instead of an index template")]
+        component: bool,
+    },
+    #[command(
+        about = "Create or replace an index template (saving any previous version), or component template"
+    )]
+    Put {
+        #[arg(help = "Name of the template")]
+        name: String,
+        #[arg(short = 'f', long = "file")]
+        #[arg(help = "JSON or YAML file containing the template body")]
+        file: String,
+        #[arg(short = 'c', long = "component")]
+        #[arg(help = "Put a component template instead of an index template")]
+        component: bool,
+    },
+    #[command(about = "Delete an index template, or component template")]
+    Rm {
+        #[arg(help = "Name of the template")]
+        name: String,
+        #[arg(short = 'c', long = "component")]
+        #[arg(help = "Delete a component template instead of an index template")]
+        component: bool,
+    },
+    #[command(about = "List the saved previous versions of an index template")]
+    History {
+        #[arg(help = "Name of the index template")]
+        name: String,
+    },
+    #[command(about = "Restore a saved version of an index template (the latest, by default)")]
+    Rollback {
+        #[arg(help = "Name of the index template")]
+        name: String,
+        #[arg(long = "to")]
+        #[arg(help = "Version number to restore, as shown by template history")]
+        to: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    #[command(about = "Check the configuration for mistakes, reporting each problem found")]
+    Validate {
+        #[arg(long = "no-connect")]
+        #[arg(help = "Skip checking that the configured URLs are reachable")]
+        no_connect: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    #[command(about = "List profiles, marking the default and the one in use")]
+    Ls {},
+    #[command(about = "Add a profile, or replace one of the same name")]
+    Add {
+        #[arg(help = "Name of the profile")]
+        name: String,
+        #[arg(long = "url")]
+        #[arg(help = "URL of the Elasticsearch service")]
+        url: String,
+        #[arg(long = "user", requires = "password")]
+        #[arg(help = "User name for authentication (default elastic)")]
+        user: Option<String>,
+        // Secrets are never taken as values, so as to keep them out of
+        // the process list and shell history
+        #[arg(long = "password")]
+        #[arg(
+            help = "Authenticate with a password, asked for on a terminal or read from standard input"
+        )]
+        password: bool,
+        #[arg(long = "api-key", conflicts_with_all = ["user", "password"])]
+        #[arg(
+            help = "Authenticate with an encoded API key, asked for on a terminal or read from standard input"
+        )]
+        api_key: bool,
+        #[arg(long = "auth-cmd", conflicts_with_all = ["user", "password", "api_key"])]
+        #[arg(help = "Shell command which prints an API key or bearer token")]
+        auth_cmd: Option<String>,
+        #[arg(long = "fallback-url")]
+        #[arg(help = "URL of a secondary cluster to read from if this one is unreachable")]
+        fallback_url: Option<String>,
+        #[arg(long = "production")]
+        #[arg(help = "Mark the cluster as production, so that changes require --yes")]
+        production: bool,
+        #[arg(long = "ca-cert", value_name = "FILE")]
+        #[arg(help = "PEM file holding the CA certificate with which to verify the server")]
+        ca_cert: Option<String>,
+        #[arg(long = "insecure", conflicts_with = "ca_cert")]
+        #[arg(help = "Skip verification of the server certificate")]
+        insecure: bool,
+        #[arg(long = "default-output", value_name = "FORMAT", value_enum)]
+        #[arg(help = "Output format to use with this profile unless --output is given")]
+        default_output: Option<OutputFormat>,
+    },
+    #[command(about = "Remove a profile")]
+    Rm {
+        #[arg(help = "Name of the profile")]
+        name: String,
+    },
+    #[command(about = "Make a profile the default")]
+    Use {
+        #[arg(help = "Name of the profile")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContextCommands {
+    #[command(about = "Make a profile the current context, targeted by subsequent commands")]
+    Use {
+        #[arg(help = "Name of the profile")]
+        name: String,
+    },
+    #[command(about = "Clear the current context, falling back to the default profile")]
+    Unset {},
+    #[command(about = "Show the current context, and which profile is in use")]
+    Current {},
+    #[command(
+        about = "Print the profile in use for a shell prompt, quickly and without connecting (prints nothing if none is in use)"
+    )]
+    Prompt {
+        #[arg(long = "format", default_value = "({name})")]
+        #[arg(help = "Template in which {name} is replaced by the profile name")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    #[command(about = "Create a snapshot")]
+    Mk {
+        #[arg(help = "Name of the snapshot repository")]
+        repository: String,
+        #[arg(help = "Name of the snapshot")]
+        snapshot: String,
+        #[arg(long = "indices")]
+        #[arg(help = "Only include indexes matching this pattern (default all)")]
+        indices: Option<String>,
+        #[arg(short = 'w', long = "wait")]
+        #[arg(help = "Show progress until the snapshot completes")]
+        wait: bool,
+    },
+    #[command(about = "List the snapshots in a repository")]
+    Ls {
+        #[arg(help = "Name of the snapshot repository")]
+        repository: String,
+    },
+    #[command(about = "Show the progress of a snapshot, or of all running snapshots")]
+    Status {
+        #[arg(help = "Name of the snapshot repository")]
+        repository: String,
+        #[arg(help = "Name of the snapshot (default all running snapshots)")]
+        snapshot: Option<String>,
+    },
+    #[command(about = "Delete a snapshot")]
+    Rm {
+        #[arg(help = "Name of the snapshot repository")]
+        repository: String,
+        #[arg(help = "Name of the snapshot")]
+        snapshot: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TransformCommands {
+    #[command(about = "List transforms, with their state")]
+    Ls {},
+    #[command(about = "Show the configuration of a transform")]
+    Get {
+        #[arg(help = "ID of the transform")]
+        id: String,
+    },
+    #[command(about = "Create a transform, reading its configuration from a file")]
+    Put {
+        #[arg(help = "ID of the transform")]
+        id: String,
+        #[arg(short = 'f', long = "file")]
+        #[arg(help = "JSON or YAML file containing the transform configuration")]
+        file: String,
+    },
+    #[command(about = "Start a transform")]
+    Start {
+        #[arg(help = "ID of the transform")]
+        id: String,
+    },
+    #[command(about = "Stop a transform")]
+    Stop {
+        #[arg(help = "ID of the transform")]
+        id: String,
+        #[arg(short = 'w', long = "wait")]
+        #[arg(help = "Wait until the transform has stopped")]
+        wait: bool,
+    },
+    #[command(about = "Show the progress and document counts of a transform")]
+    Stats {
+        #[arg(help = "ID of the transform")]
+        id: String,
+    },
+    #[command(about = "Show the documents a transform would produce, as a table")]
+    Preview {
+        #[arg(help = "ID of an existing transform", required_unless_present = "file")]
+        id: Option<String>,
+        #[arg(short = 'f', long = "file", conflicts_with = "id")]
+        #[arg(help = "JSON or YAML file containing the configuration of a new transform")]
+        file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RemoteCommands {
+    #[command(about = "List remote clusters, with their connection status")]
+    Ls {},
+    #[command(about = "Add or update a remote cluster")]
+    Add {
+        #[arg(help = "Alias of the remote cluster")]
+        name: String,
+        #[arg(required = true)]
+        #[arg(help = "Seed nodes (host:port) or, with --proxy, the proxy address")]
+        addresses: Vec<String>,
+        #[arg(long = "proxy")]
+        #[arg(help = "Connect through a single proxy address instead of seed nodes")]
+        proxy: bool,
+        #[arg(long = "skip-unavailable")]
+        #[arg(help = "Skip this cluster in cross-cluster searches if it is unavailable")]
+        skip_unavailable: bool,
+        #[arg(long = "transient")]
+        #[arg(help = "Add the remote cluster until the next full cluster restart only")]
+        transient: bool,
+    },
+    #[command(about = "Remove a remote cluster")]
+    Rm {
+        #[arg(help = "Alias of the remote cluster")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WatchCommands {
+    #[command(about = "List watches, with their schedule and status")]
+    Ls {},
+    #[command(about = "Create or update a watch, reading its definition from a file")]
+    Put {
+        #[arg(help = "ID of the watch")]
+        id: String,
+        #[arg(short = 'f', long = "file")]
+        #[arg(help = "JSON or YAML file containing the watch definition")]
+        file: String,
+    },
+    #[command(about = "Delete a watch")]
+    Rm {
+        #[arg(help = "ID of the watch")]
+        id: String,
+    },
+    #[command(about = "Run a watch now, showing the condition and action results")]
+    Execute {
+        #[arg(help = "ID of the watch")]
+        id: String,
+        #[arg(long = "dry-run")]
+        #[arg(help = "Simulate the actions instead of running them, and do not record the run")]
+        dry_run: bool,
+    },
+    #[command(
+        about = "Acknowledge the actions of a watch, throttling them until its condition resets"
+    )]
+    Ack {
+        #[arg(help = "ID of the watch")]
+        id: String,
+        #[arg(help = "Actions to acknowledge (default all)")]
+        actions: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum UserCommands {
+    #[command(about = "List users, with their roles")]
+    Ls {},
+    #[command(about = "Create or update a user, asking for a password if the user is new")]
+    Put {
+        #[arg(help = "Name of the user")]
+        name: String,
+        #[arg(long = "roles", required = true, value_delimiter = ',')]
+        #[arg(help = "Comma-separated roles to grant (replacing any existing roles)")]
+        roles: Vec<String>,
+        #[arg(long = "full-name")]
+        #[arg(help = "Full name of the user")]
+        full_name: Option<String>,
+        #[arg(long = "email")]
+        #[arg(help = "Email address of the user")]
+        email: Option<String>,
+    },
+    #[command(about = "Delete a user")]
+    Rm {
+        #[arg(help = "Name of the user")]
+        name: String,
+    },
+    #[command(about = "Change the password of a user")]
+    Passwd {
+        #[arg(help = "Name of the user")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RoleCommands {
+    #[command(about = "List roles, with their cluster and index privileges")]
+    Ls {
+        #[arg(short = 'a', long = "all")]
+        #[arg(help = "Include built-in roles")]
+        all: bool,
+    },
+    #[command(about = "Create or replace a role, reading its definition from a file")]
+    Put {
+        #[arg(help = "Name of the role")]
+        name: String,
+        #[arg(short = 'f', long = "file")]
+        #[arg(help = "JSON or YAML file containing the role definition")]
+        file: String,
+    },
+    #[command(about = "Delete a role")]
+    Rm {
+        #[arg(help = "Name of the role")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScriptCommands {
+    #[command(about = "List stored scripts")]
+    Ls {},
+    #[command(about = "Print the source of a stored script")]
+    Get {
+        #[arg(help = "ID of the script")]
+        id: String,
+    },
+    #[command(about = "Create or replace a stored script, reading its source from a file")]
+    Put {
+        #[arg(help = "ID of the script")]
+        id: String,
+        #[arg(short = 'f', long = "file")]
+        #[arg(help = "File containing the script source")]
+        file: String,
+        #[arg(long = "lang")]
+        #[arg(help = "Language of the script (e.g. painless, or mustache for search templates)")]
+        #[arg(default_value = "painless")]
+        lang: String,
+    },
+    #[command(about = "Delete a stored script")]
+    Rm {
+        #[arg(help = "ID of the script")]
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum EnrichCommands {
+    #[command(about = "List policies, with the status of their enrich indexes")]
+    Ls {},
+    #[command(about = "Create a policy (policies cannot be replaced, only deleted)")]
+    Put {
+        #[arg(help = "Name of the policy")]
+        name: String,
+        #[arg(long = "type")]
+        #[arg(help = "How incoming documents are matched to enrich data")]
+        #[arg(default_value_t = EnrichType::Match, value_enum)]
+        policy_type: EnrichType,
+        #[arg(long = "indices", required = true, value_delimiter = ',')]
+        #[arg(help = "Comma-separated source indexes holding the enrich data")]
+        indices: Vec<String>,
+        #[arg(long = "match-field")]
+        #[arg(help = "Field in the source indexes to match incoming documents against")]
+        match_field: String,
+        #[arg(long = "enrich-fields", required = true, value_delimiter = ',')]
+        #[arg(help = "Comma-separated fields to add to matching incoming documents")]
+        enrich_fields: Vec<String>,
+    },
+    #[command(about = "Build a new enrich index for a policy from its source indexes")]
+    Execute {
+        #[arg(help = "Name of the policy")]
+        name: String,
+    },
+    #[command(about = "Delete a policy and its enrich indexes")]
+    Rm {
+        #[arg(help = "Name of the policy")]
+        name: String,
+    },
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum EnrichType {
+    Match,
+    #[value(name = "geo_match")]
+    GeoMatch,
+    Range,
+}
+
+#[derive(Subcommand)]
+enum SlmCommands {
+    #[command(about = "List policies, with the times of their last success and failure")]
+    Ls {},
+    #[command(about = "Create or replace a policy")]
+    Put {
+        #[arg(help = "Name of the policy")]
+        name: String,
+        #[arg(long = "schedule")]
+        #[arg(help = "Cron schedule on which to take snapshots (e.g. '0 30 1 * * ?')")]
+        schedule: String,
+        #[arg(long = "repository")]
+        #[arg(help = "Name of the repository in which to store snapshots")]
+        repository: String,
+        #[arg(long = "snapshot-name")]
+        #[arg(help = "Name of each snapshot, which may use date math (default <NAME-{now/d}>)")]
+        snapshot_name: Option<String>,
+        #[arg(long = "indices")]
+        #[arg(help = "Only include indexes matching this pattern (default all)")]
+        indices: Option<String>,
+        #[arg(long = "expire-after")]
+        #[arg(help = "Delete snapshots older than this (e.g. 30d)")]
+        expire_after: Option<String>,
+        #[arg(long = "min-count")]
+        #[arg(help = "Keep at least this many snapshots, however old")]
+        min_count: Option<u32>,
+        #[arg(long = "max-count")]
+        #[arg(help = "Keep at most this many snapshots")]
+        max_count: Option<u32>,
+    },
+    #[command(about = "Delete a policy (its snapshots are kept)")]
+    Rm {
+        #[arg(help = "Name of the policy")]
+        name: String,
+    },
+    #[command(about = "Take a snapshot now, using a policy")]
+    Execute {
+        #[arg(help = "Name of the policy")]
+        name: String,
+    },
+    #[command(about = "Show whether snapshot lifecycle management is running, and policy health")]
+    Status {},
+}
+
+#[derive(Subcommand)]
+enum TaskCommands {
+    #[command(about = "Cancel a running task")]
+    Cancel {
+        #[arg(help = "ID of the task, as NODE_ID:NUMBER")]
+        task_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MaintCommands {
+    #[command(about = "Merge the segments of indexes, reporting progress until done")]
+    Forcemerge {
+        #[arg(help = "Names of the indexes (or patterns)", required = true)]
+        indexes: Vec<String>,
+        #[arg(long = "max-num-segments")]
+        #[arg(help = "Number of segments to merge each shard down to")]
+        max_num_segments: Option<i64>,
+    },
+    #[command(about = "Refresh indexes, making recent changes visible to search")]
+    Refresh {
+        #[arg(help = "Names of the indexes (or patterns)", required = true)]
+        indexes: Vec<String>,
+    },
+    #[command(about = "Flush indexes, committing the translog to disk")]
+    Flush {
+        #[arg(help = "Names of the indexes (or patterns)", required = true)]
+        indexes: Vec<String>,
+    },
+    #[command(name = "clear-cache")]
+    #[command(about = "Clear the query, request and field data caches of indexes")]
+    ClearCache {
+        #[arg(help = "Names of the indexes (or patterns)", required = true)]
+        indexes: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum IndexBlock {
+    Write,
+    #[value(name = "read_only")]
+    ReadOnly,
+    #[value(name = "read_only_allow_delete")]
+    ReadOnlyAllowDelete,
+}
+
+impl IndexBlock {
+    const ALL: [IndexBlock; 3] = [
+        IndexBlock::Write,
+        IndexBlock::ReadOnly,
+        IndexBlock::ReadOnlyAllowDelete,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            IndexBlock::Write => "write",
+            IndexBlock::ReadOnly => "read_only",
+            IndexBlock::ReadOnlyAllowDelete => "read_only_allow_delete",
+        }
+    }
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum HealthStatus {
+    Green,
+    Yellow,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum MappingFormat {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum LocalCommands {
+    #[command(about = "Run an SQL query against saved search results")]
+    Query {
+        #[arg(help = "SQL query to run (e.g. 'SELECT * FROM \"my-index\"')")]
+        sql: String,
+        #[arg(short = 'd', long = "db")]
+        #[arg(help = "Filename of the local SQLite database")]
+        #[arg(default_value = "results.db")]
+        db: String,
+    },
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum SearchResultFormat {
+    /// Each hit, including its metadata, as JSON
+    Raw,
+    /// A table of the source of each hit, with a column per field
+    Table,
+    /// Each hit, as Rust debug output
+    Debug,
+    /// An array of the source of each hit
+    Json,
+    /// The source of each hit, as JSON on a line of its own
+    Ndjson,
+    /// A sequence of the source of each hit
+    Yaml,
+    /// Comma-separated values, with a column per field
+    Csv,
+    /// Tab-separated values, with a column per field
+    Tsv,
+    /// Each hit as a record of field: value lines, for documents with many fields
+    Vertical,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = CommandLine::parse();
+    if let Err(e) = profile::select(args.profile.as_deref()) {
+        // Commands for checking and fixing the configuration must still run
+        if !matches!(
+            args.command,
+            Commands::Config { .. } | Commands::Profile { .. } | Commands::Context { .. }
+        ) {
+            output::print_error(&format!("Cannot select profile: {e}"));
+            return ExitCode::from(exit_status::CONFIGURATION);
+        }
+    }
+    apply_output_options(&args);
+    let out_file = match start_out_file(&args.out) {
+        Ok(it) => it,
+        Err(exit_code) => return exit_code,
+    };
+    let exit_code = run(&args).await;
+    finish_out_file(out_file, &args.out, exit_code)
+}
+
+/// Sets up output, and what counts as a failure, as chosen by the global
+/// options.
+///
+fn apply_output_options(args: &CommandLine) {
+    output::set_format(output_format(args.output));
+    exit_status::set_fail_on(args.fail_on);
+    if args.jq.is_some() {
+        output::set_format(OutputFormat::Json);
+    }
+    output::set_filter(args.jq.clone());
+    output::set_colour(args.colour);
+    output::set_ascii(args.ascii);
+    output::set_quiet(args.quiet);
+    observe::set_verbosity(args.verbose);
+    output::set_layout(TableLayout {
+        columns: args.columns.clone(),
+        max_col_width: args.max_col_width,
+        wrap: args.wrap,
+    });
+}
+
+/// Redirects stdout to the file given by `--out`, if any.
+///
+fn start_out_file(out: &Option<String>) -> Result<Option<outfile::OutputFile>, ExitCode> {
+    match out {
+        Some(path) => match outfile::OutputFile::start(path) {
+            Ok(it) => Ok(Some(it)),
+            Err(e) => {
+                output::print_error(&format!("Cannot write to {path}: {e}"));
+                Err(ExitCode::FAILURE)
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+/// Moves output redirected by `--out` into place, failing if it cannot be
+/// written.
+///
+fn finish_out_file(
+    out_file: Option<outfile::OutputFile>,
+    out: &Option<String>,
+    exit_code: ExitCode,
+) -> ExitCode {
+    if let Some(out_file) = out_file {
+        if let Err(e) = out_file.finish() {
+            output::print_error(&format!(
+                "Cannot write to {}: {e}",
+                out.as_deref().unwrap_or_default()
+            ));
+            return ExitCode::FAILURE;
+        }
+    }
+    exit_code
+}
+
+/// Chooses the output format: as given by `--output`, otherwise as set in
+/// the profile in use, otherwise a table.
+///
+fn output_format(explicit: Option<OutputFormat>) -> OutputFormat {
+    explicit
+        .or_else(|| {
+            let (_, profile) = profile::active()?;
+            OutputFormat::from_str(profile.output.as_deref()?, true).ok()
+        })
+        .unwrap_or(OutputFormat::Table)
+}
+
+/// Runs the command given, once output has been set up.
+///
+async fn run(args: &CommandLine) -> ExitCode {
+    if let Some(exit_code) = despatch_offline(&args.command) {
+        if output::filter_failed() {
+            return ExitCode::FAILURE;
+        }
+        return exit_code;
+    }
+    if args.strict_schema {
+        client::enable_strict();
+    }
+    if profile::active().is_none() && env::var("ESCLI_URL").is_ok() && !output::quiet() {
+        if let Some(name) = profile::current_context() {
+            eprintln!("Note: ESCLI_URL is set, so current context {name} is not used");
+        }
+    }
+    match SimpleClient::default() {
+        Ok(mut es) => {
+            if uses_fallback(&args.command) {
+                es = fall_back_if_unreachable(es).await;
+            }
+            if args.timings {
+                observe::enable_timings();
+                print_connection_timings(&es).await;
+            }
+            if args.show_headers {
+                observe::enable_headers();
+            }
+            if args.curl {
+                observe::enable_curl(&curl_credentials());
+            }
+            let exit_code = match args.command {
+                Commands::Shell {} => run_shell(&es, args).await,
+                _ => despatch_checked(args, &es).await,
+            };
+            if !(args.no_warnings || args.quiet) {
+                observe::print_warning_summary();
+            }
+            if output::filter_failed() {
+                return ExitCode::FAILURE;
+            }
+            if exit_code == ExitCode::SUCCESS {
+                return exit_status::success_unless_warned(observe::warned());
+            }
+            exit_code
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// Runs a command, first checking that it may run against a production
+/// cluster, and paging its output if required.
+///
+async fn despatch_checked(args: &CommandLine, es: &SimpleClient) -> ExitCode {
+    if is_mutating(&args.command) {
+        let production = is_production();
+        if production || !args.quiet {
+            let profile = profile::active().map(|(name, _)| name.as_str());
+            print_banner(es, profile, production).await;
+        }
+        if production && !args.yes {
+            eprintln!("Refusing to change a production cluster without --yes");
+            return ExitCode::from(exit_status::USAGE);
+        }
+    }
+    let pager = if args.no_pager || !uses_pager(&args.command) {
+        None
+    } else {
+        pager::Pager::start()
+    };
+    let exit_code = despatch(&args.command, es, args.yes).await;
+    if let Some(pager) = pager {
+        pager.finish();
+    }
+    exit_code
+}
+
+/// Runs an interactive session, reading commands with line editing,
+/// history and tab completion, and running each against the same client.
+/// The exit status is that of the last command run.
+///
+async fn run_shell(es: &SimpleClient, startup: &CommandLine) -> ExitCode {
+    let mut editor: Editor<ShellHelper, DefaultHistory> = match Editor::new() {
+        Ok(it) => it,
+        Err(e) => {
+            output::print_error(&e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut helper = ShellHelper::new(CommandLine::command());
+    helper.index_names = shell_index_names(es).await;
+    editor.set_helper(Some(helper));
+    let history = shell::history_path();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+    println!(
+        "Connected to {}. Type help for shell commands, or exit to leave.",
+        es.url()
+    );
+    let mut session = Session::default();
+    let mut exit_code = ExitCode::SUCCESS;
+    loop {
+        let prompt = match session.variables.get("index") {
+            Some(index) => format!("escli ({index})> "),
+            None => "escli> ".to_string(),
+        };
+        let line = match tokio::task::block_in_place(|| editor.readline(&prompt)) {
+            Ok(it) => it,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                output::print_error(&e);
+                exit_code = ExitCode::FAILURE;
+                break;
+            }
+        };
+        let words = match shell::split_words(&line).and_then(|x| session.expand(x)) {
+            Ok(it) if it.is_empty() => continue,
+            Ok(it) => it,
+            Err(e) => {
+                eprintln!("{e}");
+                continue;
+            }
+        };
+        let _ = editor.add_history_entry(line.as_str());
+        match (words[0].as_str(), &words[1..]) {
+            ("exit" | "quit", _) => break,
+            ("set", [name, value @ ..]) if !value.is_empty() => {
+                session.variables.insert(name.clone(), value.join(" "));
+            }
+            ("unset", [name]) => {
+                session.variables.remove(name);
+            }
+            ("vars", []) => {
+                for (name, value) in session.variables.iter() {
+                    println!("{name}={value}");
+                }
+            }
+            ("help", []) => print_shell_help(),
+            (word, _) if shell::BUILTINS.contains(&word) && word != "help" => {
+                eprintln!("Usage: set NAME VALUE, unset NAME, vars, help or exit");
+            }
+            _ => {
+                let helper = editor.helper_mut().expect("helper is set");
+                exit_code = run_shell_command(es, startup, &session, words, helper).await;
+            }
+        }
+    }
+    if let Some(path) = &history {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("Failed to save shell history to {} ({e})", path.display());
+        }
+    }
+    exit_code
+}
+
+fn print_shell_help() {
+    println!("Enter any escli command without the leading 'escli', for example:");
+    println!("  ls");
+    println!("  search $index --format vertical");
+    println!();
+    println!("Shell commands:");
+    println!("  set NAME VALUE  Set a variable, used in commands as $NAME");
+    println!("  unset NAME      Remove a variable");
+    println!("  vars            List variables");
+    println!("  help            Show this help");
+    println!("  exit            Leave the shell (or press Ctrl-D)");
+    println!();
+    println!("Setting 'index' shows it in the prompt, and setting 'output' changes the");
+    println!("default output format. Use 'help COMMAND' for help with a command.");
+}
+
+/// Runs a command entered in the shell, applying the session's output
+/// format, and refreshes the index names used for completion after any
+/// command that may have changed them. Global options given with the
+/// command apply to it alone, on top of those the shell was started with,
+/// except for those that set up the connection, which are rejected.
+///
+async fn run_shell_command(
+    es: &SimpleClient,
+    startup: &CommandLine,
+    session: &Session,
+    words: Vec<String>,
+    helper: &mut ShellHelper,
+) -> ExitCode {
+    let argv = std::iter::once("escli".to_string()).chain(words);
+    let mut args = match CommandLine::try_parse_from(argv) {
+        Ok(it) => it,
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::from(e.exit_code() as u8);
+        }
+    };
+    let session_wide = [
+        ("--profile", args.profile.is_some()),
+        ("--timings", args.timings),
+        ("--show-headers", args.show_headers),
+        ("--curl", args.curl),
+        ("--strict-schema", args.strict_schema),
+        ("--no-warnings", args.no_warnings),
+    ];
+    if let Some((option, _)) = session_wide.iter().find(|(_, given)| *given) {
+        output::print_error(&format!(
+            "{option} applies to the whole session, so cannot be used inside the shell; give it when starting the shell instead"
+        ));
+        return ExitCode::from(exit_status::USAGE);
+    }
+    if let (None, Some(format)) = (args.output, session.variables.get("output")) {
+        match OutputFormat::from_str(format, true) {
+            Ok(it) => args.output = Some(it),
+            Err(e) => {
+                output::print_error(&format!("Invalid output variable: {e}"));
+                return ExitCode::from(exit_status::USAGE);
+            }
+        }
+    }
+    args.output = args.output.or(startup.output);
+    args.quiet |= startup.quiet;
+    args.verbose = args.verbose.max(startup.verbose);
+    args.jq = args.jq.or(startup.jq.clone());
+    if args.columns.is_empty() {
+        args.columns = startup.columns.clone();
+    }
+    args.max_col_width = args.max_col_width.or(startup.max_col_width);
+    args.wrap |= startup.wrap;
+    if args.colour == ColourChoice::Auto {
+        args.colour = startup.colour;
+    }
+    args.ascii |= startup.ascii;
+    if args.fail_on == FailOn::Error {
+        args.fail_on = startup.fail_on;
+    }
+    apply_output_options(&args);
+    let out_file = match start_out_file(&args.out) {
+        Ok(it) => it,
+        Err(exit_code) => return exit_code,
+    };
+    let exit_code = match despatch_offline(&args.command) {
+        Some(exit_code) => exit_code,
+        None => despatch_checked(&args, es).await,
+    };
+    if is_mutating(&args.command) {
+        helper.index_names = shell_index_names(es).await;
+    }
+    let exit_code = finish_out_file(out_file, &args.out, exit_code);
+    apply_output_options(startup);
+    exit_code
+}
+
+async fn shell_index_names(es: &SimpleClient) -> Vec<String> {
+    match es.get_index_list(&["*"], true, true, true).await {
+        Ok(indexes) => indexes.into_iter().map(|x| x.name).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Runs commands that do not require a connection to Elasticsearch, returning
+/// `None` for all other commands.
+///
+fn despatch_offline(command: &Commands) -> Option<ExitCode> {
+    match command {
+        Commands::SuggestMapping {
+            csv_filename,
+            sample_size,
+        } => Some(suggest_mapping(csv_filename, *sample_size)),
+        Commands::Local { command } => Some(despatch_local(command)),
+        // Validation must not depend on creating a client, since the
+        // configuration may be what stops that from working
+        Commands::Config {
+            command: ConfigCommands::Validate { no_connect },
+        } => Some(validate_config(!no_connect)),
+        Commands::Profile { command } => Some(despatch_profile(command)),
+        Commands::Context { command } => Some(despatch_context(command)),
+        _ => None,
+    }
+}
+
+fn despatch_profile(command: &ProfileCommands) -> ExitCode {
+    let mut config = match profile::ConfigFile::load() {
+        Ok(it) => it,
+        Err(e) => {
+            output::print_error(&format!("Cannot read configuration file: {e}"));
+            return ExitCode::from(exit_status::CONFIGURATION);
+        }
+    };
+    match command {
+        ProfileCommands::Ls {} => {
+            if config.profiles.is_empty() {
+                output::print_empty("No profiles");
+                return ExitCode::SUCCESS;
+            }
+            let active = profile::active().map(|(name, _)| name.as_str());
+            let mut rows = vec![];
+            for (name, profile) in config.profiles.iter() {
+                // Secrets are never shown, only how authentication is done
+                let auth = if profile.auth_cmd.is_some() {
+                    "command".to_string()
+                } else if profile.api_key.is_some() {
+                    "api key".to_string()
+                } else if profile.password.is_some() {
+                    format!("user {}", profile.user.as_deref().unwrap_or("elastic"))
+                } else {
+                    "none".to_string()
+                };
+                rows.push(vec![
+                    Value::from(name.as_str()),
+                    Value::from(profile.url.as_str()),
+                    Value::from(auth),
+                    Value::from(profile.production),
+                    Value::from(config.default.as_deref() == Some(name.as_str())),
+                    Value::from(active == Some(name.as_str())),
+                ]);
+            }
+            output::print_rows(
+                &["name", "url", "auth", "production", "default", "active"],
+                &rows,
+            );
+            ExitCode::SUCCESS
+        }
+        ProfileCommands::Add {
+            name,
+            url,
+            user,
+            password,
+            api_key,
+            auth_cmd,
+            fallback_url,
+            production,
+            ca_cert,
+            insecure,
+            default_output,
+        } => {
+            for url in std::iter::once(url).chain(fallback_url.iter()) {
+                if let Err(e) = config::parse_url(url) {
+                    output::print_error(&format!("Invalid URL {url}: {e}"));
+                    return ExitCode::from(exit_status::USAGE);
+                }
+            }
+            let password = match password.then(|| read_secret("Password")) {
+                Some(None) => return ExitCode::FAILURE,
+                Some(Some(it)) => Some(it),
+                None => None,
+            };
+            let api_key = match api_key.then(|| read_secret("API key")) {
+                Some(None) => return ExitCode::FAILURE,
+                Some(Some(it)) => Some(it),
+                None => None,
+            };
+            let replaced = config.profiles.insert(
+                name.clone(),
+                profile::Profile {
+                    url: url.clone(),
+                    user: user.clone(),
+                    password,
+                    api_key,
+                    auth_cmd: auth_cmd.clone(),
+                    fallback_url: fallback_url.clone(),
+                    production: *production,
+                    ca_cert: ca_cert.clone(),
+                    insecure: *insecure,
+                    output: default_output
+                        .and_then(|x| x.to_possible_value())
+                        .map(|x| x.get_name().to_string()),
+                },
+            );
+            if config.default.is_none() {
+                config.default = Some(name.clone());
+            }
+            save_profiles(&config, || {
+                if replaced.is_some() {
+                    format!("Replaced profile {name}")
+                } else {
+                    format!("Added profile {name}")
+                }
+            })
+        }
+        ProfileCommands::Rm { name } => {
+            if config.profiles.remove(name).is_none() {
+                output::print_error(&format!("No such profile: {name}"));
+                return ExitCode::from(exit_status::CONFIGURATION);
+            }
+            if config.default.as_deref() == Some(name.as_str()) {
+                config.default = None;
+            }
+            if profile::current_context().as_ref() == Some(name) {
+                if let Err(e) = profile::set_current_context(None) {
+                    output::print_error(&format!("Cannot clear current context: {e}"));
+                }
+            }
+            save_profiles(&config, || format!("Removed profile {name}"))
+        }
+        ProfileCommands::Use { name } => {
+            if !config.profiles.contains_key(name) {
+                output::print_error(&format!("No such profile: {name}"));
+                return ExitCode::from(exit_status::CONFIGURATION);
+            }
+            config.default = Some(name.clone());
+            save_profiles(&config, || format!("Profile {name} is now the default"))
+        }
+    }
+}
+
+fn despatch_context(command: &ContextCommands) -> ExitCode {
+    match command {
+        ContextCommands::Use { name } => {
+            match profile::ConfigFile::load() {
+                Ok(config) if config.profiles.contains_key(name) => {}
+                Ok(_) => {
+                    output::print_error(&format!("No such profile: {name}"));
+                    return ExitCode::from(exit_status::CONFIGURATION);
+                }
+                Err(e) => {
+                    output::print_error(&format!("Cannot read configuration file: {e}"));
+                    return ExitCode::from(exit_status::CONFIGURATION);
+                }
+            }
+            if let Err(e) = profile::set_current_context(Some(name)) {
+                output::print_error(&format!("Cannot save current context: {e}"));
+                return ExitCode::FAILURE;
+            }
+            println!("Switched to context {name}");
+            if env::var("ESCLI_URL").is_ok() {
+                eprintln!("Note: ESCLI_URL is set, and takes precedence in this environment");
+            }
+            ExitCode::SUCCESS
+        }
+        ContextCommands::Unset {} => match profile::set_current_context(None) {
+            Ok(()) => {
+                println!("Cleared current context");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                output::print_error(&format!("Cannot clear current context: {e}"));
+                ExitCode::FAILURE
+            }
+        },
+        ContextCommands::Current {} => {
+            match profile::current_context() {
+                Some(name) => println!("Current context: {name}"),
+                None => println!("No current context"),
+            }
+            match profile::active() {
+                Some((name, profile)) => println!("Using profile {name} ({})", profile.url),
+                None if env::var("ESCLI_URL").is_ok() => {
+                    println!("Using environment variables (ESCLI_URL)")
+                }
+                None => println!("Using start-local, if found"),
+            }
+            ExitCode::SUCCESS
+        }
+        // This runs on every prompt, so must not fail noisily or connect
+        ContextCommands::Prompt { format } => {
+            if let Some((name, _)) = profile::active() {
+                print!("{}", format.replace("{name}", name));
+            }
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+fn save_profiles(config: &profile::ConfigFile, message: impl FnOnce() -> String) -> ExitCode {
+    match config.save() {
+        Ok(()) => {
+            println!("{}", message());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            output::print_error(&format!("Cannot write configuration file: {e}"));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn despatch_local(command: &LocalCommands) -> ExitCode {
+    match command {
+        LocalCommands::Query { sql, db } => match local::query(db, sql) {
+            Ok((column_names, rows)) => {
+                if rows.is_empty() {
+                    output::print_empty("No rows");
+                } else {
+                    let mut table = Table::with_column_names(column_names);
+                    for row in rows.into_iter() {
+                        table.push_row(row);
+                    }
+                    table.print();
+                }
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                output::print_error(&e);
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+/// Checks whether a command may change data or settings on the cluster.
+///
+fn is_mutating(command: &Commands) -> bool {
+    match command {
+        Commands::CreateIndex { .. }
+        | Commands::DeleteIndex { .. }
+        | Commands::Load { .. }
+        | Commands::Copy { .. }
+        | Commands::Deploy { .. }
+        | Commands::Drain { .. }
+        | Commands::RollingRestart { .. }
+        | Commands::Shrink { .. }
+        | Commands::Split { .. }
+        | Commands::Clone { .. }
+        | Commands::Block { .. }
+        | Commands::Unblock { .. }
+        | Commands::Restore { .. }
+        | Commands::Maint { .. }
+        | Commands::Quickstart { .. } => true,
+        Commands::Rollover { dry_run, .. } => !dry_run,
+        Commands::Task { command } => matches!(command, TaskCommands::Cancel { .. }),
+        Commands::Enrich { command } => !matches!(command, EnrichCommands::Ls { .. }),
+        Commands::User { command } => !matches!(command, UserCommands::Ls { .. }),
+        Commands::Transform { command } => matches!(
+            command,
+            TransformCommands::Put { .. }
+                | TransformCommands::Start { .. }
+                | TransformCommands::Stop { .. }
+        ),
+        Commands::Watch { command } => match command {
+            WatchCommands::Ls {} => false,
+            WatchCommands::Execute { dry_run, .. } => !dry_run,
+            _ => true,
+        },
+        Commands::Role { command } => !matches!(command, RoleCommands::Ls { .. }),
+        Commands::Script { command } => {
+            matches!(
+                command,
+                ScriptCommands::Put { .. } | ScriptCommands::Rm { .. }
+            )
+        }
+        Commands::Slm { command } => matches!(
+            command,
+            SlmCommands::Put { .. } | SlmCommands::Rm { .. } | SlmCommands::Execute { .. }
+        ),
+        Commands::Snapshot { command } => matches!(
+            command,
+            SnapshotCommands::Mk { .. } | SnapshotCommands::Rm { .. }
+        ),
+        Commands::Cluster { command } => matches!(command, ClusterCommands::Exclude { .. }),
+        Commands::Mapping { command } => matches!(command, MappingCommands::Put { .. }),
+        Commands::Settings { command } => matches!(command, SettingsCommands::Put { .. }),
+        Commands::ClusterSettings { command } => {
+            matches!(command, ClusterSettingsCommands::Set { .. })
+        }
+        Commands::Remote { command } => !matches!(command, RemoteCommands::Ls { .. }),
+        Commands::Alias { command } => !matches!(command, AliasCommands::Ls { .. }),
+        Commands::Template { command } => matches!(
+            command,
+            TemplateCommands::Put { .. }
+                | TemplateCommands::Rm { .. }
+                | TemplateCommands::Rollback { .. }
+        ),
+        _ => false,
+    }
+}
+
+/// Prints a table of remote clusters, with the addresses used to connect
+/// to each and whether they are currently connected.
+///
+async fn print_remotes(es: &SimpleClient) -> ExitCode {
+    let remotes = match es.get_remote_info().await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let remotes = remotes.as_object().cloned().unwrap_or_default();
+    if remotes.is_empty() {
+        output::print_empty("No remote clusters");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record([
+        "name",
+        "mode",
+        "addresses",
+        "connected",
+        "connections",
+        "skip_unavailable",
+    ]);
+    for (name, remote) in remotes.iter() {
+        let (addresses, connections) = match remote["mode"].as_str() {
+            Some("proxy") => (
+                data::to_cell(&remote["proxy_address"]),
+                data::to_cell(&remote["num_proxy_sockets_connected"]),
+            ),
+            _ => (
+                remote["seeds"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(data::to_cell)
+                    .collect::<Vec<String>>()
+                    .join(","),
+                data::to_cell(&remote["num_nodes_connected"]),
+            ),
+        };
+        builder.push_record([
+            name.to_string(),
+            data::to_cell(&remote["mode"]),
+            addresses,
+            data::to_cell(&remote["connected"]),
+            connections,
+            data::to_cell(&remote["skip_unavailable"]),
+        ]);
+    }
+    output::print_table(builder);
+    ExitCode::SUCCESS
+}
+
+/// Adds a remote cluster by setting `cluster.remote.<name>.*`, in either
+/// sniff mode (with seed nodes) or proxy mode (with a single address).
+///
+async fn add_remote(
+    es: &SimpleClient,
+    name: &str,
+    addresses: &[String],
+    proxy: bool,
+    skip_unavailable: bool,
+    transient: bool,
+) -> ExitCode {
+    let prefix = format!("cluster.remote.{name}");
+    let mut values = Map::new();
+    if proxy {
+        if addresses.len() != 1 {
+            eprintln!("A remote cluster in proxy mode must have exactly one address");
+            return ExitCode::from(exit_status::USAGE);
+        }
+        values.insert(format!("{prefix}.mode"), json!("proxy"));
+        values.insert(format!("{prefix}.proxy_address"), json!(addresses[0]));
+        values.insert(format!("{prefix}.seeds"), Value::Null);
+    } else {
+        values.insert(format!("{prefix}.mode"), json!("sniff"));
+        values.insert(format!("{prefix}.seeds"), json!(addresses));
+        values.insert(format!("{prefix}.proxy_address"), Value::Null);
+    }
+    values.insert(
+        format!("{prefix}.skip_unavailable"),
+        json!(skip_unavailable),
+    );
+    let section = if transient { "transient" } else { "persistent" };
+    if let Err(e) = es.put_cluster_settings(&json!({ section: values })).await {
+        return fail(&e);
+    }
+    println!("Added remote cluster {name}");
+    print_remotes(es).await
+}
+
+/// Removes a remote cluster by clearing all of its `cluster.remote.<name>.*`
+/// settings, both persistent and transient.
+///
+async fn remove_remote(es: &SimpleClient, name: &str) -> ExitCode {
+    match es.get_remote_info().await {
+        Ok(remotes) if remotes.get(name).is_none() => {
+            eprintln!("No such remote cluster: {name}");
+            return ExitCode::from(exit_status::REQUEST);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            return fail(&e);
+        }
+    }
+    let pattern = format!("cluster.remote.{name}.*");
+    let body = json!({
+        "persistent": { &pattern: null },
+        "transient": { &pattern: null },
+    });
+    match es.put_cluster_settings(&body).await {
+        Ok(_) => {
+            println!("Removed remote cluster {name}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// Adds a block to indexes. The `read_only_allow_delete` block is not
+/// supported by the index block API, so is set as an index setting instead.
+///
+async fn add_block(es: &SimpleClient, index: &str, block: IndexBlock) -> ExitCode {
+    let result = match block {
+        IndexBlock::ReadOnlyAllowDelete => {
+            es.put_index_settings(index, &json!({"index.blocks.read_only_allow_delete": true}))
+                .await
+        }
+        _ => es.add_index_block(&[index], block.name()).await,
+    };
+    match result {
+        Ok(ack) => {
+            println!(
+                "Added {} block to {} ({}acknowledged)",
+                block.name(),
+                index,
+                if ack.acknowledged { "" } else { "not " }
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// Removes a block, or all write-related blocks, from indexes by resetting
+/// the corresponding `index.blocks.*` settings. Note that Elasticsearch
+/// reapplies `read_only_allow_delete` while disk usage remains above the
+/// flood-stage watermark.
+///
+async fn remove_blocks(es: &SimpleClient, index: &str, block: Option<IndexBlock>) -> ExitCode {
+    let blocks: Vec<IndexBlock> = match block {
+        Some(x) => vec![x],
+        None => IndexBlock::ALL.to_vec(),
+    };
+    let mut settings = json!({});
+    for x in blocks.iter() {
+        settings[format!("index.blocks.{}", x.name())] = Value::Null;
+    }
+    let names: Vec<&str> = blocks.iter().map(|x| x.name()).collect();
+    match es.put_index_settings(index, &settings).await {
+        Ok(ack) => {
+            println!(
+                "Removed {} block{} from {} ({}acknowledged)",
+                names.join(", "),
+                if names.len() == 1 { "" } else { "s" },
+                index,
+                if ack.acknowledged { "" } else { "not " }
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// Asks for confirmation before deleting something, unless `--yes` was
+/// given. If stdin is not a terminal, there is nobody to ask, so deletion
+/// is refused without `--yes`.
+///
+fn confirm_deletion(question: &str, yes: bool) -> bool {
+    if yes {
+        return true;
+    }
+    if !std::io::stdin().is_terminal() {
+        eprintln!("Refusing to delete without confirmation; use --yes");
+        return false;
+    }
+    let confirmed = mapping::confirm(&format!("{question} [y/N] "), false);
+    if !confirmed {
+        println!("Nothing deleted");
+    }
+    confirmed
+}
+
+/// Deletes indexes by name or pattern. All names and patterns are resolved
+/// first, and the matching indexes are listed, so that the deletion can be
+/// confirmed before anything is deleted. Confirmation is asked for on the
+/// terminal unless `yes` is set, and is refused if stdin is not a terminal.
+/// With `if_exists`, names that match no index are skipped rather than
+/// treated as failures.
+///
+async fn delete_indexes(
+    es: &SimpleClient,
+    names: &[String],
+    if_exists: bool,
+    yes: bool,
+) -> ExitCode {
+    let mut matched: Vec<IndexDetail> = vec![];
+    for name in names.iter() {
+        match es.get_index_list(&[name], false, true, true).await {
+            Ok(found) if found.is_empty() => println!("No indexes match {name}"),
+            Ok(found) => {
+                for index in found.into_iter() {
+                    if !matched.iter().any(|x| x.name == index.name) {
+                        matched.push(index);
+                    }
+                }
+            }
+            Err(e) if e.is_not_found() && if_exists => println!("No such index: {name}"),
+            Err(e) if e.is_not_found() => {
+                eprintln!("No such index: {name}");
+                return ExitCode::from(exit_status::REQUEST);
+            }
+            Err(e) => {
+                return fail(&e);
+            }
+        }
+    }
+    if matched.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    matched.sort_by(|a, b| a.name.cmp(&b.name));
+    println!("The following indexes will be deleted:");
+    for index in matched.iter() {
+        println!(
+            "  {} ({} docs, {})",
+            index.name,
+            index.docs_count.map_or("?".to_string(), |x| x.to_string()),
+            index.store_size.map_or("?".to_string(), format_bytes)
+        );
+    }
+    let docs = format_thousands(matched.iter().filter_map(|x| x.docs_count).sum());
+    let question = match &matched[..] {
+        [index] => format!("Delete index '{}' containing {docs} docs?", index.name),
+        _ => format!("Delete {} indexes containing {docs} docs?", matched.len()),
+    };
+    if !confirm_deletion(&question, yes) {
+        return ExitCode::FAILURE;
+    }
+    let mut deleted_any = false;
+    let mut failure: Option<u8> = None;
+    for index in matched.iter() {
+        match es.delete_index(&index.name).await {
+            Ok(deleted) => {
+                println!(
+                    "Deleted {} ({}acknowledged)",
+                    index.name,
+                    if deleted.acknowledged { "" } else { "not " }
+                );
+                deleted_any = true;
+            }
+            Err(e)
+                if if_exists
+                    && e.downcast_ref::<client::Error>()
+                        .is_some_and(|x| x.is_not_found()) =>
+            {
+                println!("No such index: {}", index.name)
+            }
+            Err(e) => {
+                eprintln!("Failed to delete {} ({e})", index.name);
+                failure = Some(
+                    e.downcast_ref::<client::Error>()
+                        .map_or(exit_status::FAILURE, exit_status::of),
+                );
+            }
+        }
+    }
+    match failure {
+        Some(_) if deleted_any => ExitCode::from(exit_status::PARTIAL),
+        Some(status) => ExitCode::from(status),
+        None => ExitCode::SUCCESS,
+    }
+}
+
+/// Checks the environment variables and any _start-local_ `.env` files
+/// that escli would read, printing each problem found in the style of a
+/// compiler diagnostic. Fails if any errors are found, or any warnings
+/// with `--fail-on warn`.
+///
+fn validate_config(connect: bool) -> ExitCode {
+    let mut problems = config::check_env_vars();
+    let mut found = env::var("ESCLI_URL").is_ok();
+    if let Some(path) = profile::path().filter(|x| x.exists()) {
+        found = true;
+        problems.extend(config::check_config_file(&path));
+    }
+    problems.extend(config::check_current_context());
+    for dir in [".", "elastic-start-local"] {
+        let path = std::path::Path::new(dir).join(".env");
+        if path.exists() {
+            found = true;
+            problems.extend(config::check_start_local(&path));
+        }
+    }
+    if !found {
+        println!(
+            "No configuration found: add a profile, set ESCLI_URL, or run from a start-local directory"
+        );
+        return ExitCode::from(exit_status::CONFIGURATION);
+    }
+    if connect {
+        match profile::active() {
+            Some((name, profile)) => {
+                let source = format!("profile {name}");
+                let urls = [Some(&profile.url), profile.fallback_url.as_ref()];
+                for (key, url) in ["url", "fallback_url"].iter().zip(urls) {
+                    if let Some(url) = url.and_then(|x| config::parse_url(x).ok()) {
+                        problems.extend(config::check_reachable(&source, key, &url));
+                    }
+                }
+            }
+            None => {
+                for name in ["ESCLI_URL", "ESCLI_FALLBACK_URL"] {
+                    if let Some(url) = env::var(name).ok().and_then(|x| config::parse_url(&x).ok())
+                    {
+                        problems.extend(config::check_reachable("environment", name, &url));
+                    }
+                }
+            }
+        }
+    }
+    for problem in problems.iter() {
+        println!("{problem}");
+    }
+    let errors = problems
+        .iter()
+        .filter(|x| x.severity == config::Severity::Error)
+        .count();
+    let warnings = problems.len() - errors;
+    if problems.is_empty() {
+        println!("Configuration OK");
+    } else {
+        println!("{errors} error(s), {warnings} warning(s)");
+    }
+    if errors > 0 {
+        ExitCode::from(exit_status::CONFIGURATION)
+    } else {
+        exit_status::success_unless_warned(warnings > 0)
+    }
+}
+
+/// Checks whether a command's output may be paged. Commands that change
+/// data may ask for confirmation, and those that refresh or report over an
+/// interval need the terminal to themselves, so are never paged.
+///
+fn uses_pager(command: &Commands) -> bool {
+    !is_mutating(command)
+        && !matches!(
+            command,
+            Commands::Ping { .. }
+                | Commands::Shell { .. }
+                | Commands::ListIndexes { watch: Some(_), .. }
+                | Commands::Health { watch: Some(_), .. }
+                | Commands::Stats { watch: Some(_), .. }
+                | Commands::Recovery { watch: Some(_), .. }
+                | Commands::Report { .. }
+        )
+}
+
+/// Checks whether a command may be served from the fallback cluster when the
+/// primary is unreachable. Only read-only commands qualify, other than `ping`,
+/// which exists to check the primary itself.
+///
+fn uses_fallback(command: &Commands) -> bool {
+    !is_mutating(command) && !matches!(command, Commands::Ping { .. })
+}
+
+/// Switches to the fallback cluster given by the profile in use or by
+/// `ESCLI_FALLBACK_URL` if one is configured and the primary cannot be
+/// reached. A notice is printed to
+/// stderr, so that output served from the secondary is clearly labelled
+/// without disturbing anything piped from stdout.
+///
+async fn fall_back_if_unreachable(es: SimpleClient) -> SimpleClient {
+    let fallback = match SimpleClient::fallback() {
+        Some(Ok(fallback)) => fallback,
+        Some(Err(e)) => {
+            eprintln!("Ignoring fallback cluster: {e}");
+            return es;
+        }
+        None => return es,
+    };
+    match es.ping().await {
+        Err(e) => {
+            eprintln!("Primary cluster at {} is unreachable ({e})", es.url());
+            eprintln!(
+                "*** Serving from SECONDARY cluster at {} ***",
+                fallback.url()
+            );
+            fallback
+        }
+        Ok(_) => es,
+    }
+}
+
+/// Returns curl options that stand in for the credentials in use, naming
+/// the environment variable that holds them rather than their values.
+///
+fn curl_credentials() -> String {
+    if let Some((_, profile)) = profile::active() {
+        return if profile.auth_cmd.is_some() || profile.api_key.is_some() {
+            "-H \"Authorization: ApiKey $API_KEY\"".to_string()
+        } else {
+            format!(
+                "-u \"{}:$PASSWORD\"",
+                profile.user.as_deref().unwrap_or("elastic")
+            )
+        };
+    }
+    let set = |name: &str| env::var(name).is_ok_and(|x| !x.is_empty());
+    if set("ESCLI_API_KEY") {
+        "-H \"Authorization: ApiKey $ESCLI_API_KEY\"".to_string()
+    } else if set("ESCLI_PASSWORD") {
+        "-u \"${ESCLI_USER:-elastic}:$ESCLI_PASSWORD\"".to_string()
+    } else if set("ESCLI_URL") {
+        "-H \"Authorization: ApiKey $API_KEY\"".to_string()
+    } else {
+        "-H \"Authorization: ApiKey $ES_LOCAL_API_KEY\"".to_string()
+    }
+}
+
+/// Checks whether the target cluster has been marked as production, either
+/// in the profile in use or by setting `ESCLI_PRODUCTION` to `true`.
+///
+fn is_production() -> bool {
+    profile::active().is_some_and(|(_, profile)| profile.production)
+        || env::var("ESCLI_PRODUCTION")
+            .is_ok_and(|x| matches!(x.to_lowercase().as_str(), "true" | "yes" | "1"))
+}
+
+/// Prints a banner to stderr naming the cluster about to be changed, and
+/// the profile or context in use, with a prominent tag if it is a
+/// production cluster, so that commands run in the wrong terminal are more
+/// likely to be noticed.
+///
+async fn print_banner(es: &SimpleClient, profile: Option<&str>, production: bool) {
+    let cluster_name = match es.info().await {
+        Ok(info) => info.cluster_name,
+        Err(_) => es.url().to_string(),
+    };
+    let cluster_name = match profile {
+        Some(profile) => format!("{profile}: {cluster_name}"),
+        None => cluster_name,
+    };
+    let colour = output::colour_stderr();
+    let mut banner = if colour {
+        format!("\x1b[1;36m[{cluster_name}]\x1b[0m")
+    } else {
+        format!("[{cluster_name}]")
+    };
+    if production {
+        banner.push_str(if colour {
+            " \x1b[1;37;41m PRODUCTION \x1b[0m"
+        } else {
+            " PRODUCTION"
+        });
+    }
+    eprintln!("{banner}");
+}
+
+/// Prints the time taken by each phase of making a fresh connection to the
+/// service, to stderr so as not to interfere with command output.
+///
+async fn print_connection_timings(es: &SimpleClient) {
+    match timing::probe(es.url()).await {
+        Ok(phases) => {
+            eprintln!("Connection to {}", es.url());
+            for (phase, elapsed) in phases.iter() {
+                eprintln!("  {:<14}{:.1?}", phase, elapsed);
+            }
+        }
+        Err(e) => eprintln!("Failed to time connection to {} ({e})", es.url()),
+    }
+}
+
+async fn despatch(command: &Commands, es: &SimpleClient, yes: bool) -> ExitCode {
+    match command {
+        Commands::Ping {
+            count,
+            interval,
+            compare,
+            save,
+            until_available,
+            timeout,
+            concurrent,
+            request_timeout,
+        } => {
+            let options = PingOptions {
+                count: *count,
+                interval: Duration::from_secs_f64(*interval),
+                concurrent: concurrent.get(),
+                request_timeout: *request_timeout,
+                until_available: *until_available,
+                timeout: *timeout,
+            };
+            ping(es, &options, compare, save).await
+        }
+        Commands::Info {} => print_info(es).await,
+        Commands::ListIndexes {
+            index,
+            all,
+            open,
+            closed,
+            long,
+            watch,
+        } => print_index_list(es, index, *all, *open, *closed, *long, watch).await,
+        Commands::CreateIndex {
+            index,
+            mappings,
+            mappings_file,
+            like,
+            shards,
+            replicas,
+            refresh_interval,
+            settings,
+            interactive,
+            wait_for_active_shards,
+            timeout,
+            if_not_exists,
+        } => {
+            let mut body = match (mappings_file, like) {
+                (Some(path), _) => match mapping::from_file(path) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        output::print_error(&e);
+                        return ExitCode::from(exit_status::USAGE);
+                    }
+                },
+                (None, Some(other)) => match get_settings_and_mappings(es, other).await {
+                    Ok((settings, mappings)) => {
+                        let settings: Map<String, Value> = settings
+                            .into_iter()
+                            .filter(|(key, _)| {
+                                !UNCOPIABLE_SETTINGS.iter().any(|x| key.starts_with(x))
+                            })
+                            .collect();
+                        json!({"settings": settings, "mappings": mappings})
+                    }
+                    Err(e) => {
+                        return fail(&e);
+                    }
+                },
+                (None, None) => json!({"mappings": {"properties": {}}}),
+            };
+            mapping::add_specs(&mut body, mappings);
+            if let Some(x) = shards {
+                body["settings"]["index.number_of_shards"] = json!(x);
+            }
+            if let Some(x) = replicas {
+                body["settings"]["index.number_of_replicas"] = json!(x);
+            }
+            if let Some(x) = refresh_interval {
+                body["settings"]["index.refresh_interval"] = json!(x);
+            }
+            for (key, value) in settings.iter() {
+                body["settings"][key] = json!(value);
+            }
+            if *interactive {
+                body = match mapping::build_interactively(index, body) {
+                    Some(it) => it,
+                    None => {
+                        println!("Index not created");
+                        return ExitCode::SUCCESS;
+                    }
+                };
+            }
+            match &es
+                .create_index(index, &body, wait_for_active_shards, timeout)
+                .await
+            {
+                Ok(created) => {
+                    if created.acknowledged && created.shards_acknowledged {
+                        println!("Created index {} (acknowledged)", created.index);
+                    } else if created.acknowledged {
+                        println!("Created index {} (acknowledged)", created.index);
+                        println!(
+                            "Timed out waiting for the required number of active shards; \
+                             the index exists but may not yet be available for use"
+                        );
+                    } else {
+                        println!("Created index {} (not acknowledged)", created.index);
+                        println!(
+                            "Timed out waiting for the cluster to acknowledge the request; \
+                             the index may still be created, try a longer --timeout"
+                        );
+                    }
+                }
+                Err(error) if *if_not_exists && error.is_already_exists() => {
+                    println!("Index {index} already exists");
+                }
+                Err(error) => return fail(error),
+            };
+            ExitCode::SUCCESS
+        }
+        Commands::DeleteIndex { indexes, if_exists } => {
+            delete_indexes(es, indexes, *if_exists, yes).await
+        }
+        Commands::Load {
+            index,
+            csv_filenames,
+            xlsx_filenames,
+            sheet,
+            batch_size,
+            headers,
+            max_docs_per_sec,
+            max_bytes_per_sec,
+            retries,
+            retry_backoff,
+            validate,
+            strict,
+            auto_create,
+            sample_size,
+            geo_points,
+            geo_points_as_strings,
+            routing_field,
+            dedupe_by,
+            dedupe_hash,
+        } => {
+            if *auto_create {
+                if let Err(e) =
+                    auto_create_index(es, index, csv_filenames, headers, *sample_size, geo_points)
+                        .await
+                {
+                    return exit_status::fail_any(&*e);
+                }
+            }
+            if *validate || *strict {
+                match validate_load(es, index, csv_filenames, headers, geo_points).await {
+                    Ok(0) => {}
+                    Ok(problems) => {
+                        eprintln!("Validation found {} problems", problems);
+                        if *strict {
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                    Err(e) => return exit_status::fail_any(&*e),
+                }
+            }
+            let interrupted = Arc::new(AtomicBool::new(false));
+            tokio::spawn(watch_for_interrupt(interrupted.clone()));
+            let options = LoadOptions {
+                batch_size: *batch_size,
+                headers: headers.clone(),
+                max_docs_per_sec: *max_docs_per_sec,
+                max_bytes_per_sec: max_bytes_per_sec.map(|x| x as f64),
+                retries: *retries,
+                retry_backoff: *retry_backoff,
+                geo_points: geo_points.clone(),
+                geo_points_as_strings: *geo_points_as_strings,
+                routing_field: routing_field.clone(),
+                dedupe: if *dedupe_hash {
+                    Some(Dedupe::AllFields)
+                } else if !dedupe_by.is_empty() {
+                    Some(Dedupe::Fields(dedupe_by.clone()))
+                } else {
+                    None
+                },
+            };
+            let mut inputs: Vec<LoadSource> = csv_filenames
+                .iter()
+                .map(|filename| LoadSource::Csv(filename.to_owned()))
+                .collect();
+            inputs.extend(xlsx_filenames.iter().map(|filename| LoadSource::Xlsx {
+                location: filename.to_owned(),
+                sheet: sheet.to_owned(),
+            }));
+            let summary = &match es.load(index, &inputs, &options, &interrupted).await {
+                Ok(it) => it,
+                Err(e) => return exit_status::fail_any(&*e),
+            };
+            print_bulk_summary(summary)
+        }
+        Commands::Copy {
+            source,
+            destination,
+            query,
+            dest_url,
+            dest_api_key,
+            batch_size,
+        } => {
+            let remote = match (dest_url, dest_api_key) {
+                (Some(url), Some(api_key)) => match Url::parse(url) {
+                    Ok(url) => match SimpleClient::new(
+                        url,
+                        Credentials::EncodedApiKey(api_key.to_string()),
+                    ) {
+                        Ok(it) => Some(it),
+                        Err(e) => return fail(&e),
+                    },
+                    Err(e) => {
+                        eprintln!("failed to parse destination URL ({e})");
+                        return ExitCode::from(exit_status::USAGE);
+                    }
+                },
+                _ => None,
+            };
+            let interrupted = Arc::new(AtomicBool::new(false));
+            tokio::spawn(watch_for_interrupt(interrupted.clone()));
+            let summary = &match es
+                .copy(
+                    source,
+                    remote.as_ref().unwrap_or(es),
+                    destination,
+                    query,
+                    *batch_size,
+                    &interrupted,
+                )
+                .await
+            {
+                Ok(it) => it,
+                Err(e) => return exit_status::fail_any(&*e),
+            };
+            print_bulk_summary(summary)
+        }
+        Commands::Export {
+            index,
+            query,
+            output_file,
+            flatten,
+            batch_size,
+            slices,
+        } => match export(
+            es,
+            index,
+            query,
+            output_file,
+            *flatten,
+            *batch_size,
+            *slices as usize,
+        )
+        .await
+        {
+            Ok(count) => {
+                if output_file.is_some() {
+                    println!("Exported {} documents", count);
+                }
+                ExitCode::SUCCESS
+            }
+            Err(e) => exit_status::fail_any(&*e),
+        },
+        Commands::SuggestMapping {
+            csv_filename,
+            sample_size,
+        } => suggest_mapping(csv_filename, *sample_size),
+        Commands::Local { command } => despatch_local(command),
+        Commands::Config {
+            command: ConfigCommands::Validate { no_connect },
+        } => validate_config(!no_connect),
+        Commands::Profile { command } => despatch_profile(command),
+        Commands::Context { command } => despatch_context(command),
+        Commands::FieldUsage { index } => print_field_usage(es, index).await,
+        Commands::Tiers {} => print_tiers(es).await,
+        Commands::SettingsDiff { a, b } => print_settings_diff(es, a, b).await,
+        Commands::Deploy {
+            alias,
+            new_index,
+            verify_count,
+            keep,
+        } => match deploy(es, alias, new_index, *verify_count, *keep).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => exit_status::fail_any(&*e),
+        },
+        Commands::Rollover {
+            target,
+            max_docs,
+            max_size,
+            max_age,
+            dry_run,
+        } => {
+            let mut conditions = json!({});
+            if let Some(x) = max_docs {
+                conditions["max_docs"] = json!(x);
+            }
+            if let Some(x) = max_size {
+                conditions["max_size"] = json!(x);
+            }
+            if let Some(x) = max_age {
+                conditions["max_age"] = json!(x);
+            }
+            match es.rollover(target, &conditions, *dry_run).await {
+                Ok(rollover) => {
+                    print_rollover(target, &rollover);
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            }
+        }
+        Commands::Quickstart {} => quickstart::run(es).await,
+        Commands::Shell {} => {
+            eprintln!("Already in a shell");
+            ExitCode::from(exit_status::USAGE)
+        }
+        Commands::Segments { index } => print_segments(es, index).await,
+        Commands::Shards { index } => print_shards(es, index.as_deref().unwrap_or("*")).await,
+        Commands::AllocationExplain {
+            index,
+            shard,
+            primary,
+        } => {
+            let target = index.as_deref().map(|x| (x, *shard, *primary));
+            match es.explain_allocation(target).await {
+                Ok(explanation) => {
+                    print_allocation_explanation(&explanation);
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            }
+        }
+        Commands::Recovery {
+            index,
+            active_only,
+            watch,
+        } => print_recovery(es, index.as_deref().unwrap_or("*"), *active_only, watch).await,
+        Commands::Stats { index, watch } => print_index_stats(es, index, watch).await,
+        Commands::Status { terse, cache } => print_status(es, *terse, cache).await,
+        Commands::Health {
+            wait_for,
+            timeout,
+            watch: None,
+        } => print_health(es, *wait_for, timeout).await,
+        Commands::Health {
+            watch: Some(interval),
+            ..
+        } => watch_health(es, *interval).await,
+        Commands::Nodes {} => print_nodes(es).await,
+        Commands::PendingTasks {} => print_pending_tasks(es).await,
+        Commands::ClusterStats {} => print_cluster_stats(es).await,
+        Commands::NodeStats { node, metrics } => print_node_stats(es, node, metrics).await,
+        Commands::Shrink {
+            source,
+            target,
+            shards,
+        } => resize(es, Resize::Shrink, source, target, Some(*shards)).await,
+        Commands::Split {
+            source,
+            target,
+            shards,
+        } => resize(es, Resize::Split, source, target, Some(*shards)).await,
+        Commands::Clone { source, target } => resize(es, Resize::Clone, source, target, None).await,
+        Commands::Block { index, block } => add_block(es, index, *block).await,
+        Commands::Unblock { index, block } => remove_blocks(es, index, *block).await,
+        Commands::Drain { node, interval } => drain(es, node, *interval).await,
+        Commands::Deprecations { index } => print_deprecations(es, index.as_deref()).await,
+        Commands::PreflightUpgrade { target } => preflight_upgrade(es, *target).await,
+        Commands::RollingRestart {
+            pause_between,
+            flush,
+            hook,
+        } => match rolling_restart(es, *pause_between, *flush, hook).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => exit_status::fail_any(&*e),
+        },
+        Commands::Cluster { command } => match command {
+            ClusterCommands::Quorum {} => print_quorum(es).await,
+            ClusterCommands::Exclude { nodes, clear } => {
+                let node_names: Vec<&str> = nodes.iter().map(|x| x.as_str()).collect();
+                match es.set_voting_config_exclusions(&node_names).await {
+                    Ok(()) if *clear => {
+                        println!("Cleared voting configuration exclusions");
+                        ExitCode::SUCCESS
+                    }
+                    Ok(()) => {
+                        println!(
+                            "Excluded {} from the voting configuration",
+                            node_names.join(", ")
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+        },
+        Commands::Alias { command } => {
+            let result = match command {
+                AliasCommands::Ls { alias } => {
+                    return print_alias_list(es, alias.as_deref().unwrap_or("*")).await;
+                }
+                AliasCommands::Add { alias, index } => es
+                    .update_aliases(json!([{"add": {"index": index, "alias": alias}}]))
+                    .await
+                    .map(|updated| {
+                        println!(
+                            "Added alias {alias} to {index} ({}acknowledged)",
+                            if updated.acknowledged { "" } else { "not " }
+                        )
+                    })
+                    .map_err(Box::from),
+                AliasCommands::Rm { alias, index } => es
+                    .update_aliases(json!([{"remove": {"index": index, "alias": alias}}]))
+                    .await
+                    .map(|updated| {
+                        println!(
+                            "Removed alias {alias} from {index} ({}acknowledged)",
+                            if updated.acknowledged { "" } else { "not " }
+                        )
+                    })
+                    .map_err(Box::from),
+                AliasCommands::Switch { alias, new_index } => match es.get_alias(alias).await {
+                    Ok(current) => {
+                        let old_indexes = alias_targets(&current, new_index);
+                        switch_alias(es, alias, new_index, &old_indexes).await
+                    }
+                    Err(e) => Err(Box::from(e)),
+                },
+            };
+            match result {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => exit_status::fail_any(&*e),
+            }
+        }
+        Commands::Template { command } => match command {
+            TemplateCommands::Ls { pattern, component } => {
+                print_template_list(es, *component, pattern.as_deref().unwrap_or("*")).await
+            }
+            TemplateCommands::Get { name, component } => {
+                match es.list_templates(*component, name).await {
+                    Ok(templates) if templates.is_empty() => {
+                        eprintln!("No template named {name}");
+                        ExitCode::from(exit_status::REQUEST)
+                    }
+                    Ok(templates) => {
+                        for (_, body) in templates.iter() {
+                            output::print_json(body, false);
+                        }
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            TemplateCommands::Put {
+                name,
+                file,
+                component,
+            } => match template::read_body(file) {
+                Ok(body) if *component => match es.put_component_template(name, &body).await {
+                    Ok(response) => {
+                        println!(
+                            "Put component template {name} ({}acknowledged)",
+                            if response.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                },
+                Ok(body) => replace_template(es, name, &body).await,
+                Err(e) => {
+                    output::print_error(&e);
+                    ExitCode::from(exit_status::USAGE)
+                }
+            },
+            TemplateCommands::Rm { name, component }
+                if !confirm_deletion(
+                    &format!(
+                        "Delete {} template '{name}'?",
+                        if *component { "component" } else { "index" }
+                    ),
+                    yes,
+                ) =>
+            {
+                ExitCode::FAILURE
+            }
+            TemplateCommands::Rm { name, component } => {
+                match es.delete_template(*component, name).await {
+                    Ok(response) => {
+                        println!(
+                            "Deleted {} template {name} ({}acknowledged)",
+                            if *component { "component" } else { "index" },
+                            if response.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            TemplateCommands::History { name } => print_template_history(es, name),
+            TemplateCommands::Rollback { name, to } => {
+                let versions = match template::history(es.url(), name) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        output::print_error(&e);
+                        return ExitCode::FAILURE;
+                    }
+                };
+                let version = match to {
+                    Some(number) => versions.iter().find(|x| x.number == *number),
+                    None => versions.last(),
+                };
+                match version.map(|x| (x.number, x.body())) {
+                    Some((number, Ok(body))) => {
+                        println!("Restoring version {number} of {name}");
+                        replace_template(es, name, &body).await
+                    }
+                    Some((_, Err(e))) => {
+                        output::print_error(&e);
+                        ExitCode::FAILURE
+                    }
+                    None => {
+                        eprintln!("No saved version of {name} to restore");
+                        ExitCode::from(exit_status::USAGE)
+                    }
+                }
+            }
+        },
+        Commands::Tasks { actions, detailed } => print_tasks(es, actions, *detailed).await,
+        Commands::Whoami { privileges } => print_whoami(es, *privileges).await,
+        Commands::License {} => print_license(es).await,
+        Commands::Transform { command } => match command {
+            TransformCommands::Ls {} => print_transforms(es).await,
+            TransformCommands::Get { id } => match es.get_transforms(Some(id)).await {
+                Ok(response) => {
+                    output::print_json(&response["transforms"][0], false);
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+            TransformCommands::Put { id, file } => {
+                let body = match template::read_body(file) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        output::print_error(&e);
+                        return ExitCode::from(exit_status::USAGE);
+                    }
+                };
+                match es.put_transform(id, &body).await {
+                    Ok(ack) => {
+                        println!(
+                            "Created transform {id} ({}acknowledged); start it with escli transform start {id}",
+                            if ack.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            TransformCommands::Start { id } => match es.start_transform(id).await {
+                Ok(_) => {
+                    println!("Started transform {id}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+            TransformCommands::Stop { id, wait } => match es.stop_transform(id, *wait).await {
+                Ok(_) if *wait => {
+                    println!("Stopped transform {id}");
+                    ExitCode::SUCCESS
+                }
+                Ok(_) => {
+                    println!("Stopping transform {id}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+            TransformCommands::Stats { id } => print_transform_stats(es, id).await,
+            TransformCommands::Preview { id, file } => {
+                let body = match file {
+                    Some(path) => match template::read_body(path) {
+                        Ok(it) => it,
+                        Err(e) => {
+                            output::print_error(&e);
+                            return ExitCode::from(exit_status::USAGE);
+                        }
+                    },
+                    None => json!({}),
+                };
+                match es.preview_transform(id.as_deref(), &body).await {
+                    Ok(preview) => {
+                        print_documents(
+                            preview["preview"]
+                                .as_array()
+                                .map(|x| x.as_slice())
+                                .unwrap_or_default(),
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+        },
+        Commands::Watch { command } => match command {
+            WatchCommands::Ls {} => print_watches(es).await,
+            WatchCommands::Put { id, file } => {
+                let body = match template::read_body(file) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        output::print_error(&e);
+                        return ExitCode::from(exit_status::USAGE);
+                    }
+                };
+                match es.put_watch(id, &body).await {
+                    Ok(response) if response["created"].as_bool().unwrap_or(false) => {
+                        println!("Created watch {id}");
+                        ExitCode::SUCCESS
+                    }
+                    Ok(response) => {
+                        println!("Updated watch {id} (version {})", response["_version"]);
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            WatchCommands::Rm { id }
+                if !confirm_deletion(&format!("Delete watch '{id}'?"), yes) =>
+            {
+                ExitCode::FAILURE
+            }
+            WatchCommands::Rm { id } => match es.delete_watch(id).await {
+                Ok(response) if response["found"].as_bool().unwrap_or(false) => {
+                    println!("Deleted watch {id}");
+                    ExitCode::SUCCESS
+                }
+                Ok(_) => {
+                    eprintln!("No such watch: {id}");
+                    ExitCode::from(exit_status::REQUEST)
+                }
+                Err(e) => fail(&e),
+            },
+            WatchCommands::Execute { id, dry_run } => match es.execute_watch(id, *dry_run).await {
+                Ok(response) => {
+                    print_watch_record(&response["watch_record"]);
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+            WatchCommands::Ack { id, actions } => {
+                let actions: Vec<&str> = actions.iter().map(|x| x.as_str()).collect();
+                match es.ack_watch(id, &actions).await {
+                    Ok(response) => {
+                        for (action, status) in response["status"]["actions"]
+                            .as_object()
+                            .into_iter()
+                            .flatten()
+                        {
+                            println!("{action}: {}", data::to_cell(&status["ack"]["state"]));
+                        }
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+        },
+        Commands::Features {} => print_features(es).await,
+        Commands::User { command } => match command {
+            UserCommands::Ls {} => print_users(es).await,
+            UserCommands::Put {
+                name,
+                roles,
+                full_name,
+                email,
+            } => put_user(es, name, roles, full_name, email).await,
+            UserCommands::Rm { name }
+                if !confirm_deletion(&format!("Delete user '{name}'?"), yes) =>
+            {
+                ExitCode::FAILURE
+            }
+            UserCommands::Rm { name } => match es.delete_user(name).await {
+                Ok(response) if response["found"].as_bool().unwrap_or(false) => {
+                    println!("Deleted user {name}");
+                    ExitCode::SUCCESS
+                }
+                Ok(_) => {
+                    eprintln!("No such user: {name}");
+                    ExitCode::from(exit_status::REQUEST)
+                }
+                Err(e) => fail(&e),
+            },
+            UserCommands::Passwd { name } => {
+                let Some(password) = read_new_password(name) else {
+                    return ExitCode::FAILURE;
+                };
+                match es.change_password(name, &password).await {
+                    Ok(()) => {
+                        println!("Changed password for {name}");
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+        },
+        Commands::Role { command } => match command {
+            RoleCommands::Ls { all } => print_roles(es, *all).await,
+            RoleCommands::Put { name, file } => {
+                let body = match template::read_body(file) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        output::print_error(&e);
+                        return ExitCode::from(exit_status::USAGE);
+                    }
+                };
+                match es.put_role(name, &body).await {
+                    Ok(response) => {
+                        if response["role"]["created"].as_bool().unwrap_or(false) {
+                            println!("Created role {name}");
+                        } else {
+                            println!("Updated role {name}");
+                        }
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            RoleCommands::Rm { name }
+                if !confirm_deletion(&format!("Delete role '{name}'?"), yes) =>
+            {
+                ExitCode::FAILURE
+            }
+            RoleCommands::Rm { name } => match es.delete_role(name).await {
+                Ok(response) if response["found"].as_bool().unwrap_or(false) => {
+                    println!("Deleted role {name}");
+                    ExitCode::SUCCESS
+                }
+                Ok(_) => {
+                    eprintln!("No such role: {name}");
+                    ExitCode::from(exit_status::REQUEST)
+                }
+                Err(e) => fail(&e),
+            },
+        },
+        Commands::Script { command } => match command {
+            ScriptCommands::Ls {} => print_scripts(es).await,
+            ScriptCommands::Get { id } => match es.get_script(id).await {
+                Ok(Some(script)) => {
+                    println!("{}", script["source"].as_str().unwrap_or(""));
+                    ExitCode::SUCCESS
+                }
+                Ok(None) => {
+                    eprintln!("No such script: {id}");
+                    ExitCode::from(exit_status::REQUEST)
+                }
+                Err(e) => fail(&e),
+            },
+            ScriptCommands::Put { id, file, lang } => {
+                let source = match fs::read_to_string(file) {
+                    Ok(it) => it,
+                    Err(e) => {
+                        eprintln!("{file}: {e}");
+                        return ExitCode::from(exit_status::USAGE);
+                    }
+                };
+                match es.put_script(id, lang, &source).await {
+                    Ok(ack) => {
+                        println!(
+                            "Saved script {id} ({}acknowledged)",
+                            if ack.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            ScriptCommands::Rm { id }
+                if !confirm_deletion(&format!("Delete script '{id}'?"), yes) =>
+            {
+                ExitCode::FAILURE
+            }
+            ScriptCommands::Rm { id } => match es.delete_script(id).await {
+                Ok(ack) => {
+                    println!(
+                        "Deleted script {id} ({}acknowledged)",
+                        if ack.acknowledged { "" } else { "not " }
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+        },
+        Commands::Enrich { command } => match command {
+            EnrichCommands::Ls {} => print_enrich_policies(es).await,
+            EnrichCommands::Put {
+                name,
+                policy_type,
+                indices,
+                match_field,
+                enrich_fields,
+            } => {
+                let policy_type = match policy_type {
+                    EnrichType::Match => "match",
+                    EnrichType::GeoMatch => "geo_match",
+                    EnrichType::Range => "range",
+                };
+                let body = json!({
+                    policy_type: {
+                        "indices": indices,
+                        "match_field": match_field,
+                        "enrich_fields": enrich_fields,
+                    }
+                });
+                match es.put_enrich_policy(name, &body).await {
+                    Ok(ack) => {
+                        println!(
+                            "Created policy {name} ({}acknowledged); run escli enrich execute {name} to build its index",
+                            if ack.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            EnrichCommands::Execute { name } => match es.execute_enrich_policy(name).await {
+                Ok(response) => {
+                    println!(
+                        "Executed policy {name} ({})",
+                        response["status"]["phase"]
+                            .as_str()
+                            .unwrap_or("unknown")
+                            .to_lowercase()
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+            EnrichCommands::Rm { name }
+                if !confirm_deletion(&format!("Delete enrich policy '{name}'?"), yes) =>
+            {
+                ExitCode::FAILURE
+            }
+            EnrichCommands::Rm { name } => match es.delete_enrich_policy(name).await {
+                Ok(ack) => {
+                    println!(
+                        "Deleted policy {name} ({}acknowledged)",
+                        if ack.acknowledged { "" } else { "not " }
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+        },
+        Commands::Slm { command } => match command {
+            SlmCommands::Ls {} => print_slm_policies(es, false).await,
+            SlmCommands::Status {} => print_slm_policies(es, true).await,
+            SlmCommands::Put {
+                name,
+                schedule,
+                repository,
+                snapshot_name,
+                indices,
+                expire_after,
+                min_count,
+                max_count,
+            } => {
+                let mut body = json!({
+                    "schedule": schedule,
+                    "name": snapshot_name.clone().unwrap_or(format!("<{name}-{{now/d}}>")),
+                    "repository": repository,
+                    "config": {},
+                });
+                if let Some(x) = indices {
+                    body["config"]["indices"] = json!(x);
+                }
+                if let Some(x) = expire_after {
+                    body["retention"]["expire_after"] = json!(x);
+                }
+                if let Some(x) = min_count {
+                    body["retention"]["min_count"] = json!(x);
+                }
+                if let Some(x) = max_count {
+                    body["retention"]["max_count"] = json!(x);
+                }
+                match es.put_slm_policy(name, &body).await {
+                    Ok(ack) => {
+                        println!(
+                            "Saved policy {name} ({}acknowledged)",
+                            if ack.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+            SlmCommands::Rm { name }
+                if !confirm_deletion(
+                    &format!("Delete snapshot lifecycle policy '{name}'?"),
+                    yes,
+                ) =>
+            {
+                ExitCode::FAILURE
+            }
+            SlmCommands::Rm { name } => match es.delete_slm_policy(name).await {
+                Ok(ack) => {
+                    println!(
+                        "Deleted policy {name} ({}acknowledged)",
+                        if ack.acknowledged { "" } else { "not " }
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+            SlmCommands::Execute { name } => match es.execute_slm_policy(name).await {
+                Ok(response) => {
+                    println!(
+                        "Started snapshot {}",
+                        response["snapshot_name"].as_str().unwrap_or("?")
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+        },
+        Commands::Restore {
+            repository,
+            snapshot,
+            indices,
+            rename_pattern,
+            rename_replacement,
+            wait,
+        } => {
+            let mut body = json!({});
+            if let Some(x) = indices {
+                body["indices"] = json!(x);
+            }
+            if let (Some(pattern), Some(replacement)) = (rename_pattern, rename_replacement) {
+                body["rename_pattern"] = json!(pattern);
+                body["rename_replacement"] = json!(replacement);
+            }
+            match es
+                .restore_snapshot(repository, snapshot, &body, *wait)
+                .await
+            {
+                Ok(restored) if *wait => {
+                    let detail = &restored["snapshot"];
+                    let names: Vec<&str> = detail["indices"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|x| x.as_str())
+                        .collect();
+                    println!("Restored {} from snapshot {snapshot}", names.join(", "));
+                    let failed = detail["shards"]["failed"].as_u64().unwrap_or(0);
+                    println!(
+                        "{}/{} shards restored ({failed} failed)",
+                        detail["shards"]["successful"], detail["shards"]["total"]
+                    );
+                    if failed > 0 {
+                        ExitCode::from(exit_status::PARTIAL)
+                    } else {
+                        ExitCode::SUCCESS
+                    }
+                }
+                Ok(_) => {
+                    println!(
+                        "Started restore from snapshot {snapshot}; follow it with escli recovery"
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            }
+        }
+        Commands::Snapshot { command } => match command {
+            SnapshotCommands::Mk {
+                repository,
+                snapshot,
+                indices,
+                wait,
+            } => create_snapshot(es, repository, snapshot, indices.as_deref(), *wait).await,
+            SnapshotCommands::Ls { repository } => print_snapshots(es, repository).await,
+            SnapshotCommands::Status {
+                repository,
+                snapshot,
+            } => print_snapshot_status(es, repository, snapshot.as_deref()).await,
+            SnapshotCommands::Rm {
+                repository,
+                snapshot,
+            } if !confirm_deletion(
+                &format!("Delete snapshot '{snapshot}' from {repository}?"),
+                yes,
+            ) =>
+            {
+                ExitCode::FAILURE
+            }
+            SnapshotCommands::Rm {
+                repository,
+                snapshot,
+            } => match es.delete_snapshot(repository, snapshot).await {
+                Ok(deleted) => {
+                    println!(
+                        "Deleted snapshot {snapshot} ({}acknowledged)",
+                        if deleted.acknowledged { "" } else { "not " }
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(&e),
+            },
+        },
+        Commands::Task { command } => match command {
+            TaskCommands::Cancel { task_id } => cancel_task(es, task_id).await,
+        },
+        Commands::Maint { command } => match command {
+            MaintCommands::Forcemerge {
+                indexes,
+                max_num_segments,
+            } => forcemerge(es, indexes, *max_num_segments).await,
+            MaintCommands::Refresh { indexes } => {
+                let patterns: Vec<&str> = indexes.iter().map(|x| x.as_str()).collect();
+                print_shards_outcome("Refreshed", es.refresh(&patterns).await)
+            }
+            MaintCommands::Flush { indexes } => {
+                let patterns: Vec<&str> = indexes.iter().map(|x| x.as_str()).collect();
+                print_shards_outcome("Flushed", es.flush(&patterns).await)
+            }
+            MaintCommands::ClearCache { indexes } => {
+                let patterns: Vec<&str> = indexes.iter().map(|x| x.as_str()).collect();
+                print_shards_outcome("Cleared caches on", es.clear_cache(&patterns).await)
+            }
+        },
+        Commands::ClusterSettings { command } => match command {
+            ClusterSettingsCommands::Get { filter } => {
+                print_cluster_settings(es, filter.as_deref().unwrap_or("")).await
+            }
+            ClusterSettingsCommands::Set {
+                settings,
+                transient,
+                persistent: _,
+            } => {
+                let section = if *transient {
+                    "transient"
+                } else {
+                    "persistent"
+                };
+                let values: Map<String, Value> = settings
+                    .iter()
+                    .map(|(key, value)| match value.as_str() {
+                        "null" => (key.to_owned(), Value::Null),
+                        _ => (key.to_owned(), json!(value)),
+                    })
+                    .collect();
+                match es.put_cluster_settings(&json!({ section: values })).await {
+                    Ok(updated) => {
+                        println!(
+                            "Updated {section} cluster settings ({}acknowledged)",
+                            if updated["acknowledged"].as_bool().unwrap_or(false) {
+                                ""
+                            } else {
+                                "not "
+                            }
+                        );
+                        for (key, _) in settings.iter() {
+                            println!(
+                                "  {key} = {}",
+                                match &updated[section][key] {
+                                    Value::Null => "(default)".to_string(),
+                                    value => data::to_cell(value),
+                                }
+                            );
+                        }
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+        },
+        Commands::Remote { command } => match command {
+            RemoteCommands::Ls {} => print_remotes(es).await,
+            RemoteCommands::Add {
+                name,
+                addresses,
+                proxy,
+                skip_unavailable,
+                transient,
+            } => add_remote(es, name, addresses, *proxy, *skip_unavailable, *transient).await,
+            RemoteCommands::Rm { name } => remove_remote(es, name).await,
+        },
+        Commands::Settings { command } => match command {
+            SettingsCommands::Get {
+                index,
+                include_defaults,
+            } => print_index_settings(es, index, *include_defaults).await,
+            SettingsCommands::Put { index, settings } => {
+                let body: Map<String, Value> = settings
+                    .iter()
+                    .map(|(key, value)| match value.as_str() {
+                        "null" => (key.to_owned(), Value::Null),
+                        _ => (key.to_owned(), json!(value)),
+                    })
+                    .collect();
+                match es.put_index_settings(index, &Value::Object(body)).await {
+                    Ok(updated) => {
+                        println!(
+                            "Updated settings for index {} ({}acknowledged)",
+                            index,
+                            if updated.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+        },
+        Commands::Mapping { command } => match command {
+            MappingCommands::Get { index, format } => print_mapping(es, index, format).await,
+            MappingCommands::Put {
+                index,
+                mappings,
+                mappings_file,
+            } => {
+                let mut body = match mappings_file {
+                    Some(path) => match mapping::from_file(path) {
+                        Ok(it) => it,
+                        Err(e) => {
+                            output::print_error(&e);
+                            return ExitCode::from(exit_status::USAGE);
+                        }
+                    },
+                    None => json!({"mappings": {"properties": {}}}),
+                };
+                mapping::add_specs(&mut body, mappings);
+                match es.put_mapping(index, &body["mappings"]).await {
+                    Ok(updated) => {
+                        println!(
+                            "Updated mapping for index {} ({}acknowledged)",
+                            index,
+                            if updated.acknowledged { "" } else { "not " }
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(&e),
+                }
+            }
+        },
+        Commands::Report { command } => match command {
+            ReportCommands::Usage { index, interval } => {
+                print_usage_report(es, index, *interval).await
+            }
+        },
+        Commands::Search {
+            index,
+            query,
+            order_by,
+            limit,
+            routing,
+            format,
+            compact,
+            nested,
+            with_meta,
+            save_to,
+            assert_schema,
+        } => {
+            let result = &match es.search(index, query, order_by, limit, routing).await {
+                Ok(it) => it,
+                Err(e) => return fail(&e),
+            };
+            print_search_result(result, format, *compact, *nested, *with_meta);
+            if let Some(db) = save_to {
+                match local::save_hits(db, index, &result.hits.hits) {
+                    Ok(count) => eprintln!("Saved {} hits to {}", count, db),
+                    Err(e) => {
+                        output::print_error(&e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            if let Some(path) = assert_schema {
+                match check_schema(path, &result.hits.hits) {
+                    Ok(0) => {}
+                    Ok(failures) => {
+                        eprintln!("{} hits do not match the schema", failures);
+                        return ExitCode::FAILURE;
+                    }
+                    Err(e) => return exit_status::fail_any(&*e),
+                }
+            }
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// Validates the source of each search hit against a JSON Schema read from
+/// a file, reporting the ID of each failing document along with the reasons
+/// it failed. Returns the number of hits that failed.
+///
+fn check_schema(
+    path: &str,
+    hits: &[RawSearchResultHitsHit],
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let schema: Value = serde_json::from_reader(File::open(path)?)?;
+    let validator = match jsonschema::validator_for(&schema) {
+        Ok(it) => it,
+        Err(e) => return Err(format!("invalid schema in {path} ({e})").into()),
+    };
+    let mut failures = 0;
+    for hit in hits.iter() {
+        let source = json!(hit._source);
+        let errors: Vec<String> = validator
+            .iter_errors(&source)
+            .map(|e| match e.instance_path.to_string().as_str() {
+                "" => e.to_string(),
+                field => format!("{field}: {e}"),
+            })
+            .collect();
+        if !errors.is_empty() {
+            failures += 1;
+            eprintln!("{}/{} does not match the schema", hit._index, hit._id);
+            for error in errors.iter() {
+                eprintln!("  {error}");
+            }
+        }
+    }
+    Ok(failures)
+}
+
+/// Sends HEAD requests to the service root on a fixed schedule, printing
+/// the outcome of each. Requests are sent at each interval regardless of
+/// how long the last took, unless the limit of requests in flight has been
+/// reached, in which case the next is sent as soon as a response arrives.
+///
+async fn ping(
+    es: &SimpleClient,
+    options: &PingOptions,
+    compare: &Option<String>,
+    save: &Option<String>,
+) -> ExitCode {
+    println!("HEAD {}", es.url());
+    // Ctrl-C ends the run rather than the process, so that a summary can
+    // be printed and the results are not lost
+    let interrupted = Arc::new(AtomicBool::new(false));
+    tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    let mut run = PingRun::new(es.url().as_str());
+    let mut ticker = tokio::time::interval(options.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let started = Instant::now();
+    let deadline = options.timeout.map(|x| started + x);
+    let mut in_flight = JoinSet::new();
+    let mut seq: usize = 0;
+    let mut available = false;
+    let mut timed_out = false;
+    loop {
+        let stopping = interrupted.load(Ordering::SeqCst)
+            || timed_out
+            || (available && options.until_available);
+        if stopping {
+            // Requests still awaiting a response count as lost
+            for _ in 0..in_flight.len() {
+                run.record(None);
+            }
+            in_flight.abort_all();
+            break;
+        }
+        let can_send =
+            options.count.is_none_or(|x| seq < x) && in_flight.len() < options.concurrent;
+        if !can_send && in_flight.is_empty() {
+            break;
+        }
+        tokio::select! {
+            _ = ticker.tick(), if can_send => {
+                seq += 1;
+                let es = es.clone();
+                let request_timeout = options.request_timeout;
+                in_flight.spawn(async move {
+                    let t0 = Instant::now();
+                    let result = match request_timeout {
+                        Some(x) => tokio::time::timeout(x, es.ping()).await.ok(),
+                        None => Some(es.ping().await),
+                    };
+                    (seq, result, t0.elapsed())
+                });
+            }
+            Some(Ok((seq, result, elapsed))) = in_flight.join_next() => match result {
+                Some(Ok(status_code)) => {
+                    println!("{status_code}: seq={seq} time={elapsed:?}");
+                    run.record(Some(elapsed));
+                    available = status_code.is_success();
+                }
+                Some(Err(e)) => {
+                    println!("{e}: seq={seq} time={elapsed:?}");
+                    run.record(None);
+                }
+                None => {
+                    println!("Timed out: seq={seq} time={elapsed:?}");
+                    run.record(None);
+                }
+            },
+            _ = tokio::time::sleep_until(deadline.unwrap_or(started).into()), if deadline.is_some() => {
+                timed_out = true;
+            }
+            // Wakes periodically to notice Ctrl-C while waiting on a response
+            _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+        }
+    }
+    println!();
+    ping::print_summary(&run, started.elapsed());
+    if let Some(path) = compare {
+        println!();
+        if std::path::Path::new(path).exists() {
+            match PingRun::load(path) {
+                Ok(baseline) => ping::print_comparison(&baseline, &run),
+                Err(e) => {
+                    eprintln!("Failed to load baseline from {path} ({e})");
+                    return ExitCode::from(exit_status::USAGE);
+                }
+            }
+        } else {
+            match run.save(path) {
+                Ok(()) => println!("Saved this run as the baseline in {path}"),
+                Err(e) => {
+                    eprintln!("Failed to save baseline to {path} ({e})");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    }
+    if let Some(path) = save {
+        if let Err(e) = run.save(path) {
+            eprintln!("Failed to save run to {path} ({e})");
+            return ExitCode::FAILURE;
+        }
+    }
+    if options.until_available && !available {
+        if timed_out {
+            eprintln!("Timed out waiting for {} to become available", es.url());
+        } else {
+            eprintln!("{} did not become available", es.url());
+        }
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+async fn print_info(es: &SimpleClient) -> ExitCode {
+    match es.info().await {
+        Ok(info) if output::format() == OutputFormat::Json => match serde_json::to_value(&info) {
+            Ok(mut value) => {
+                if let Some((name, _)) = profile::active() {
+                    value["context"] = json!(name);
+                }
+                output::print_json(&value, false);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                output::print_error(&e);
+                ExitCode::FAILURE
+            }
+        },
+        Ok(info) => {
+            if let Some((name, profile)) = profile::active() {
+                if profile.production {
+                    println!("Context: {name} (production)");
+                } else {
+                    println!("Context: {name}");
+                }
+            }
+            println!("Name: {}", info.name);
+            println!("Cluster Name: {}", info.cluster_name);
+            println!("Cluster UUID: {}", info.cluster_uuid);
+            println!("Version:");
+            println!("  Number: {}", info.version.number);
+            println!("  Build Flavor: {}", info.version.build_flavor);
+            println!("  Build Type: {}", info.version.build_type);
+            println!("  Build Hash: {}", info.version.build_hash);
+            println!("  Build Date: {}", info.version.build_date);
+            println!("  Build Snapshot: {}", info.version.build_snapshot);
+            println!("  Lucene Version: {}", info.version.lucene_version);
+            println!(
+                "  Minimum Wire Compatibility Version: {}",
+                info.version.minimum_wire_compatibility_version
+            );
+            println!(
+                "  Minimum Index Compatibility Version: {}",
+                info.version.minimum_index_compatibility_version
+            );
+            println!("Tagline: {}", info.tagline);
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+async fn print_index_list(
+    es: &SimpleClient,
+    index: &Option<String>,
+    all: bool,
     open: bool,
     closed: bool,
+    long: bool,
+    watch: &Option<f64>,
+) -> ExitCode {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    if watch.is_some() {
+        tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    }
+    let mut screen = Screen::new();
+    loop {
+        let index_list = match es
+            .get_index_list(
+                &[index.clone().unwrap_or(String::from("*")).as_str()],
+                all,
+                open,
+                closed,
+            )
+            .await
+        {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+        if output::format() != OutputFormat::Table {
+            let mut entries: Vec<&IndexDetail> = index_list
+                .iter()
+                .filter(|entry| all || !entry.name.starts_with('.'))
+                .collect();
+            entries.sort_by(|a, b| a.name.cmp(&b.name));
+            print_index_data(&entries, long);
+        } else if watch.is_some() {
+            screen.show(&render_index_list(&index_list, all, long).unwrap_or_default());
+        } else if let Some(table) = render_index_list(&index_list, all, long) {
+            println!("{table}");
+        }
+        let interval = match watch {
+            Some(x) => *x,
+            None => return ExitCode::SUCCESS,
+        };
+        tokio::time::sleep(Duration::from_secs_f64(interval)).await;
+        if interrupted.load(Ordering::SeqCst) {
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+    }
+}
+
+/// Renders an index list as a table, with a marker for the health of each
+/// index, or returns `None` if there are no indexes to show.
+///
+fn render_index_list(index_list: &[IndexDetail], all: bool, long: bool) -> Option<String> {
+    let mut builder = tabled::builder::Builder::default();
+    let mut has_rows = false;
+    for entry in index_list.iter() {
+        if all || !entry.name.starts_with('.') {
+            let mut record = vec![
+                if output::ascii() {
+                    entry.health.clone()
+                } else {
+                    match entry.health.as_str() {
+                        "green" => "🟢",
+                        "yellow" => "🟡",
+                        "red" => "🔴",
+                        _ => "⚫",
+                    }
+                    .to_string()
+                },
+                entry.uuid.clone(),
+                entry.name.clone(),
+                format!("{} docs", entry.docs_count.unwrap_or(0),),
+                format!(
+                    "{:-#.1}",
+                    Byte::from_u64(entry.dataset_size.unwrap_or(0))
+                        .get_appropriate_unit(UnitType::Decimal)
+                ),
+            ];
+            if long {
+                record.push(format!(
+                    "{} pri",
+                    entry.primaries.map_or("?".to_string(), |x| x.to_string())
+                ));
+                record.push(format!(
+                    "{} rep",
+                    entry.replicas.map_or("?".to_string(), |x| x.to_string())
+                ));
+                record.push(
+                    entry
+                        .creation_date
+                        .as_deref()
+                        .map_or(String::new(), format_date_time),
+                );
+            }
+            record.push(
+                match entry.status.as_str() {
+                    "closed" if output::ascii() => "closed",
+                    "closed" => "🔒",
+                    _ => "",
+                }
+                .to_string(),
+            );
+            builder.push_record(record);
+            has_rows = true;
+        }
+    }
+    if !has_rows {
+        return None;
+    }
+    // This table has no header row, so is rendered directly rather than
+    // through output::print_table
+    let table = builder
+        .build()
+        .with(Style::empty())
+        .modify(Columns::first(), Padding::new(0, 1, 0, 0))
+        .modify(
+            Columns::new(3..if long { 7 } else { 5 }),
+            Alignment::right(),
+        )
+        .to_string();
+    if !(output::ascii() && output::colour_stdout()) {
+        return Some(table);
+    }
+    // Codes are added after layout, as tabled would count them towards the
+    // width of the health column
+    let lines: Vec<String> = table
+        .lines()
+        .map(|line| match line.split_once(' ') {
+            Some((health, rest)) => format!(
+                "{} {rest}",
+                output::paint(output::health_style(health), health, true)
+            ),
+            None => line.to_string(),
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Column names used for machine-readable index lists.
+///
+const INDEX_LIST_COLUMNS: [&str; 8] = [
+    "index",
+    "uuid",
+    "health",
+    "status",
+    "docs_count",
+    "docs_deleted",
+    "store_size",
+    "dataset_size",
+];
+
+/// Further column names used for machine-readable index lists when the
+/// long format is requested.
+///
+const INDEX_LIST_LONG_COLUMNS: [&str; 3] = ["pri", "rep", "creation_date"];
+
+/// Prints an index list in a machine-readable output format, with raw
+/// numbers rather than the humanised values of the table format. Sizes are
+/// in bytes.
+///
+fn print_index_data(entries: &[&IndexDetail], long: bool) {
+    let mut columns = INDEX_LIST_COLUMNS.to_vec();
+    if long {
+        columns.extend(INDEX_LIST_LONG_COLUMNS);
+    }
+    let rows: Vec<Vec<Value>> = entries
+        .iter()
+        .map(|entry| {
+            let mut row = vec![
+                json!(entry.name),
+                json!(entry.uuid),
+                json!(entry.health),
+                json!(entry.status),
+                json!(entry.docs_count),
+                json!(entry.docs_deleted),
+                json!(entry.store_size),
+                json!(entry.dataset_size),
+            ];
+            if long {
+                row.push(json!(entry.primaries));
+                row.push(json!(entry.replicas));
+                row.push(json!(entry.creation_date));
+            }
+            row
+        })
+        .collect();
+    output::print_rows(&columns, &rows);
+}
+
+/// Exports all documents matching a query to CSV, flattening nested values
+/// into separate columns. Documents are read by one worker per slice, and
+/// each page is written as it arrives, in whatever order, so the columns
+/// are fixed up front, from `--columns` if given or otherwise from the
+/// fields in the index mapping. Nested values not flattened into a column
+/// of their own, such as arrays, are written as JSON. Returns the number of
+/// documents exported.
+///
+async fn export(
+    es: &SimpleClient,
+    index: &str,
+    query: &Option<String>,
+    output_file: &Option<String>,
+    flatten: FlattenPolicy,
+    batch_size: usize,
+    slices: usize,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let column_names = match output::selected_columns() {
+        columns if columns.is_empty() => {
+            export_columns(es, index, query, flatten, batch_size, slices).await?
+        }
+        columns => columns,
+    };
+    let mut receiver = scan_slices(es, index, query, batch_size, slices);
+    let output: Box<dyn Write> = match output_file {
+        Some(filename) => Box::new(File::create(filename)?),
+        None => Box::new(stdout()),
+    };
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record(&column_names)?;
+    let mut count = 0;
+    while let Some(page) = receiver.recv().await {
+        for document in page?.iter() {
+            writer.write_record(
+                column_names
+                    .iter()
+                    .map(|name| data::lookup(document, name).map_or(String::new(), data::to_cell)),
+            )?;
+            count += 1;
+        }
+        writer.flush()?;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Lists the columns to export from the fields in the mappings of the
+/// indexes matched, in the order first found. When flattening in full, each
+/// field holding arrays is replaced by a column for every array item found
+/// in the documents, such as `tags.0` and `tags.1`.
+///
+async fn export_columns(
+    es: &SimpleClient,
+    index: &str,
+    query: &Option<String>,
+    flatten: FlattenPolicy,
+    batch_size: usize,
+    slices: usize,
+) -> Result<Vec<String>, client::Error> {
+    let mapping = es.get_mapping(index).await?;
+    let mut fields: Vec<String> = vec![];
+    let mut mapped: HashSet<String> = HashSet::new();
+    for (_name, detail) in mapping.as_object().into_iter().flatten() {
+        for field in mapping::source_fields(&detail["mappings"], flatten != FlattenPolicy::None) {
+            if mapped.insert(field.clone()) {
+                fields.push(field);
+            }
+        }
+    }
+    if flatten != FlattenPolicy::Full {
+        return Ok(fields);
+    }
+    // The mapping does not say how long arrays are, so the documents are
+    // read through once to find the items present
+    let mut found: HashMap<String, BTreeMap<Vec<usize>, String>> = HashMap::new();
+    let mut receiver = scan_slices(es, index, query, batch_size, slices);
+    while let Some(page) = receiver.recv().await {
+        for document in page?.iter() {
+            for (column, field, indexes) in data::indexed_columns(document) {
+                if mapped.contains(&field) {
+                    found
+                        .entry(field)
+                        .or_default()
+                        .entry(indexes)
+                        .or_insert(column);
+                }
+            }
+        }
+    }
+    Ok(fields
+        .into_iter()
+        .flat_map(|field| match found.remove(&field) {
+            Some(columns) if columns.keys().any(|x| !x.is_empty()) => {
+                columns.into_values().collect()
+            }
+            _ => vec![field],
+        })
+        .collect())
+}
+
+/// Starts scrolling through the documents matching a query in the given
+/// number of slices, returning a channel that receives each page of
+/// documents as it arrives.
+///
+fn scan_slices(
+    es: &SimpleClient,
+    index: &str,
+    query: &Option<String>,
+    batch_size: usize,
+    slices: usize,
+) -> Receiver<Result<Vec<Document>, client::Error>> {
+    // A page or two per slice is enough to keep the reader busy, and stops
+    // pages piling up in memory when it cannot keep up
+    let (sender, receiver) = channel(2 * slices);
+    for id in 0..slices {
+        tokio::spawn(export_slice(
+            es.clone(),
+            index.to_string(),
+            query.clone(),
+            batch_size,
+            if slices > 1 { Some((id, slices)) } else { None },
+            sender.clone(),
+        ));
+    }
+    receiver
+}
+
+/// Scrolls through one slice of the documents matching a query, sending
+/// each page of documents to the given channel.
+///
+async fn export_slice(
+    es: SimpleClient,
+    index: String,
+    query: Option<String>,
+    batch_size: usize,
+    slice: Option<(usize, usize)>,
+    sender: Sender<Result<Vec<Document>, client::Error>>,
+) {
+    let mut result = match es.scroll_start(&index, &query, batch_size, slice).await {
+        Ok(it) => it,
+        Err(e) => {
+            let _ = sender.send(Err(e)).await;
+            return;
+        }
+    };
+    while !result.hits.hits.is_empty() {
+        let scroll_id = result._scroll_id.take();
+        let page = result
+            .hits
+            .hits
+            .into_iter()
+            .map(|hit| hit._source)
+            .collect();
+        if sender.send(Ok(page)).await.is_err() {
+            return;
+        }
+        result = match &scroll_id {
+            Some(scroll_id) => match es.scroll_next(scroll_id).await {
+                Ok(it) => it,
+                Err(e) => {
+                    let _ = sender.send(Err(e)).await;
+                    return;
+                }
+            },
+            None => return,
+        };
+    }
+    if let Some(scroll_id) = &result._scroll_id {
+        let _ = es.clear_scroll(scroll_id).await;
+    }
+}
+
+fn suggest_mapping(csv_filename: &str, sample_size: usize) -> ExitCode {
+    let result = match File::open(csv_filename) {
+        Ok(file) => mapping::infer_from_csv(file, sample_size),
+        Err(e) => Err(csv::Error::from(e)),
+    };
+    match result {
+        Ok(body) => {
+            let mut flags: Vec<String> = vec![];
+            if let Some(properties) = body["mappings"]["properties"].as_object() {
+                for (name, field) in properties.iter() {
+                    flags.push(format!(
+                        "-m {}:{}",
+                        name,
+                        field["type"].as_str().unwrap_or("keyword")
+                    ));
+                }
+            }
+            println!("{}", flags.join(" "));
+            println!();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&body).unwrap_or_else(|_| body.to_string())
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            output::print_error(&e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Checks CSV data against the mapping of the index it is to be loaded into,
+/// warning about columns with no corresponding field, and about values that
+/// cannot be coerced to the type of their field. Returns the number of
+/// problems found.
+///
+async fn validate_load(
+    es: &SimpleClient,
+    index: &str,
+    csv_filenames: &[String],
+    headers: &[String],
+    geo_points: &[GeoPoint],
+) -> Result<usize, Box<dyn std::error::Error>> {
+    const MAX_WARNINGS_PER_COLUMN: usize = 10;
+    let mapping = es.get_mapping(index).await?;
+    let mut field_types: HashMap<String, String> = HashMap::new();
+    let mut field_formats: HashMap<String, String> = HashMap::new();
+    for (_name, detail) in mapping.as_object().into_iter().flatten() {
+        field_types.extend(mapping::field_types(&detail["mappings"]));
+        field_formats.extend(mapping::field_formats(&detail["mappings"]));
+    }
+    // Columns combined into geo points are not mapped themselves, but must
+    // still hold numbers
+    for geo_point in geo_points.iter() {
+        for column in [&geo_point.lat, &geo_point.lon] {
+            field_types.insert(column.to_owned(), "double".to_string());
+        }
+    }
+    let mut problems = 0;
+    for filename in csv_filenames.iter() {
+        let mut reader = csv::Reader::from_reader(source::open(filename, headers).await?);
+        let columns = reader.headers()?.clone();
+        for column in columns.iter() {
+            if !field_types.contains_key(column) {
+                eprintln!("{filename}: column {column} is not in the mapping for {index}");
+                problems += 1;
+            }
+        }
+        let mut bad_values: Vec<usize> = vec![0; columns.len()];
+        for result in reader.records() {
+            let record = result?;
+            let line = record.position().map_or(0, |position| position.line());
+            for (i, (column, value)) in columns.iter().zip(record.iter()).enumerate() {
+                match field_types.get(column) {
+                    Some(field_type)
+                        if !value.is_empty()
+                            && !mapping::can_coerce(
+                                value,
+                                field_type,
+                                field_formats.get(column).map(String::as_str),
+                            ) =>
+                    {
+                        bad_values[i] += 1;
+                        if bad_values[i] <= MAX_WARNINGS_PER_COLUMN {
+                            eprintln!("{filename}:{line}: value {value:?} in column {column} is not a valid {field_type}");
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for (column, count) in columns.iter().zip(bad_values.iter()) {
+            if *count > MAX_WARNINGS_PER_COLUMN {
+                eprintln!(
+                    "{filename}: {} more invalid values in column {column}",
+                    count - MAX_WARNINGS_PER_COLUMN
+                );
+            }
+        }
+        problems += bad_values.iter().sum::<usize>();
+    }
+    Ok(problems)
+}
+
+async fn auto_create_index(
+    es: &SimpleClient,
+    index: &str,
+    csv_filenames: &[String],
+    headers: &[String],
+    sample_size: usize,
+    geo_points: &[GeoPoint],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if es.index_exists(index).await? {
+        return Ok(());
+    }
+    let filename = match csv_filenames.first() {
+        Some(it) => it,
+        None => return Ok(()),
+    };
+    let mut body = mapping::infer_from_csv(source::open(filename, headers).await?, sample_size)?;
+    if let Some(properties) = body["mappings"]["properties"].as_object_mut() {
+        for geo_point in geo_points.iter() {
+            properties.remove(&geo_point.lat);
+            properties.remove(&geo_point.lon);
+            properties.insert(geo_point.field.to_owned(), json!({"type": "geo_point"}));
+        }
+    }
+    let created = es.create_index(index, &body, &None, &None).await?;
+    println!(
+        "Created index {} with inferred mapping ({}acknowledged)",
+        created.index,
+        if created.acknowledged { "" } else { "not " }
+    );
+    Ok(())
+}
+
+/// Parses a size such as `500KB` or `5 MiB` into a number of bytes.
+///
+fn parse_byte_size(value: &str) -> Result<u64, String> {
+    match Byte::parse_str(value, true) {
+        Ok(byte) => Ok(byte.as_u64()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parses a rate limit in bytes per second, which must be above zero.
+///
+fn parse_byte_rate(value: &str) -> Result<u64, String> {
+    match parse_byte_size(value)? {
+        0 => Err("rate must be greater than zero".to_string()),
+        bytes => Ok(bytes),
+    }
+}
+
+/// Parses a delay in seconds, which must be a finite number, zero or above.
+///
+fn parse_backoff(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(x) if x.is_finite() && x >= 0.0 => Ok(x),
+        Ok(_) => Err("delay must be zero or more".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parses a rate limit, which must be a finite number above zero.
+///
+fn parse_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(x) if x.is_finite() && x > 0.0 => Ok(x),
+        Ok(_) => Err("rate must be greater than zero".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parses a duration such as `90`, `30s`, `2m` or `1h`, where a bare number
+/// is taken as seconds.
+///
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1.0),
+        Some((i, 'm')) => (&value[..i], 60.0),
+        Some((i, 'h')) => (&value[..i], 3600.0),
+        _ => (value, 1.0),
+    };
+    match number.parse::<f64>() {
+        Ok(x) if x >= 0.0 => Ok(Duration::from_secs_f64(x * multiplier)),
+        _ => Err(format!(
+            "invalid duration {value} (expected e.g. 30s, 2m or 1h)"
+        )),
+    }
+}
+
+/// Parses a version such as `9`, `9.x` or `9.1.0`, returning only the major
+/// version number.
+///
+fn parse_major_version(value: &str) -> Result<u64, String> {
+    match value.split('.').next().map(|x| x.parse::<u64>()) {
+        Some(Ok(major)) => Ok(major),
+        _ => Err(format!("invalid version {value} (expected e.g. 9.x)")),
+    }
+}
+
+/// Parses a setting given as `KEY=VALUE`.
+///
+fn parse_setting(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("invalid setting {value} (expected KEY=VALUE)")),
+    }
+}
+
+/// Parses a geo point specification of the form `field=lat,lon`, where `lat`
+/// and `lon` name the columns holding the latitude and longitude.
+///
+fn parse_geo_point(value: &str) -> Result<GeoPoint, String> {
+    match value.split_once('=') {
+        Some((field, columns)) => match columns.split_once(',') {
+            Some((lat, lon)) if !field.is_empty() && !lat.is_empty() && !lon.is_empty() => {
+                Ok(GeoPoint {
+                    field: field.to_string(),
+                    lat: lat.to_string(),
+                    lon: lon.to_string(),
+                })
+            }
+            _ => Err(format!(
+                "invalid geo point {value} (expected FIELD=LAT,LON)"
+            )),
+        },
+        None => Err(format!(
+            "invalid geo point {value} (expected FIELD=LAT,LON)"
+        )),
+    }
+}
+
+/// Sets the given flag on the first Ctrl-C, allowing work in progress to be
+/// wound down cleanly, and exits immediately on the second.
+///
+async fn watch_for_interrupt(interrupted: Arc<AtomicBool>) {
+    if tokio::signal::ctrl_c().await.is_ok() {
+        eprintln!("Interrupted, finishing up (press Ctrl-C again to abort)");
+        interrupted.store(true, Ordering::SeqCst);
+        if tokio::signal::ctrl_c().await.is_ok() {
+            exit(exit_status::INTERRUPTED.into());
+        }
+    }
+}
+
+async fn print_field_usage(es: &SimpleClient, index: &str) -> ExitCode {
+    let stats = match es.get_field_usage_stats(index).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mapping = match es.get_mapping(index).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    // Usage counters are reported per shard, so sum them across all shards
+    // of all matching indexes
+    const COUNTERS: [&str; 6] = [
+        "any",
+        "inverted_index",
+        "doc_values",
+        "points",
+        "stored_fields",
+        "norms",
+    ];
+    let mut usage: HashMap<String, [u64; 6]> = HashMap::new();
+    if let Some(indices) = mapping.as_object() {
+        for (_name, detail) in indices.iter() {
+            for field in mapping::field_names(&detail["mappings"]) {
+                usage.entry(field).or_insert([0; 6]);
+            }
+        }
+    }
+    if let Some(indices) = stats.as_object() {
+        for (_name, detail) in indices.iter().filter(|(name, _)| *name != "_shards") {
+            for shard in detail["shards"].as_array().into_iter().flatten() {
+                for (field, counts) in shard["stats"]["fields"].as_object().into_iter().flatten() {
+                    let totals = usage.entry(field.to_owned()).or_insert([0; 6]);
+                    for (i, counter) in COUNTERS.iter().enumerate() {
+                        totals[i] += match &counts[counter] {
+                            Value::Object(detail) => {
+                                detail.values().filter_map(Value::as_u64).sum()
+                            }
+                            value => value.as_u64().unwrap_or(0),
+                        };
+                    }
+                }
+            }
+        }
+    }
+    let mut fields: Vec<(String, [u64; 6])> = usage.into_iter().collect();
+    fields.sort_by(|a, b| b.1[0].cmp(&a.1[0]).then(a.0.cmp(&b.0)));
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record([
+        "Field",
+        "Any",
+        "Inverted Index",
+        "Doc Values",
+        "Points",
+        "Stored",
+        "Norms",
+    ]);
+    for (field, totals) in fields.iter() {
+        let mut record = vec![field.to_owned()];
+        record.extend(totals.iter().map(|x| x.to_string()));
+        builder.push_record(record);
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::new(1..7), Alignment::right());
+    });
+    let unused = fields.iter().filter(|(_, totals)| totals[0] == 0).count();
+    output::print_note(&format!(
+        "{} of {} fields have not been accessed",
+        unused,
+        fields.len()
+    ));
+    ExitCode::SUCCESS
+}
+
+/// Lists the indexes to which an alias points, given the result of
+/// [`SimpleClient::get_alias`], excluding the index named `except`.
+///
+fn alias_targets<'a>(current: &'a Value, except: &str) -> Vec<&'a String> {
+    current
+        .as_object()
+        .into_iter()
+        .flat_map(|map| map.keys())
+        .filter(|name| *name != except)
+        .collect()
+}
+
+/// Points an alias at a new index in place of the given old indexes, using
+/// a single atomic update, and reports the change.
+///
+async fn switch_alias(
+    es: &SimpleClient,
+    alias: &str,
+    new_index: &str,
+    old_indexes: &[&String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut actions = vec![json!({"add": {"index": new_index, "alias": alias}})];
+    for index in old_indexes.iter() {
+        actions.push(json!({"remove": {"index": index, "alias": alias}}));
+    }
+    let updated = es.update_aliases(Value::Array(actions)).await?;
+    let from = old_indexes
+        .iter()
+        .map(|x| x.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ");
+    println!(
+        "Switched {alias}: {} -> {new_index} ({}acknowledged)",
+        if from.is_empty() { "(none)" } else { &from },
+        if updated.acknowledged { "" } else { "not " }
+    );
+    Ok(())
+}
+
+/// Prints the outcome of a rollover, including which of the conditions (if
+/// any were given) were met.
+///
+fn print_rollover(target: &str, rollover: &RawRollover) {
+    let outcome = match (rollover.rolled_over, rollover.dry_run) {
+        (true, _) => "rolled over",
+        (false, true) => "dry run, not rolled over",
+        (false, false) => "not rolled over, no conditions met",
+    };
+    println!("{target}: {outcome}");
+    println!("Old index: {}", rollover.old_index);
+    println!("New index: {}", rollover.new_index);
+    let mut conditions: Vec<(&String, &bool)> = rollover.conditions.iter().collect();
+    conditions.sort();
+    for (condition, met) in conditions {
+        println!("  {condition} {}", if *met { "met" } else { "not met" });
+    }
+}
+
+/// Lists aliases matching a pattern, with the indexes to which they point.
+///
+async fn print_alias_list(es: &SimpleClient, pattern: &str) -> ExitCode {
+    let aliases = match es.get_alias(pattern).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mut rows: Vec<[String; 4]> = vec![];
+    for (index, detail) in aliases.as_object().into_iter().flatten() {
+        for (alias, properties) in detail["aliases"].as_object().into_iter().flatten() {
+            rows.push([
+                alias.to_owned(),
+                index.to_owned(),
+                match properties["is_write_index"].as_bool() {
+                    Some(true) => "write".to_string(),
+                    _ => String::new(),
+                },
+                if properties.get("filter").is_some() {
+                    "filtered".to_string()
+                } else {
+                    String::new()
+                },
+            ]);
+        }
+    }
+    if rows.is_empty() {
+        output::print_empty("No aliases");
+        return ExitCode::SUCCESS;
+    }
+    rows.sort();
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Alias", "Index", "", ""]);
+    for row in rows.into_iter() {
+        builder.push_record(row);
+    }
+    output::print_table(builder);
+    ExitCode::SUCCESS
+}
+
+/// Atomically switches an alias from whichever indexes it currently points
+/// to over to a new index, optionally verifying the new index first, and
+/// optionally pruning older versions of the index afterwards. Versions are
+/// identified by sharing the name of the new index up to a `-NUMBER` suffix
+/// (e.g. `products-6` and `products-7`), and pruning is refused for names
+/// without one, so that unrelated indexes are never matched.
+///
+async fn deploy(
+    es: &SimpleClient,
+    alias: &str,
+    new_index: &str,
+    verify_count: bool,
+    keep: Option<u16>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let prefix = match version_prefix(new_index) {
+        Some(prefix) => prefix,
+        None if keep.is_some() => {
+            return Err(format!(
+                "{new_index} has no -NUMBER version suffix, so older versions cannot be pruned with --keep"
+            )
+            .into())
+        }
+        None => "",
+    };
+    let current = es.get_alias(alias).await?;
+    let old_indexes = alias_targets(&current, new_index);
+    if verify_count {
+        let new_count = es.count(new_index).await?;
+        let mut old_count = 0;
+        for index in old_indexes.iter() {
+            old_count += es.count(index).await?;
+        }
+        if new_count == 0 || new_count < old_count {
+            return Err(format!(
+                "{new_index} has {new_count} documents, compared to {old_count} behind {alias}; not switching"
+            )
+            .into());
+        }
+    }
+    switch_alias(es, alias, new_index, &old_indexes).await?;
+    if let Some(keep) = keep {
+        let pattern = format!("{prefix}*");
+        let settings = es
+            .get_settings(&[&pattern], &["index.creation_date"])
+            .await?;
+        let mut versions: Vec<(&String, u64)> = settings
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(name, _)| version_prefix(name) == Some(prefix))
+            .map(|(name, detail)| {
+                let created = detail["settings"]["index.creation_date"]
+                    .as_str()
+                    .and_then(|x| x.parse::<u64>().ok())
+                    .unwrap_or(0);
+                (name, created)
+            })
+            .collect();
+        // Newest first, but always keep the index just deployed
+        versions.sort_by(|a, b| {
+            (b.0 == new_index)
+                .cmp(&(a.0 == new_index))
+                .then(b.1.cmp(&a.1))
+        });
+        for (name, _) in versions.iter().skip(keep as usize) {
+            let deleted = es.delete_index(name).await?;
+            println!(
+                "Deleted old index {name} ({}acknowledged)",
+                if deleted.acknowledged { "" } else { "not " }
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of an index up to and including the `-` of a numeric
+/// version suffix, as in `products-` for `products-7`, or `None` if it has
+/// no such suffix.
+///
+fn version_prefix(name: &str) -> Option<&str> {
+    let (base, version) = name.rsplit_once('-')?;
+    if base.is_empty() || version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(&name[..base.len() + 1])
+}
+
+/// Settings that identify an individual index, and so always differ.
+const IDENTITY_SETTINGS: [&str; 6] = [
+    "index.creation_date",
+    "index.provided_name",
+    "index.uuid",
+    "index.version.created",
+    "index.version.upgraded",
+    "index.history.uuid",
+];
+
+/// Prints the settings of one or more indexes as a table, sorted by name.
+/// Default values, if requested, are marked as such.
+///
+async fn print_index_settings(es: &SimpleClient, index: &str, include_defaults: bool) -> ExitCode {
+    let settings = match es.get_index_settings(index, include_defaults).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let indexes = settings.as_object().cloned().unwrap_or_default();
+    let mut builder = tabled::builder::Builder::default();
+    if indexes.len() == 1 {
+        builder.push_record(["Setting", "Value", ""]);
+    } else {
+        builder.push_record(["Index", "Setting", "Value", ""]);
+    }
+    for (name, detail) in indexes.iter() {
+        let mut rows: Vec<(&String, &Value, &str)> = vec![];
+        for (section, marker) in [("settings", ""), ("defaults", "(default)")] {
+            for (key, value) in detail[section].as_object().into_iter().flatten() {
+                rows.push((key, value, marker));
+            }
+        }
+        rows.sort_by_key(|(key, _, _)| *key);
+        for (key, value, marker) in rows {
+            let value = match value {
+                Value::String(x) => x.to_owned(),
+                _ => value.to_string(),
+            };
+            if indexes.len() == 1 {
+                builder.push_record([key.to_owned(), value, marker.to_string()]);
+            } else {
+                builder.push_record([name.to_owned(), key.to_owned(), value, marker.to_string()]);
+            }
+        }
+    }
+    output::print_table(builder);
+    ExitCode::SUCCESS
+}
+
+/// Prints the cluster settings that have been set, alongside their default,
+/// persistent and transient values. The effective value is the transient
+/// one, if set, then the persistent one, then the default.
+///
+async fn print_cluster_settings(es: &SimpleClient, filter: &str) -> ExitCode {
+    let settings = match es.get_cluster_settings(true).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mut keys: Vec<&String> = ["persistent", "transient"]
+        .iter()
+        .flat_map(|section| settings[*section].as_object().into_iter().flatten())
+        .map(|(key, _)| key)
+        .filter(|key| key.contains(filter))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    if keys.is_empty() {
+        output::print_empty("No settings differ from their defaults");
+        return ExitCode::SUCCESS;
+    }
+    let cell = |section: &str, key: &str| match &settings[section][key] {
+        Value::Null => String::new(),
+        value => data::to_cell(value),
+    };
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Setting", "Default", "Persistent", "Transient"]);
+    for key in keys.iter() {
+        builder.push_record([
+            key.to_string(),
+            cell("defaults", key),
+            cell("persistent", key),
+            cell("transient", key),
+        ]);
+    }
+    output::print_table(builder);
+    ExitCode::SUCCESS
+}
+
+async fn print_mapping(es: &SimpleClient, index: &str, format: &MappingFormat) -> ExitCode {
+    let mappings = match es.get_mapping(index).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    match format {
+        MappingFormat::Json => output::print_json(&mappings, false),
+        MappingFormat::Table => {
+            let indexes = mappings.as_object().cloned().unwrap_or_default();
+            let mut builder = tabled::builder::Builder::default();
+            if indexes.len() == 1 {
+                builder.push_record(["Field", "Type"]);
+            } else {
+                builder.push_record(["Index", "Field", "Type"]);
+            }
+            for (name, detail) in indexes.iter() {
+                for (field, field_type) in mapping::field_types(&detail["mappings"]) {
+                    if indexes.len() == 1 {
+                        builder.push_record([field, field_type]);
+                    } else {
+                        builder.push_record([name.to_owned(), field, field_type]);
+                    }
+                }
+            }
+            output::print_table(builder);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prefixes of settings that are not copied when creating an index like
+/// another, as they are tied to the original index or would change the
+/// behaviour of the copy (e.g. by attaching it to a lifecycle policy).
+///
+const UNCOPIABLE_SETTINGS: [&str; 5] = [
+    "index.blocks.",
+    "index.lifecycle.",
+    "index.resize.",
+    "index.routing.allocation.initial_recovery.",
+    "index.shrink.",
+];
+
+/// Fetches the settings and mappings of an index, or of an index template if
+/// the name is given as `template:NAME`. Settings are returned as a flat map
+/// of fully qualified names, with identity settings removed.
+///
+async fn get_settings_and_mappings(
+    es: &SimpleClient,
+    name: &str,
+) -> Result<(Map<String, Value>, Value), client::Error> {
+    let (settings, mappings) = match name.strip_prefix("template:") {
+        Some(template) => {
+            let found = es.get_index_template(template).await?;
+            let detail = &found["index_templates"][0]["index_template"]["template"];
+            (detail["settings"].clone(), detail["mappings"].clone())
+        }
+        None => {
+            let patterns = [name];
+            let (settings, mappings) =
+                tokio::try_join!(es.get_settings(&patterns, &[]), es.get_mapping(name))?;
+            (
+                settings[name]["settings"].clone(),
+                mappings[name]["mappings"].clone(),
+            )
+        }
+    };
+    let settings = diff::flatten(&settings)
+        .into_iter()
+        .map(|(key, value)| match key.starts_with("index.") {
+            true => (key, value),
+            false => (format!("index.{key}"), value),
+        })
+        .filter(|(key, _)| !IDENTITY_SETTINGS.contains(&key.as_str()))
+        .collect();
+    Ok((settings, mappings))
+}
+
+async fn print_settings_diff(es: &SimpleClient, a: &str, b: &str) -> ExitCode {
+    let ((a_settings, a_mappings), (b_settings, b_mappings)) = match tokio::try_join!(
+        get_settings_and_mappings(es, a),
+        get_settings_and_mappings(es, b)
+    ) {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let settings_changes = diff::compare(
+        &diff::flatten(&Value::Object(a_settings)),
+        &diff::flatten(&Value::Object(b_settings)),
+    );
+    let mappings_changes = diff::compare(&diff::flatten(&a_mappings), &diff::flatten(&b_mappings));
+    println!("--- {a}");
+    println!("+++ {b}");
+    println!("Settings:");
+    if settings_changes.is_empty() {
+        println!("  (no differences)");
+    }
+    diff::print(&settings_changes);
+    println!("Mappings:");
+    if mappings_changes.is_empty() {
+        println!("  (no differences)");
+    }
+    diff::print(&mappings_changes);
+    ExitCode::SUCCESS
+}
+
+/// Data tiers, along with the `_cat/nodes` role abbreviations that denote them.
+const DATA_TIERS: [(&str, char); 5] = [
+    ("data_content", 's'),
+    ("data_hot", 'h'),
+    ("data_warm", 'w'),
+    ("data_cold", 'c'),
+    ("data_frozen", 'f'),
+];
+
+/// Determines the data tiers served by a node from its abbreviated role list.
+/// Nodes with the generic `data` role (`d`) belong to every tier.
+///
+fn node_tiers(roles: &str) -> Vec<&'static str> {
+    DATA_TIERS
+        .iter()
+        .filter(|(_, abbreviation)| roles.contains(*abbreviation) || roles.contains('d'))
+        .map(|(tier, _)| *tier)
+        .collect()
+}
+
+/// Shrinks, splits or clones an index into a new index. Writes to the source
+/// index are blocked first, as the resize APIs require, and the block is
+/// left in place afterwards so that the two indexes cannot drift apart. The
+/// new index is created without the block, and progress is reported until
+/// all of its shards are allocated and it turns green.
+///
+async fn resize(
+    es: &SimpleClient,
+    resize: Resize,
+    source: &str,
+    target: &str,
+    shards: Option<u32>,
+) -> ExitCode {
+    if let Err(e) = es
+        .put_index_settings(source, &json!({"index.blocks.write": true}))
+        .await
+    {
+        return fail(&e);
+    }
+    println!("Blocked writes to {source}");
+    let mut body = json!({"settings": {"index.blocks.write": null}});
+    if let Some(x) = shards {
+        body["settings"]["index.number_of_shards"] = json!(x);
+    }
+    match es.resize_index(resize, source, target, &body).await {
+        Ok(response) => println!(
+            "Started {resize} of {source} into {target} ({}acknowledged)",
+            if response.acknowledged { "" } else { "not " }
+        ),
+        Err(e) => {
+            return fail(&e);
+        }
+    }
+    let interrupted = Arc::new(AtomicBool::new(false));
+    tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    loop {
+        let health = match es.get_index_health(target).await {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+        let status = health["status"].as_str().unwrap_or("unknown");
+        print!(
+            "\r{target} is {status}: {} active, {} initializing, {} unassigned shards   ",
+            health["active_shards"], health["initializing_shards"], health["unassigned_shards"]
+        );
+        let _ = stdout().flush();
+        if status == "green" {
+            println!();
+            println!("Finished {resize} of {source} into {target}; {source} remains write-blocked");
+            return ExitCode::SUCCESS;
+        }
+        if interrupted.load(Ordering::SeqCst) {
+            println!();
+            println!("Stopped watching; {target} will continue to recover in the background");
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Creates or replaces an index template. If a template of the same name
+/// already exists, its body is first saved to the local history, so that a
+/// bad change can be rolled back. A rollback is itself a replacement, so the
+/// version rolled back from is saved too.
+///
+async fn replace_template(es: &SimpleClient, name: &str, body: &Value) -> ExitCode {
+    match es.find_index_template(name).await {
+        Ok(Some(previous)) => {
+            if let Err(e) = template::save(es.url(), name, &previous) {
+                eprintln!("Failed to save previous version of {name} ({e})");
+                return ExitCode::FAILURE;
+            }
+            println!("Saved previous version of {name} to history");
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return fail(&e);
+        }
+    }
+    match es.put_index_template(name, body).await {
+        Ok(response) => {
+            println!(
+                "Put index template {name} ({}acknowledged)",
+                if response.acknowledged { "" } else { "not " }
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// Lists index templates with their patterns, priorities and component
+/// templates, or component templates with the sections each defines.
+///
+async fn print_template_list(es: &SimpleClient, component: bool, pattern: &str) -> ExitCode {
+    let templates = match es.list_templates(component, pattern).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    if templates.is_empty() {
+        output::print_empty("No templates");
+        return ExitCode::SUCCESS;
+    }
+    let strings = |value: &Value| -> String {
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.as_str())
+            .collect::<Vec<&str>>()
+            .join(",")
+    };
+    let mut table = if component {
+        Table::with_column_names(vec![
+            "name".to_string(),
+            "version".to_string(),
+            "sections".to_string(),
+        ])
+    } else {
+        Table::with_column_names(vec![
+            "name".to_string(),
+            "index_patterns".to_string(),
+            "priority".to_string(),
+            "version".to_string(),
+            "composed_of".to_string(),
+        ])
+    };
+    for (name, body) in templates.iter() {
+        let version = data::to_cell(&body["version"]);
+        if component {
+            let sections: Vec<&str> = ["settings", "mappings", "aliases"]
+                .into_iter()
+                .filter(|x| body["template"].get(x).is_some())
+                .collect();
+            table.push_row(vec![name.to_owned(), version, sections.join(",")]);
+        } else {
+            table.push_row(vec![
+                name.to_owned(),
+                strings(&body["index_patterns"]),
+                data::to_cell(&body["priority"]),
+                version,
+                strings(&body["composed_of"]),
+            ]);
+        }
+    }
+    table.print();
+    ExitCode::SUCCESS
+}
+
+fn print_template_history(es: &SimpleClient, name: &str) -> ExitCode {
+    let versions = match template::history(es.url(), name) {
+        Ok(it) => it,
+        Err(e) => {
+            output::print_error(&e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if versions.is_empty() {
+        println!("No saved versions of {name}");
+        return ExitCode::SUCCESS;
+    }
+    let mut table = Table::with_column_names(vec![
+        "version".to_string(),
+        "saved".to_string(),
+        "file".to_string(),
+    ]);
+    for version in versions.iter().rev() {
+        let age = version.saved_at.elapsed().unwrap_or_default();
+        table.push_row(vec![
+            version.number.to_string(),
+            format!("{} ago", format_age(age)),
+            version.path.display().to_string(),
+        ]);
+    }
+    table.print();
+    ExitCode::SUCCESS
+}
+
+/// Shortens an ISO 8601 date and time, such as an index creation date or
+/// snapshot start time, to the minute (e.g. `2024-05-01 09:30`).
+///
+fn format_date_time(date: &str) -> String {
+    date.get(..16).unwrap_or(date).replacen('T', " ", 1)
+}
+
+/// Starts a snapshot and, with `wait`, shows its progress until it
+/// completes. Interrupting the wait leaves the snapshot running.
+///
+async fn create_snapshot(
+    es: &SimpleClient,
+    repository: &str,
+    snapshot: &str,
+    indices: Option<&str>,
+    wait: bool,
 ) -> ExitCode {
-    match es
-        .get_index_list(
-            &[index.clone().unwrap_or(String::from("*")).as_str()],
-            all,
-            open,
-            closed,
-        )
+    if let Err(e) = es.create_snapshot(repository, snapshot, indices).await {
+        return fail(&e);
+    }
+    println!("Started snapshot {snapshot} in {repository}");
+    if !wait {
+        return ExitCode::SUCCESS;
+    }
+    let interrupted = Arc::new(AtomicBool::new(false));
+    tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    loop {
+        let status = match es.get_snapshot_status(repository, Some(snapshot)).await {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+        let detail = &status["snapshots"][0];
+        let state = detail["state"].as_str().unwrap_or("UNKNOWN");
+        match state {
+            "SUCCESS" => {
+                println!();
+                println!("Snapshot {snapshot} completed");
+                return ExitCode::SUCCESS;
+            }
+            "FAILED" | "PARTIAL" => {
+                println!();
+                eprintln!(
+                    "Snapshot {snapshot} finished with state {state} ({} of {} shards failed)",
+                    detail["shards_stats"]["failed"], detail["shards_stats"]["total"]
+                );
+                return ExitCode::from(if state == "PARTIAL" {
+                    exit_status::PARTIAL
+                } else {
+                    exit_status::FAILURE
+                });
+            }
+            _ => {
+                print!("\r{}   ", format_snapshot_progress(detail));
+                let _ = stdout().flush();
+            }
+        }
+        if interrupted.load(Ordering::SeqCst) {
+            println!();
+            println!("Stopped watching; snapshot {snapshot} continues in the background");
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Summarises the progress of a snapshot from its status, as shards done
+/// out of the total and bytes processed out of those to be copied.
+///
+fn format_snapshot_progress(detail: &Value) -> String {
+    let shards = &detail["shards_stats"];
+    let stats = &detail["stats"];
+    let processed = stats["processed"]["size_in_bytes"].as_u64().unwrap_or(0);
+    let incremental = stats["incremental"]["size_in_bytes"].as_u64().unwrap_or(0);
+    format!(
+        "{} {}: {}/{} shards, {} of {} ({:.0}%)",
+        data::to_cell(&detail["snapshot"]),
+        data::to_cell(&detail["state"]).to_lowercase(),
+        shards["done"],
+        shards["total"],
+        format_bytes(processed),
+        format_bytes(incremental),
+        if incremental > 0 {
+            100.0 * processed as f64 / incremental as f64
+        } else {
+            100.0
+        }
+    )
+}
+
+/// Prints a table of transforms, with the source and destination indexes
+/// and current state of each.
+///
+async fn print_transforms(es: &SimpleClient) -> ExitCode {
+    let (transforms, stats) =
+        match tokio::try_join!(es.get_transforms(None), es.get_transform_stats("_all"),) {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+    let configs: Vec<&Value> = transforms["transforms"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .collect();
+    if configs.is_empty() {
+        output::print_empty("No transforms");
+        return ExitCode::SUCCESS;
+    }
+    let state_of = |id: &str| {
+        stats["transforms"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|x| x["id"].as_str() == Some(id))
+            .map_or(String::new(), |x| data::to_cell(&x["state"]))
+    };
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["id", "type", "source", "dest", "state"]);
+    for config in configs.iter() {
+        let id = config["id"].as_str().unwrap_or("");
+        let sources: Vec<String> = match &config["source"]["index"] {
+            Value::Array(x) => x.iter().map(data::to_cell).collect(),
+            x => vec![data::to_cell(x)],
+        };
+        builder.push_record([
+            id.to_string(),
+            if config.get("latest").is_some() {
+                "latest".to_string()
+            } else {
+                "pivot".to_string()
+            },
+            sources.join(","),
+            data::to_cell(&config["dest"]["index"]),
+            state_of(id),
+        ]);
+    }
+    output::print_table(builder);
+    ExitCode::SUCCESS
+}
+
+/// Prints the state, health, last checkpoint and document counts of a
+/// transform.
+///
+async fn print_transform_stats(es: &SimpleClient, id: &str) -> ExitCode {
+    let response = match es.get_transform_stats(id).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    for stats in response["transforms"].as_array().into_iter().flatten() {
+        let counts = &stats["stats"];
+        let last = &stats["checkpointing"]["last"];
+        println!("Transform: {}", data::to_cell(&stats["id"]));
+        println!("State: {}", data::to_cell(&stats["state"]));
+        if let Some(x) = stats["health"]["status"].as_str() {
+            println!("Health: {x}");
+        }
+        if let Some(x) = stats["reason"].as_str() {
+            println!("Reason: {x}");
+        }
+        match last["timestamp_millis"].as_u64() {
+            Some(millis) => println!(
+                "Last Checkpoint: {} ({} ago)",
+                last["checkpoint"],
+                format_age(
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH + Duration::from_millis(millis))
+                        .unwrap_or_default()
+                )
+            ),
+            None => println!("Last Checkpoint: none"),
+        }
+        println!(
+            "Documents: {} processed, {} indexed",
+            counts["documents_processed"], counts["documents_indexed"]
+        );
+        println!(
+            "Failures: {} search, {} index",
+            counts["search_failures"], counts["index_failures"]
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of watches, with the schedule, state and the time at
+/// which the condition was last checked and last met.
+///
+async fn print_watches(es: &SimpleClient) -> ExitCode {
+    let response = match es.query_watches().await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let watches: Vec<&Value> = response["watches"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .collect();
+    if watches.is_empty() {
+        output::print_empty("No watches");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record([
+        "id",
+        "active",
+        "schedule",
+        "actions",
+        "last_checked",
+        "last_met",
+    ]);
+    for watch in watches.iter() {
+        let status = &watch["status"];
+        let schedule = match watch["watch"]["trigger"]["schedule"].as_object() {
+            Some(x) => x
+                .iter()
+                .map(|(kind, value)| format!("{kind} {}", data::to_cell(value)))
+                .collect::<Vec<String>>()
+                .join(", "),
+            None => String::new(),
+        };
+        let actions: Vec<&String> = watch["watch"]["actions"]
+            .as_object()
+            .into_iter()
+            .flat_map(|x| x.keys())
+            .collect();
+        builder.push_record([
+            data::to_cell(&watch["_id"]),
+            data::to_cell(&status["state"]["active"]),
+            schedule,
+            actions
+                .iter()
+                .map(|x| x.as_str())
+                .collect::<Vec<&str>>()
+                .join(","),
+            status["last_checked"]
+                .as_str()
+                .map_or("-".to_string(), format_date_time),
+            status["last_met_condition"]
+                .as_str()
+                .map_or("-".to_string(), format_date_time),
+        ]);
+    }
+    output::print_table(builder);
+    ExitCode::SUCCESS
+}
+
+/// Prints the outcome of a watch execution: whether the condition was met
+/// and, for each action, its status and the request or message it sent (or,
+/// when simulated, would have sent).
+///
+fn print_watch_record(record: &Value) {
+    println!("Watch: {}", data::to_cell(&record["watch_id"]));
+    println!("State: {}", data::to_cell(&record["state"]));
+    let result = &record["result"];
+    println!(
+        "Condition: {}",
+        if result["condition"]["met"].as_bool().unwrap_or(false) {
+            "met"
+        } else {
+            "not met"
+        }
+    );
+    for action in result["actions"].as_array().into_iter().flatten() {
+        let kind = action["type"].as_str().unwrap_or("");
+        println!();
+        println!(
+            "Action: {} ({kind}) {}",
+            data::to_cell(&action["id"]),
+            data::to_cell(&action["status"])
+        );
+        if let Some(x) = action["reason"].as_str() {
+            println!("Reason: {x}");
+        }
+        if !action[kind].is_null() {
+            println!("{}", json::render(&action[kind], false));
+        }
+    }
+}
+
+/// Prints documents as a table, flattening nested objects into dotted
+/// columns, in the order in which they are first seen.
+///
+fn print_documents(documents: &[Value]) {
+    let mut columns: Vec<String> = vec![];
+    let mut rows: Vec<Vec<(String, Value)>> = vec![];
+    for document in documents.iter() {
+        let Some(object) = document.as_object() else {
+            continue;
+        };
+        let fields: HashMap<String, Value> = object.clone().into_iter().collect();
+        let flattened = data::flatten(&fields, FlattenPolicy::Objects);
+        for (name, _) in flattened.iter() {
+            if !columns.contains(name) {
+                columns.push(name.to_owned());
+            }
+        }
+        rows.push(flattened);
+    }
+    if rows.is_empty() {
+        output::print_empty("No rows");
+        return;
+    }
+    let mut table = Table::with_column_names(columns.clone());
+    for row in rows.iter() {
+        table.push_row(
+            columns
+                .iter()
+                .map(|column| {
+                    row.iter()
+                        .find(|(name, _)| name == column)
+                        .map_or(String::new(), |(_, value)| data::to_cell(value))
+                })
+                .collect(),
+        );
+    }
+    table.print();
+}
+
+/// Prints the cluster license, with the time remaining until it expires.
+/// Fails if the license is not active.
+///
+async fn print_license(es: &SimpleClient) -> ExitCode {
+    let license = match es.get_license().await {
+        Ok(it) => it["license"].to_owned(),
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let text = |value: &Value| value.as_str().unwrap_or("?").to_string();
+    let status = text(&license["status"]);
+    println!("Type: {}", text(&license["type"]));
+    println!("Status: {status}");
+    println!("Issued To: {}", text(&license["issued_to"]));
+    println!(
+        "Issued: {}",
+        license["issue_date"]
+            .as_str()
+            .map_or("?".to_string(), format_date_time)
+    );
+    match license["expiry_date_in_millis"].as_u64() {
+        Some(millis) => {
+            let expiry = UNIX_EPOCH + Duration::from_millis(millis);
+            let remaining = match expiry.duration_since(SystemTime::now()) {
+                Ok(it) => format!("in {}", format_age(it)),
+                Err(e) => format!("{} ago", format_age(e.duration())),
+            };
+            println!(
+                "Expires: {} ({remaining})",
+                license["expiry_date"]
+                    .as_str()
+                    .map_or("?".to_string(), format_date_time)
+            );
+        }
+        None => println!("Expires: never"),
+    }
+    match (
+        license["max_nodes"].as_u64(),
+        license["max_resource_units"].as_u64(),
+    ) {
+        (Some(x), _) => println!("Max Nodes: {x}"),
+        (None, Some(x)) => println!("Max Resource Units: {x}"),
+        _ => {}
+    }
+    if status == "active" {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Prints a table of X-Pack features, with whether each is available under
+/// the current license and whether it is enabled.
+///
+async fn print_features(es: &SimpleClient) -> ExitCode {
+    let info = match es.get_xpack_info().await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let flag = |value: &Value| match value.as_bool() {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "",
+    };
+    println!(
+        "License: {} ({})",
+        info["license"]["type"].as_str().unwrap_or("?"),
+        info["license"]["status"].as_str().unwrap_or("?")
+    );
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["feature", "available", "enabled"]);
+    for (name, feature) in info["features"].as_object().into_iter().flatten() {
+        builder.push_record([
+            name.as_str(),
+            flag(&feature["available"]),
+            flag(&feature["enabled"]),
+        ]);
+    }
+    output::print_table(builder);
+    ExitCode::SUCCESS
+}
+
+/// Prints the authenticated identity, including the realm or API key used
+/// and the roles held, and optionally the privileges granted by them.
+///
+async fn print_whoami(es: &SimpleClient, privileges: bool) -> ExitCode {
+    let identity = match es.authenticate().await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let text = |value: &Value| value.as_str().unwrap_or("?").to_string();
+    println!("User: {}", text(&identity["username"]));
+    if let Some(x) = identity["full_name"].as_str() {
+        println!("Full Name: {x}");
+    }
+    println!(
+        "Authenticated By: {} ({} realm {})",
+        text(&identity["authentication_type"]),
+        text(&identity["authentication_realm"]["type"]),
+        text(&identity["authentication_realm"]["name"])
+    );
+    if identity["api_key"].is_object() {
+        println!(
+            "API Key: {} ({})",
+            text(&identity["api_key"]["name"]),
+            text(&identity["api_key"]["id"])
+        );
+    }
+    let roles: Vec<String> = identity["roles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(data::to_cell)
+        .collect();
+    println!("Roles: {}", roles.join(", "));
+    if !privileges {
+        return ExitCode::SUCCESS;
+    }
+    let granted = match es.get_user_privileges().await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let strings = |value: &Value| -> String {
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(data::to_cell)
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    println!("Cluster Privileges: {}", strings(&granted["cluster"]));
+    println!("Index Privileges:");
+    for index in granted["indices"].as_array().into_iter().flatten() {
+        println!(
+            "  {}: {}",
+            strings(&index["names"]),
+            strings(&index["privileges"])
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of users, omitting reserved users such as `elastic`.
+///
+async fn print_users(es: &SimpleClient) -> ExitCode {
+    let users = match es.get_users(&[]).await {
+        Ok(it) => it.as_object().cloned().unwrap_or_default(),
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["user", "full name", "email", "roles", "enabled"]);
+    let mut count = 0;
+    for (name, user) in users.iter() {
+        if user["metadata"]["_reserved"].as_bool().unwrap_or(false) {
+            continue;
+        }
+        let roles: Vec<String> = user["roles"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(data::to_cell)
+            .collect();
+        builder.push_record([
+            name.to_owned(),
+            data::to_cell(&user["full_name"]),
+            data::to_cell(&user["email"]),
+            roles.join(","),
+            if user["enabled"].as_bool().unwrap_or(false) {
+                "yes".to_string()
+            } else {
+                "no".to_string()
+            },
+        ]);
+        count += 1;
+    }
+    if count == 0 {
+        output::print_empty("No users");
+    } else {
+        output::print_table(builder);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Creates or updates a user. Existing users keep their password, while
+/// new users must be given one.
+///
+async fn put_user(
+    es: &SimpleClient,
+    name: &str,
+    roles: &[String],
+    full_name: &Option<String>,
+    email: &Option<String>,
+) -> ExitCode {
+    let exists = match es.get_users(&[name]).await {
+        Ok(found) => found.get(name).is_some(),
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mut body = json!({ "roles": roles });
+    if let Some(x) = full_name {
+        body["full_name"] = json!(x);
+    }
+    if let Some(x) = email {
+        body["email"] = json!(x);
+    }
+    if !exists {
+        match read_new_password(name) {
+            Some(password) => body["password"] = json!(password),
+            None => return ExitCode::FAILURE,
+        }
+    }
+    match es.put_user(name, &body).await {
+        Ok(_) => {
+            println!("{} user {name}", if exists { "Updated" } else { "Created" });
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// Reads a new password for a user. On a terminal, the password is asked
+/// for twice without being echoed; otherwise, a single line is read from
+/// standard input, so that passwords can be piped in from scripts.
+///
+fn read_new_password(name: &str) -> Option<String> {
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        return match std::io::stdin().read_line(&mut line) {
+            Ok(_) if !line.trim().is_empty() => Some(line.trim().to_string()),
+            _ => {
+                eprintln!("No password given on standard input");
+                None
+            }
+        };
+    }
+    let password = mapping::prompt_hidden(&format!("New password for {name}: "));
+    if password.is_empty() {
+        eprintln!("Password cannot be empty");
+        return None;
+    }
+    if mapping::prompt_hidden("Confirm password: ") != password {
+        eprintln!("Passwords do not match");
+        return None;
+    }
+    Some(password)
+}
+
+/// Reads a secret, such as a password or API key, without echoing it on a
+/// terminal, or as a single line from standard input otherwise.
+///
+fn read_secret(what: &str) -> Option<String> {
+    let secret = if std::io::stdin().is_terminal() {
+        mapping::prompt_hidden(&format!("{what}: "))
+    } else {
+        let mut line = String::new();
+        let _ = std::io::stdin().read_line(&mut line);
+        line.trim().to_string()
+    };
+    if secret.is_empty() {
+        eprintln!("No {} given", what.to_lowercase());
+        return None;
+    }
+    Some(secret)
+}
+
+/// Prints a table of roles, with their cluster privileges and the index
+/// privileges granted for each group of index patterns. Built-in roles are
+/// omitted unless `all` is set.
+///
+async fn print_roles(es: &SimpleClient, all: bool) -> ExitCode {
+    let roles = match es.get_roles(&[]).await {
+        Ok(it) => it.as_object().cloned().unwrap_or_default(),
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let strings = |value: &Value| -> String {
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(data::to_cell)
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["role", "cluster", "indices"]);
+    let mut count = 0;
+    for (name, role) in roles.iter() {
+        if !all && role["metadata"]["_reserved"].as_bool().unwrap_or(false) {
+            continue;
+        }
+        let indices: Vec<String> = role["indices"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|x| format!("{}: {}", strings(&x["names"]), strings(&x["privileges"])))
+            .collect();
+        builder.push_record([
+            name.to_owned(),
+            strings(&role["cluster"]),
+            indices.join("; "),
+        ]);
+        count += 1;
+    }
+    if count == 0 {
+        output::print_empty("No roles");
+    } else {
+        output::print_table(builder);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of stored scripts, with the first line of each.
+///
+async fn print_scripts(es: &SimpleClient) -> ExitCode {
+    let scripts = match es.list_scripts().await {
+        Ok(it) => it.as_object().cloned().unwrap_or_default(),
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    if scripts.is_empty() {
+        output::print_empty("No scripts");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["id", "lang", "lines", "source"]);
+    for (id, script) in scripts.iter() {
+        let source = script["source"].as_str().unwrap_or("");
+        builder.push_record([
+            id.to_owned(),
+            data::to_cell(&script["lang"]),
+            source.lines().count().to_string(),
+            source.lines().next().unwrap_or("").trim().to_string(),
+        ]);
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::single(2), Alignment::right());
+    });
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of enrich policies. For each, the latest of its backing
+/// `.enrich-*` indexes is shown, with its health and document count, or a
+/// note that the policy has not been executed yet.
+///
+async fn print_enrich_policies(es: &SimpleClient) -> ExitCode {
+    let (response, backing) = match tokio::try_join!(
+        es.get_enrich_policies(),
+        es.get_index_list(&[".enrich-*"], true, true, false),
+    ) {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let policies: Vec<&Value> = response["policies"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .collect();
+    if policies.is_empty() {
+        output::print_empty("No policies");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record([
+        "policy",
+        "type",
+        "indices",
+        "match_field",
+        "enrich index",
+        "health",
+        "docs",
+    ]);
+    for policy in policies.iter() {
+        let Some((policy_type, config)) =
+            policy["config"].as_object().and_then(|x| x.iter().next())
+        else {
+            continue;
+        };
+        let name = config["name"].as_str().unwrap_or("");
+        // backing indexes are named .enrich-NAME-TIMESTAMP, so the latest sorts last
+        let latest = backing
+            .iter()
+            .filter(|x| {
+                x.name
+                    .strip_prefix(&format!(".enrich-{name}-"))
+                    .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+            })
+            .max_by(|a, b| a.name.cmp(&b.name));
+        let indices: Vec<String> = config["indices"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(data::to_cell)
+            .collect();
+        let mut record = vec![
+            name.to_string(),
+            policy_type.to_owned(),
+            indices.join(","),
+            data::to_cell(&config["match_field"]),
+        ];
+        match latest {
+            Some(index) => record.extend([
+                index.name.clone(),
+                index.health.clone(),
+                index.docs_count.unwrap_or(0).to_string(),
+            ]),
+            None => record.extend(["(not executed)".to_string(), String::new(), String::new()]),
+        }
+        builder.push_record(record);
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::single(6), Alignment::right());
+    });
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of snapshot lifecycle policies, with how long ago each
+/// last succeeded and failed. With `status`, the operation mode is printed
+/// first, and the command fails if any policy's last run was a failure, so
+/// that scheduled backups can be checked in scripts.
+///
+async fn print_slm_policies(es: &SimpleClient, status: bool) -> ExitCode {
+    if status {
+        match es.get_slm_status().await {
+            Ok(response) => println!(
+                "Operation mode: {}",
+                response["operation_mode"].as_str().unwrap_or("unknown")
+            ),
+            Err(e) => {
+                return fail(&e);
+            }
+        }
+    }
+    let policies = match es.get_slm_policies(&[]).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let policies = policies.as_object().cloned().unwrap_or_default();
+    if policies.is_empty() {
+        output::print_empty("No policies");
+        return ExitCode::SUCCESS;
+    }
+    let ago = |millis: &Value| match millis.as_u64() {
+        Some(x) => {
+            let then = UNIX_EPOCH + Duration::from_millis(x);
+            format!(
+                "{} ago",
+                format_age(SystemTime::now().duration_since(then).unwrap_or_default())
+            )
+        }
+        None => "never".to_string(),
+    };
+    let mut failing = 0;
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record([
+        "policy",
+        "schedule",
+        "repository",
+        "last success",
+        "last failure",
+    ]);
+    for (name, policy) in policies.iter() {
+        let success = policy["last_success"]["time"].as_u64().unwrap_or(0);
+        let failure = policy["last_failure"]["time"].as_u64().unwrap_or(0);
+        if failure > success {
+            failing += 1;
+        }
+        builder.push_record([
+            name.to_owned(),
+            data::to_cell(&policy["policy"]["schedule"]),
+            data::to_cell(&policy["policy"]["repository"]),
+            ago(&policy["last_success"]["time"]),
+            ago(&policy["last_failure"]["time"]),
+        ]);
+    }
+    output::print_table(builder);
+    if status && failing > 0 {
+        eprintln!("{failing} policies failed on their last run");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of the snapshots in a repository, oldest first.
+///
+async fn print_snapshots(es: &SimpleClient, repository: &str) -> ExitCode {
+    let response = match es.list_snapshots(repository).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let snapshots: Vec<&Value> = response["snapshots"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .collect();
+    if snapshots.is_empty() {
+        output::print_empty("No snapshots");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["snapshot", "state", "started", "took", "indices", "shards"]);
+    for snapshot in snapshots.iter() {
+        builder.push_record([
+            data::to_cell(&snapshot["snapshot"]),
+            data::to_cell(&snapshot["state"]),
+            snapshot["start_time"]
+                .as_str()
+                .map_or(String::new(), format_date_time),
+            snapshot["duration_in_millis"]
+                .as_u64()
+                .map_or(String::new(), |x| format_age(Duration::from_millis(x))),
+            snapshot["indices"]
+                .as_array()
+                .map_or(String::new(), |x| x.len().to_string()),
+            format!(
+                "{}/{}",
+                snapshot["shards"]["successful"], snapshot["shards"]["total"]
+            ),
+        ]);
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::new(3..6), Alignment::right());
+    });
+    output::print_note(&format!("{} snapshots", snapshots.len()));
+    ExitCode::SUCCESS
+}
+
+/// Prints the progress of a snapshot, or of each snapshot running in a
+/// repository.
+///
+async fn print_snapshot_status(
+    es: &SimpleClient,
+    repository: &str,
+    snapshot: Option<&str>,
+) -> ExitCode {
+    let status = match es.get_snapshot_status(repository, snapshot).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let snapshots: Vec<&Value> = status["snapshots"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .collect();
+    if snapshots.is_empty() {
+        output::print_empty("No snapshots running");
+    }
+    for detail in snapshots.iter() {
+        println!("{}", format_snapshot_progress(detail));
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of running tasks, longest running first. Detailed
+/// listings add each task's description and, for tasks that report counts
+/// of documents processed (such as reindex), its progress.
+///
+async fn print_tasks(es: &SimpleClient, actions: &[String], detailed: bool) -> ExitCode {
+    let patterns: Vec<&str> = actions.iter().map(|x| x.as_str()).collect();
+    let response = match es.list_tasks(&patterns, detailed).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mut tasks: Vec<&Value> = response["tasks"].as_array().into_iter().flatten().collect();
+    if tasks.is_empty() {
+        output::print_empty("No tasks");
+        return ExitCode::SUCCESS;
+    }
+    tasks.sort_by_key(|x| std::cmp::Reverse(x["running_time_in_nanos"].as_u64().unwrap_or(0)));
+    let mut builder = tabled::builder::Builder::default();
+    let mut header = vec!["id", "action", "running", "cancellable", "parent"];
+    if detailed {
+        header.extend(["progress", "description"]);
+    }
+    builder.push_record(header);
+    for task in tasks.iter() {
+        let mut record = vec![
+            format!("{}:{}", data::to_cell(&task["node"]), task["id"]),
+            data::to_cell(&task["action"]),
+            format_age(Duration::from_nanos(
+                task["running_time_in_nanos"].as_u64().unwrap_or(0),
+            )),
+            if task["cancellable"].as_bool().unwrap_or(false) {
+                "yes".to_string()
+            } else {
+                "no".to_string()
+            },
+            task["parent_task_id"].as_str().unwrap_or("").to_string(),
+        ];
+        if detailed {
+            let status = &task["status"];
+            let total = status["total"].as_u64().unwrap_or(0);
+            let done: u64 = [
+                "created",
+                "updated",
+                "deleted",
+                "noops",
+                "version_conflicts",
+            ]
+            .iter()
+            .map(|x| status[*x].as_u64().unwrap_or(0))
+            .sum();
+            record.push(if total > 0 {
+                format!("{:.1}%", 100.0 * done as f64 / total as f64)
+            } else {
+                String::new()
+            });
+            record.push(task["description"].as_str().unwrap_or("").to_string());
+        }
+        builder.push_record(record);
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::single(2), Alignment::right());
+    });
+    output::print_note(&format!("{} tasks", tasks.len()));
+    ExitCode::SUCCESS
+}
+
+/// Requests cancellation of a task, reporting any failures to cancel it.
+///
+async fn cancel_task(es: &SimpleClient, task_id: &str) -> ExitCode {
+    let response = match es.cancel_task(task_id).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let failures: Vec<&Value> = ["node_failures", "task_failures"]
+        .iter()
+        .flat_map(|key| response[*key].as_array().into_iter().flatten())
+        .collect();
+    if failures.is_empty() {
+        println!("Cancellation of task {task_id} requested");
+        ExitCode::SUCCESS
+    } else {
+        for failure in failures.iter() {
+            let reason = &failure["reason"];
+            eprintln!(
+                "Failed to cancel task {task_id}: {}",
+                reason["reason"]
+                    .as_str()
+                    .or(reason["type"].as_str())
+                    .unwrap_or("unknown reason")
+            );
+        }
+        ExitCode::FAILURE
+    }
+}
+
+/// Formats a duration coarsely, in the largest whole unit that fits.
+///
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3_600 => format!("{}m", secs / 60),
+        3_600..86_400 => format!("{}h", secs / 3_600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+/// Prints the shard counts reported by a broadcast operation such as a
+/// refresh or flush, failing if any shards could not be processed.
+///
+fn print_shards_outcome(done: &str, result: Result<Value, client::Error>) -> ExitCode {
+    match result {
+        Ok(response) => {
+            let shards = &response["_shards"];
+            println!(
+                "{done} {}/{} shards ({} failed)",
+                shards["successful"], shards["total"], shards["failed"]
+            );
+            if shards["failed"].as_u64().unwrap_or(0) > 0 {
+                ExitCode::from(exit_status::PARTIAL)
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(e) => fail(&e),
+    }
+}
+
+/// Force merges indexes as a background task, polling the task until it
+/// completes. Interrupting stops the polling but not the merge itself.
+///
+async fn forcemerge(
+    es: &SimpleClient,
+    indexes: &[String],
+    max_num_segments: Option<i64>,
+) -> ExitCode {
+    let patterns: Vec<&str> = indexes.iter().map(|x| x.as_str()).collect();
+    let task_id = match es.start_forcemerge(&patterns, max_num_segments).await {
+        Ok(response) => match response["task"].as_str() {
+            Some(it) => it.to_string(),
+            None => {
+                eprintln!("No task ID returned for force merge");
+                return ExitCode::FAILURE;
+            }
+        },
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    println!(
+        "Started force merge of {} (task {task_id})",
+        patterns.join(", ")
+    );
+    let interrupted = Arc::new(AtomicBool::new(false));
+    tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    loop {
+        let task = match es.get_task(&task_id).await {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+        let running =
+            Duration::from_nanos(task["task"]["running_time_in_nanos"].as_u64().unwrap_or(0));
+        if task["completed"].as_bool().unwrap_or(false) {
+            println!();
+            if let Some(error) = task.get("error") {
+                eprintln!(
+                    "Force merge failed after {running:.1?}: {}",
+                    error["reason"]
+                );
+                return ExitCode::FAILURE;
+            }
+            println!("Force merge completed in {running:.1?}");
+            return ExitCode::SUCCESS;
+        }
+        print!("\rForce merge running for {running:.0?}   ");
+        let _ = stdout().flush();
+        if interrupted.load(Ordering::SeqCst) {
+            println!();
+            println!("Stopped watching; task {task_id} continues in the background");
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Excludes a node from shard allocation, then waits for all its shards to
+/// be relocated elsewhere, reporting progress until the node is empty. Any
+/// existing exclusions are preserved.
+///
+async fn drain(es: &SimpleClient, node: &str, interval: f64) -> ExitCode {
+    const EXCLUDE_NAME: &str = "cluster.routing.allocation.exclude._name";
+    let settings = match es.get_cluster_settings(false).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let existing = settings["persistent"][EXCLUDE_NAME]
+        .as_str()
+        .or(settings["transient"][EXCLUDE_NAME].as_str())
+        .unwrap_or("");
+    let mut excluded: Vec<&str> = existing.split(',').filter(|x| !x.is_empty()).collect();
+    if !excluded.contains(&node) {
+        excluded.push(node);
+        let body = json!({"persistent": {EXCLUDE_NAME: excluded.join(",")}});
+        if let Err(e) = es.put_cluster_settings(&body).await {
+            return fail(&e);
+        }
+    }
+    println!(
+        "Excluded {node} from shard allocation ({EXCLUDE_NAME}={})",
+        excluded.join(",")
+    );
+    let interrupted = Arc::new(AtomicBool::new(false));
+    tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    let mut initial: Option<usize> = None;
+    loop {
+        let shards = match es.get_shard_list(&["*"], &[]).await {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+        // Relocating shards are listed against "SOURCE -> ADDRESS ID TARGET"
+        let remaining = shards
+            .iter()
+            .filter(|x| x["node"].as_str().and_then(|n| n.split(" -> ").next()) == Some(node))
+            .count();
+        let total = *initial.get_or_insert(remaining);
+        print!(
+            "\rShards remaining on {node}: {remaining}/{total} ({:.0}% moved)   ",
+            if total == 0 {
+                100.0
+            } else {
+                100.0 * (total - remaining.min(total)) as f64 / total as f64
+            }
+        );
+        let _ = stdout().flush();
+        if remaining == 0 {
+            println!();
+            println!("Node {node} holds no shards and is safe to stop");
+            return ExitCode::SUCCESS;
+        }
+        if interrupted.load(Ordering::SeqCst) {
+            println!();
+            println!("Stopped watching; {node} remains excluded from shard allocation");
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+        tokio::time::sleep(Duration::from_secs_f64(interval)).await;
+    }
+}
+
+/// Restarts each node in the cluster in turn, following the documented
+/// procedure: replica allocation is disabled, indexes are optionally
+/// flushed, and the node is restarted, either by running a hook command or
+/// by the operator. Once the node has rejoined, allocation is re-enabled
+/// and the cluster is given time to return to green before moving on. The
+/// elected master is restarted last.
+///
+async fn rolling_restart(
+    es: &SimpleClient,
+    pause_between: Duration,
+    flush: bool,
+    hook: &Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const ALLOCATION_ENABLE: &str = "cluster.routing.allocation.enable";
+    const POLL_INTERVAL: Duration = Duration::from_secs(5);
+    let nodes = es.get_node_list(&["name", "master"]).await?;
+    let mut names: Vec<(bool, &str)> = nodes
+        .iter()
+        .map(|x| {
+            (
+                x["master"].as_str() == Some("*"),
+                x["name"].as_str().unwrap_or(""),
+            )
+        })
+        .collect();
+    names.sort();
+    for (i, (_, name)) in names.iter().enumerate() {
+        if i > 0 && !pause_between.is_zero() {
+            println!("Pausing for {:?}", pause_between);
+            tokio::time::sleep(pause_between).await;
+        }
+        println!("[{}/{}] Restarting {name}", i + 1, names.len());
+        es.put_cluster_settings(&json!({"persistent": {ALLOCATION_ENABLE: "primaries"}}))
+            .await?;
+        println!("Disabled replica allocation");
+        if flush {
+            es.flush(&[]).await?;
+            println!("Flushed all indexes");
+        }
+        match hook {
+            Some(command) => {
+                let command = command.replace("{node}", name);
+                println!("Running {command}");
+                let status = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .status()
+                    .await?;
+                if !status.success() {
+                    return Err(format!(
+                        "Restart hook failed ({status}); replica allocation remains disabled"
+                    )
+                    .into());
+                }
+            }
+            None => {
+                mapping::prompt(&format!(
+                    "Restart {name} now, then press Enter to continue "
+                ));
+            }
+        }
+        println!("Waiting for {name} to rejoin the cluster");
+        loop {
+            let present = es
+                .get_node_list(&["name"])
+                .await
+                .is_ok_and(|nodes| nodes.iter().any(|x| x["name"].as_str() == Some(*name)));
+            if present {
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        es.put_cluster_settings(&json!({"persistent": {ALLOCATION_ENABLE: null}}))
+            .await?;
+        println!("Re-enabled allocation");
+        loop {
+            let health = es.get_cluster_health().await?;
+            let status = health["status"].as_str().unwrap_or("unknown");
+            print!(
+                "\rCluster is {status}: {} initializing, {} relocating, {} unassigned shards   ",
+                health["initializing_shards"],
+                health["relocating_shards"],
+                health["unassigned_shards"]
+            );
+            let _ = stdout().flush();
+            if status == "green" {
+                println!();
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+    println!("Restarted {} nodes", names.len());
+    Ok(())
+}
+
+/// Pairs each warning in a deprecation info response with the subject it
+/// applies to: the cluster, nodes, machine learning, or a named index, data
+/// stream, template or ILM policy.
+///
+fn deprecation_warnings(deprecations: &Value) -> Vec<(String, &Value)> {
+    let mut warnings: Vec<(String, &Value)> = vec![];
+    for (section, subject) in [
+        ("cluster_settings", "cluster"),
+        ("node_settings", "nodes"),
+        ("ml_settings", "machine learning"),
+    ] {
+        for warning in deprecations[section].as_array().into_iter().flatten() {
+            warnings.push((subject.to_string(), warning));
+        }
+    }
+    for (section, kind) in [
+        ("index_settings", ""),
+        ("data_streams", "data stream "),
+        ("templates", "template "),
+        ("ilm_policies", "ILM policy "),
+    ] {
+        for (name, items) in deprecations[section].as_object().into_iter().flatten() {
+            for warning in items.as_array().into_iter().flatten() {
+                warnings.push((format!("{kind}{name}"), warning));
+            }
+        }
+    }
+    warnings
+}
+
+/// Prints a table of deprecation warnings, most severe first, with a count
+/// by level. Fails if any warning is critical, as critical issues must be
+/// resolved before upgrading to the next major version, or if there are
+/// any warnings at all with `--fail-on warn`.
+///
+async fn print_deprecations(es: &SimpleClient, index: Option<&str>) -> ExitCode {
+    let deprecations = match es.get_deprecations(index).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mut warnings = deprecation_warnings(&deprecations);
+    if warnings.is_empty() {
+        output::print_empty("No deprecation warnings");
+        return ExitCode::SUCCESS;
+    }
+    let level_of = |warning: &Value| warning["level"].as_str().unwrap_or("warning").to_string();
+    warnings.sort_by(|a, b| {
+        (level_of(a.1) != "critical")
+            .cmp(&(level_of(b.1) != "critical"))
+            .then(a.0.cmp(&b.0))
+    });
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["level", "subject", "message", "url"]);
+    for (subject, warning) in warnings.iter() {
+        builder.push_record([
+            level_of(warning),
+            subject.to_string(),
+            data::to_cell(&warning["message"]),
+            data::to_cell(&warning["url"]),
+        ]);
+    }
+    output::print_table(builder);
+    let critical = warnings
+        .iter()
+        .filter(|(_, x)| level_of(x) == "critical")
+        .count();
+    output::print_note(&format!(
+        "\n{} critical, {} other warnings",
+        critical,
+        warnings.len() - critical
+    ));
+    if critical > 0 {
+        ExitCode::FAILURE
+    } else {
+        exit_status::success_unless_warned(true)
+    }
+}
+
+/// Checks for problems that would prevent or complicate an upgrade to the
+/// given major version, printing a checklist of remediation steps. Index
+/// creation versions and mappings are inspected directly, and the
+/// deprecation info API is consulted for everything else. Fails if any
+/// critical issue is found.
+///
+async fn preflight_upgrade(es: &SimpleClient, target: u64) -> ExitCode {
+    const VERSION_CREATED: &str = "index.version.created";
+    const LEGACY_MAPPING_FIELDS: [&str; 3] = ["_type", "_parent", "_all"];
+    let (settings, mappings, deprecations) = match tokio::try_join!(
+        es.get_settings(&["*"], &[VERSION_CREATED]),
+        es.get_mapping("*"),
+        es.get_deprecations(None),
+    ) {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    // Each issue is a (level, subject, description) triple
+    let mut issues: Vec<(String, String, String)> = vec![];
+    for (index, detail) in settings.as_object().into_iter().flatten() {
+        let created = detail["settings"][VERSION_CREATED]
+            .as_str()
+            .and_then(|x| x.parse::<u64>().ok())
+            .unwrap_or(0);
+        // Index versions are encoded with the major version in the millions
+        let created_major = created / 1_000_000;
+        if created_major > 0 && created_major + 1 < target {
+            issues.push((
+                "critical".to_string(),
+                index.to_owned(),
+                format!(
+                    "created in {created_major}.x, which {target}.x cannot read; reindex or delete it"
+                ),
+            ));
+        }
+    }
+    for (index, detail) in mappings.as_object().into_iter().flatten() {
+        let mapping = &detail["mappings"];
+        for field in LEGACY_MAPPING_FIELDS.iter() {
+            if mapping.get(field).is_some() {
+                issues.push((
+                    "critical".to_string(),
+                    index.to_owned(),
+                    format!("mapping contains legacy {field}; reindex into a new mapping"),
+                ));
+            }
+        }
+        for (name, field_type) in mapping::field_types(mapping) {
+            if field_type == "join" {
+                issues.push((
+                    "warning".to_string(),
+                    index.to_owned(),
+                    format!("join field {name} should be checked against {target}.x parent-child behaviour"),
+                ));
+            }
+        }
+    }
+    for (subject, warning) in deprecation_warnings(&deprecations) {
+        issues.push((
+            warning["level"].as_str().unwrap_or("warning").to_string(),
+            subject,
+            match warning["url"].as_str() {
+                Some(url) => {
+                    format!("{} (see {url})", warning["message"].as_str().unwrap_or(""))
+                }
+                None => warning["message"].as_str().unwrap_or("").to_string(),
+            },
+        ));
+    }
+    println!("Preflight check for upgrade to {target}.x");
+    println!();
+    if issues.is_empty() {
+        println!("No issues found");
+        return ExitCode::SUCCESS;
+    }
+    issues.sort_by(|a, b| {
+        (a.0 != "critical")
+            .cmp(&(b.0 != "critical"))
+            .then(a.1.cmp(&b.1))
+    });
+    for (level, subject, description) in issues.iter() {
+        println!("[ ] {subject}: {description} ({level})");
+    }
+    let critical = issues.iter().filter(|x| x.0 == "critical").count();
+    println!();
+    println!(
+        "{} critical, {} other issues",
+        critical,
+        issues.len() - critical
+    );
+    if critical > 0 {
+        ExitCode::FAILURE
+    } else {
+        exit_status::success_unless_warned(!issues.is_empty())
+    }
+}
+
+/// Prints a table of pending cluster tasks, in the order in which they will
+/// be executed.
+///
+async fn print_pending_tasks(es: &SimpleClient) -> ExitCode {
+    let response = match es.get_pending_tasks().await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let tasks: Vec<&Value> = response["tasks"].as_array().into_iter().flatten().collect();
+    if tasks.is_empty() {
+        output::print_empty("No pending tasks");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["order", "priority", "waiting", "executing", "source"]);
+    for task in tasks.iter() {
+        builder.push_record([
+            task["insert_order"].to_string(),
+            data::to_cell(&task["priority"]),
+            format_age(Duration::from_millis(
+                task["time_in_queue_millis"].as_u64().unwrap_or(0),
+            )),
+            if task["executing"].as_bool().unwrap_or(false) {
+                "yes".to_string()
+            } else {
+                String::new()
+            },
+            data::to_cell(&task["source"]),
+        ]);
+    }
+    output::print_table_with(builder, |table| {
+        table
+            .modify(Columns::first(), Alignment::right())
+            .modify(Columns::single(2), Alignment::right());
+    });
+    output::print_note(&format!("{} pending tasks", tasks.len()));
+    ExitCode::SUCCESS
+}
+
+/// Prints a summary of cluster statistics: node counts by role, index,
+/// shard and document counts, heap usage, and the spread of versions
+/// across nodes.
+///
+async fn print_cluster_stats(es: &SimpleClient) -> ExitCode {
+    let stats = match es
+        .get_cluster_stats(&[
+            "cluster_name",
+            "status",
+            "indices.count",
+            "indices.shards",
+            "indices.docs.count",
+            "indices.store.size_in_bytes",
+            "nodes.count",
+            "nodes.versions",
+            "nodes.jvm.mem",
+        ])
         .await
     {
-        Ok(index_list) => {
-            let mut builder = tabled::builder::Builder::default();
-            let mut has_rows = false;
-            for entry in index_list.iter() {
-                if all || !entry.name.starts_with('.') {
-                    builder.push_record(vec![
-                        match entry.health.as_str() {
-                            "green" => "🟢",
-                            "yellow" => "🟡",
-                            "red" => "🔴",
-                            _ => "⚫",
-                        },
-                        &entry.uuid,
-                        &entry.name,
-                        &format!("{} docs", entry.docs_count.unwrap_or(0),),
-                        &format!(
-                            "{:-#.1}",
-                            Byte::from_u64(entry.dataset_size.unwrap_or(0))
-                                .get_appropriate_unit(UnitType::Decimal)
-                        ),
-                        match entry.status.as_str() {
-                            "closed" => "🔒",
-                            _ => "",
-                        },
-                    ]);
-                    has_rows = true;
-                }
-            }
-            if has_rows {
-                println!(
-                    "{}",
-                    builder
-                        .build()
-                        .with(Style::empty())
-                        .modify(Columns::first(), Padding::new(0, 1, 0, 0))
-                        .modify(Columns::single(3), Alignment::right())
-                        .modify(Columns::single(4), Alignment::right())
-                );
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let indices = &stats["indices"];
+    let nodes = &stats["nodes"];
+    println!(
+        "Cluster Name: {}",
+        stats["cluster_name"].as_str().unwrap_or("?")
+    );
+    println!("Status: {}", stats["status"].as_str().unwrap_or("unknown"));
+    let roles: Vec<String> = nodes["count"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(role, count)| *role != "total" && count.as_u64().unwrap_or(0) > 0)
+        .map(|(role, count)| format!("{count} {role}"))
+        .collect();
+    println!("Nodes: {} ({})", nodes["count"]["total"], roles.join(", "));
+    println!("Indexes: {}", indices["count"]);
+    println!(
+        "Shards: {} ({} primary, replication {:.1})",
+        indices["shards"]["total"].as_u64().unwrap_or(0),
+        indices["shards"]["primaries"].as_u64().unwrap_or(0),
+        indices["shards"]["replication"].as_f64().unwrap_or(0.0)
+    );
+    println!(
+        "Documents: {}",
+        indices["docs"]["count"].as_u64().unwrap_or(0)
+    );
+    println!(
+        "Store Size: {}",
+        format_bytes(indices["store"]["size_in_bytes"].as_u64().unwrap_or(0))
+    );
+    let heap_used = nodes["jvm"]["mem"]["heap_used_in_bytes"]
+        .as_u64()
+        .unwrap_or(0);
+    let heap_max = nodes["jvm"]["mem"]["heap_max_in_bytes"]
+        .as_u64()
+        .unwrap_or(0);
+    println!(
+        "Heap: {} of {} ({:.1}%)",
+        format_bytes(heap_used),
+        format_bytes(heap_max),
+        if heap_max > 0 {
+            100.0 * heap_used as f64 / heap_max as f64
+        } else {
+            0.0
+        }
+    );
+    let versions: Vec<&str> = nodes["versions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|x| x.as_str())
+        .collect();
+    if versions.len() > 1 {
+        println!("Versions: {} (mixed)", versions.join(", "));
+    } else {
+        println!("Version: {}", versions.join(", "));
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a table of nodes, marking the elected master with an asterisk.
+///
+async fn print_nodes(es: &SimpleClient) -> ExitCode {
+    const COLUMNS: [&str; 9] = [
+        "master",
+        "name",
+        "node.role",
+        "heap.percent",
+        "disk.used_percent",
+        "disk.avail",
+        "load_1m",
+        "version",
+        "ip",
+    ];
+    let mut nodes = match es.get_node_list(&COLUMNS).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    nodes.sort_by(|a, b| data::to_cell(&a["name"]).cmp(&data::to_cell(&b["name"])));
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record([
+        "", "name", "roles", "heap", "disk", "avail", "load", "version", "ip",
+    ]);
+    for node in nodes.iter() {
+        builder.push_record(COLUMNS.map(|column| {
+            let value = node.get(column).unwrap_or(&Value::Null);
+            match (column, value.as_str()) {
+                ("heap.percent" | "disk.used_percent", Some(x)) => format!("{x}%"),
+                ("disk.avail", Some(x)) => x.parse::<u64>().map_or(x.to_string(), format_bytes),
+                _ => data::to_cell(value),
             }
-            ExitCode::SUCCESS
+        }));
+    }
+    output::print_table_with(builder, |table| {
+        table
+            .modify(Columns::first(), Padding::new(0, 1, 0, 0))
+            .modify(Columns::new(3..7), Alignment::right());
+    });
+    output::print_note(&format!("{} nodes", nodes.len()));
+    ExitCode::SUCCESS
+}
+
+/// Prints statistics for each matching node. The `jvm`, `os` and `fs`
+/// metrics are summarised; any others are printed as JSON.
+///
+async fn print_node_stats(es: &SimpleClient, node: &str, metrics: &[String]) -> ExitCode {
+    let metric_names: Vec<&str> = metrics.iter().map(|x| x.as_str()).collect();
+    let stats = match es.get_node_stats(node, &metric_names).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let nodes = match stats["nodes"].as_object() {
+        Some(it) if !it.is_empty() => it,
+        _ => {
+            eprintln!("No nodes match {node}");
+            return ExitCode::from(exit_status::REQUEST);
         }
+    };
+    for (id, stats) in nodes.iter() {
+        println!("{} ({id})", stats["name"].as_str().unwrap_or("?"));
+        for metric in metric_names.iter() {
+            let value = &stats[*metric];
+            match *metric {
+                "jvm" => {
+                    let mem = &value["mem"];
+                    println!(
+                        "  jvm: heap {}% ({} of {}), uptime {}",
+                        mem["heap_used_percent"],
+                        format_bytes(mem["heap_used_in_bytes"].as_u64().unwrap_or(0)),
+                        format_bytes(mem["heap_max_in_bytes"].as_u64().unwrap_or(0)),
+                        format_age(Duration::from_millis(
+                            value["uptime_in_millis"].as_u64().unwrap_or(0)
+                        ))
+                    );
+                    for (name, collector) in
+                        value["gc"]["collectors"].as_object().into_iter().flatten()
+                    {
+                        println!(
+                            "  jvm: gc {name} {} collections ({:.1}s)",
+                            collector["collection_count"],
+                            collector["collection_time_in_millis"]
+                                .as_f64()
+                                .unwrap_or(0.0)
+                                / 1000.0
+                        );
+                    }
+                }
+                "os" => {
+                    let load = &value["cpu"]["load_average"];
+                    println!(
+                        "  os: cpu {}%, load {}/{}/{}, memory {}% used",
+                        value["cpu"]["percent"],
+                        load["1m"],
+                        load["5m"],
+                        load["15m"],
+                        value["mem"]["used_percent"]
+                    );
+                }
+                "fs" => {
+                    let total = value["total"]["total_in_bytes"].as_u64().unwrap_or(0);
+                    let available = value["total"]["available_in_bytes"].as_u64().unwrap_or(0);
+                    println!(
+                        "  fs: {} available of {} ({:.1}% used)",
+                        format_bytes(available),
+                        format_bytes(total),
+                        if total > 0 {
+                            100.0 * (total - available.min(total)) as f64 / total as f64
+                        } else {
+                            0.0
+                        }
+                    );
+                }
+                _ => match serde_json::to_string_pretty(value) {
+                    Ok(json) => println!("  {metric}: {}", json.replace('\n', "\n  ")),
+                    Err(e) => output::print_error(&e),
+                },
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints cluster health, including node and shard counts, after waiting
+/// for a status if one is given. Fails if the wait times out, so that this
+/// can gate scripts on the cluster recovering after a restart.
+///
+async fn print_health(
+    es: &SimpleClient,
+    wait_for: Option<HealthStatus>,
+    timeout: &str,
+) -> ExitCode {
+    let status = wait_for.map(|x| match x {
+        HealthStatus::Green => elasticsearch::params::WaitForStatus::Green,
+        HealthStatus::Yellow => elasticsearch::params::WaitForStatus::Yellow,
+    });
+    let health = match es.wait_for_cluster_health(status, timeout).await {
+        Ok(it) => it,
         Err(e) => {
-            eprintln!("{}", e);
-            ExitCode::FAILURE
+            return fail(&e);
+        }
+    };
+    println!("{}", render_health(&health));
+    if health["timed_out"].as_bool().unwrap_or(false) {
+        eprintln!(
+            "Timed out after {timeout} waiting for the cluster to become {}",
+            match wait_for {
+                Some(HealthStatus::Green) => "green",
+                _ => "yellow",
+            }
+        );
+        return ExitCode::FAILURE;
+    }
+    exit_status::success_unless_warned(health["status"] != "green")
+}
+
+/// Shows cluster health, refreshed on an interval until interrupted.
+///
+async fn watch_health(es: &SimpleClient, interval: f64) -> ExitCode {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    let mut screen = Screen::new();
+    loop {
+        match es.get_cluster_health().await {
+            Ok(health) => screen.show(&render_health(&health)),
+            Err(e) => {
+                return fail(&e);
+            }
+        }
+        tokio::time::sleep(Duration::from_secs_f64(interval)).await;
+        if interrupted.load(Ordering::SeqCst) {
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+    }
+}
+
+/// Formats cluster health as lines of text, for printing or watching.
+///
+fn render_health(health: &Value) -> String {
+    let status = health["status"].as_str().unwrap_or("unknown");
+    [
+        format!(
+            "Cluster Name: {}",
+            health["cluster_name"].as_str().unwrap_or("?")
+        ),
+        format!(
+            "Status: {}",
+            output::paint(
+                output::health_style(status),
+                status,
+                output::colour_stdout()
+            )
+        ),
+        format!(
+            "Nodes: {} ({} data)",
+            health["number_of_nodes"], health["number_of_data_nodes"]
+        ),
+        format!(
+            "Shards: {} active ({} primary), {} relocating, {} initializing, {} unassigned",
+            health["active_shards"],
+            health["active_primary_shards"],
+            health["relocating_shards"],
+            health["initializing_shards"],
+            health["unassigned_shards"]
+        ),
+    ]
+    .join("\n")
+}
+
+/// Prints cluster name, health, node count, document count and disk usage.
+/// The figures come from two small requests made in parallel, so that the
+/// terse form is quick enough to run from a shell prompt. With `cache`, the
+/// terse line is kept in a temporary file and reused while fresh enough.
+///
+async fn print_status(es: &SimpleClient, terse: bool, cache: &Option<u64>) -> ExitCode {
+    let url = es.url();
+    let cache_path = env::temp_dir().join(format!(
+        "escli-status-{}-{}",
+        url.host_str().unwrap_or("localhost"),
+        url.port_or_known_default().unwrap_or(9200)
+    ));
+    if let (true, Some(max_age)) = (terse, cache) {
+        let fresh = fs::metadata(&cache_path)
+            .and_then(|x| x.modified())
+            .is_ok_and(|x| x.elapsed().is_ok_and(|age| age.as_secs() < *max_age));
+        if fresh {
+            if let Ok(line) = fs::read_to_string(&cache_path) {
+                println!("{}", line.trim_end());
+                return ExitCode::SUCCESS;
+            }
+        }
+    }
+    let (health, stats) = match tokio::try_join!(
+        es.get_cluster_health(),
+        es.get_cluster_stats(&["indices.docs.count", "nodes.fs"]),
+    ) {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let cluster_name = health["cluster_name"].as_str().unwrap_or("?");
+    let status = health["status"].as_str().unwrap_or("unknown");
+    let nodes = health["number_of_nodes"].as_u64().unwrap_or(0);
+    let docs = stats["indices"]["docs"]["count"].as_u64().unwrap_or(0);
+    let total = stats["nodes"]["fs"]["total_in_bytes"].as_u64().unwrap_or(0);
+    let available = stats["nodes"]["fs"]["available_in_bytes"]
+        .as_u64()
+        .unwrap_or(0);
+    let disk_used = if total > 0 {
+        100.0 * (total - available.min(total)) as f64 / total as f64
+    } else {
+        0.0
+    };
+    if terse {
+        let line = format!(
+            "{cluster_name} {status} {nodes}n {}docs {disk_used:.0}%disk",
+            format_count(docs)
+        );
+        println!("{line}");
+        if cache.is_some() {
+            let _ = fs::write(&cache_path, line);
+        }
+    } else {
+        println!("Cluster Name: {cluster_name}");
+        println!("Health: {status}");
+        println!("Nodes: {nodes}");
+        println!("Documents: {docs}");
+        println!(
+            "Disk Used: {disk_used:.1}% ({} of {})",
+            format_bytes(total - available.min(total)),
+            format_bytes(total)
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// Formats a count in full, with commas between groups of three digits.
+///
+fn format_thousands(count: u64) -> String {
+    let digits = count.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Formats a count compactly, using a `k`, `M` or `B` suffix as needed.
+///
+fn format_count(count: u64) -> String {
+    // Each suffix starts where rounding to one place would otherwise give
+    // 1000.0 of the one before
+    match count {
+        0..1_000 => count.to_string(),
+        1_000..999_950 => format!("{:.1}k", count as f64 / 1e3),
+        999_950..999_950_000 => format!("{:.1}M", count as f64 / 1e6),
+        _ => format!("{:.1}B", count as f64 / 1e9),
+    }
+}
+
+/// Prints the master-eligible nodes in the cluster, along with whether each
+/// is in the committed voting configuration or excluded from it, followed by
+/// a summary of the voting configuration and discovery status.
+///
+async fn print_quorum(es: &SimpleClient) -> ExitCode {
+    let (nodes, coordination, health) = match tokio::try_join!(
+        es.get_node_list(&["id", "name", "node.role", "master"]),
+        es.get_cluster_coordination(),
+        es.get_cluster_health(),
+    ) {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let ids_in = |key: &str| -> Vec<String> {
+        coordination[key]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.as_str().map(|id| id.to_string()))
+            .collect()
+    };
+    let committed = ids_in("last_committed_config");
+    let accepted = ids_in("last_accepted_config");
+    let exclusions: Vec<(&str, &str)> = coordination["voting_config_exclusions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|x| {
+            (
+                x["node_id"].as_str().unwrap_or(""),
+                x["node_name"].as_str().unwrap_or(""),
+            )
+        })
+        .collect();
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Name", "ID", "Voting", "Excluded", "Master"]);
+    let mut present_voters = 0;
+    for node in nodes
+        .iter()
+        .filter(|x| x["node.role"].as_str().unwrap_or("").contains('m'))
+    {
+        let id = node["id"].as_str().unwrap_or("");
+        let voting = committed.iter().any(|x| x == id);
+        if voting {
+            present_voters += 1;
+        }
+        builder.push_record([
+            node["name"].as_str().unwrap_or("").to_string(),
+            id.to_string(),
+            if voting { "yes" } else { "no" }.to_string(),
+            if exclusions.iter().any(|(x, _)| *x == id) {
+                "yes"
+            } else {
+                "no"
+            }
+            .to_string(),
+            if node["master"].as_str() == Some("*") {
+                "*"
+            } else {
+                ""
+            }
+            .to_string(),
+        ]);
+    }
+    output::print_table(builder);
+    println!();
+    let quorum = committed.len() / 2 + 1;
+    println!(
+        "Voting configuration: {} nodes, quorum {}, {} present",
+        committed.len(),
+        quorum,
+        present_voters
+    );
+    if committed != accepted {
+        println!("Voting configuration is being changed (accepted but not yet committed)");
+    }
+    if exclusions.is_empty() {
+        println!("Exclusions: none");
+    } else {
+        let names: Vec<&str> = exclusions
+            .iter()
+            .map(|(id, name)| if name.is_empty() { *id } else { *name })
+            .collect();
+        println!("Exclusions: {}", names.join(", "));
+    }
+    println!(
+        "Discovery: {} master, {} nodes, status {}",
+        if nodes.iter().any(|x| x["master"].as_str() == Some("*")) {
+            "elected"
+        } else {
+            "no"
+        },
+        health["number_of_nodes"],
+        health["status"].as_str().unwrap_or("unknown")
+    );
+    if present_voters < quorum {
+        eprintln!("Warning: fewer voting nodes are present than are needed for a quorum");
+    }
+    ExitCode::SUCCESS
+}
+
+async fn print_tiers(es: &SimpleClient) -> ExitCode {
+    const TIER_PREFERENCE: &str = "index.routing.allocation.include._tier_preference";
+    let (nodes, shards, settings) = match tokio::try_join!(
+        es.get_node_list(&["name", "node.role"]),
+        es.get_shard_list(&["*"], &[]),
+        es.get_settings(&["*"], &[TIER_PREFERENCE]),
+    ) {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let tiers_by_node: HashMap<&str, Vec<&str>> = nodes
+        .iter()
+        .map(|node| {
+            (
+                node["name"].as_str().unwrap_or(""),
+                node_tiers(node["node.role"].as_str().unwrap_or("")),
+            )
+        })
+        .collect();
+    let mut nodes_by_tier: HashMap<&str, usize> = HashMap::new();
+    for tiers in tiers_by_node.values() {
+        for tier in tiers.iter() {
+            *nodes_by_tier.entry(tier).or_insert(0) += 1;
+        }
+    }
+    let mut indexes_by_tier: HashMap<&str, usize> = HashMap::new();
+    let mut bytes_by_tier: HashMap<&str, u64> = HashMap::new();
+    let mut violations: Vec<String> = vec![];
+    let empty = Map::new();
+    for (index, detail) in settings.as_object().unwrap_or(&empty).iter() {
+        let preference: Vec<&str> = detail["settings"][TIER_PREFERENCE]
+            .as_str()
+            .unwrap_or("")
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .collect();
+        let preferred = match preference
+            .iter()
+            .find(|tier| nodes_by_tier.contains_key(*tier))
+        {
+            Some(tier) => *tier,
+            None => {
+                if !preference.is_empty() {
+                    violations.push(format!(
+                        "{index} prefers {} but no nodes exist in those tiers",
+                        preference.join(", ")
+                    ));
+                }
+                continue;
+            }
+        };
+        *indexes_by_tier.entry(preferred).or_insert(0) += 1;
+        for shard in shards.iter().filter(|x| x["index"].as_str() == Some(index)) {
+            let node = shard["node"].as_str().unwrap_or("");
+            let size = shard["store"]
+                .as_str()
+                .and_then(|x| x.parse::<u64>().ok())
+                .unwrap_or(0);
+            *bytes_by_tier.entry(preferred).or_insert(0) += size;
+            match tiers_by_node.get(node) {
+                Some(tiers) if !tiers.contains(&preferred) => violations.push(format!(
+                    "{index} prefers {preferred} but has a shard on {node} ({})",
+                    if tiers.is_empty() {
+                        "no data tier".to_string()
+                    } else {
+                        tiers.join(", ")
+                    }
+                )),
+                _ => {}
+            }
+        }
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Tier", "Nodes", "Indexes", "Size"]);
+    for (tier, _) in DATA_TIERS.iter() {
+        builder.push_record([
+            tier.to_string(),
+            nodes_by_tier.get(tier).unwrap_or(&0).to_string(),
+            indexes_by_tier.get(tier).unwrap_or(&0).to_string(),
+            format_bytes(*bytes_by_tier.get(tier).unwrap_or(&0)),
+        ]);
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::new(1..4), Alignment::right());
+    });
+    if violations.is_empty() {
+        println!("All indexes are allocated to their preferred tiers");
+    } else {
+        violations.sort();
+        violations.dedup();
+        println!("Indexes not allocated to their preferred tiers:");
+        for violation in violations.iter() {
+            println!("  {}", violation);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!(
+        "{:-#.1}",
+        Byte::from_u64(bytes).get_appropriate_unit(UnitType::Decimal)
+    )
+}
+
+/// Prints a table of statistics for each index matching a pattern. With
+/// `watch`, the table is redrawn at each interval until interrupted, with
+/// indexing and search rates worked out from the previous sample.
+///
+async fn print_index_stats(es: &SimpleClient, index: &str, watch: &Option<f64>) -> ExitCode {
+    let metrics = [
+        "docs",
+        "store",
+        "indexing",
+        "search",
+        "merge",
+        "segments",
+        "fielddata",
+        "query_cache",
+    ];
+    let interrupted = Arc::new(AtomicBool::new(false));
+    if watch.is_some() {
+        tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    }
+    let mut previous: Option<(RawIndicesStats, SystemTime)> = None;
+    let mut screen = Screen::new();
+    loop {
+        let stats = match es.get_index_stats(&[index], &metrics).await {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+        let now = SystemTime::now();
+        let mut names: Vec<&String> = stats.indices.keys().collect();
+        names.sort();
+        let mut builder = tabled::builder::Builder::default();
+        builder.push_record([
+            "Index",
+            "Docs",
+            "Store",
+            "Indexed/s",
+            "Searches/s",
+            "Merges",
+            "Segments",
+            "Fielddata",
+            "Query Cache",
+        ]);
+        for name in names {
+            let total = &stats.indices[name].total;
+            let rates = previous.as_ref().and_then(|(earlier, then)| {
+                let seconds = now.duration_since(*then).ok()?.as_secs_f64();
+                let earlier = &earlier.indices.get(name)?.total;
+                Some((
+                    total
+                        .indexing
+                        .index_total
+                        .saturating_sub(earlier.indexing.index_total) as f64
+                        / seconds,
+                    total
+                        .search
+                        .query_total
+                        .saturating_sub(earlier.search.query_total) as f64
+                        / seconds,
+                ))
+            });
+            builder.push_record([
+                name.to_string(),
+                total.docs.count.to_string(),
+                format_bytes(total.store.size_in_bytes),
+                rates.map_or("-".to_string(), |x| format!("{:.2}", x.0)),
+                rates.map_or("-".to_string(), |x| format!("{:.2}", x.1)),
+                format!("{} ({} running)", total.merges.total, total.merges.current),
+                total.segments.count.to_string(),
+                format_bytes(total.fielddata.memory_size_in_bytes),
+                format_bytes(total.query_cache.memory_size_in_bytes),
+            ]);
+        }
+        let align = |table: &mut tabled::Table| {
+            table.modify(Columns::new(1..9), Alignment::right());
+        };
+        let interval = match watch {
+            Some(x) => *x,
+            None => {
+                output::print_table_with(builder, align);
+                return ExitCode::SUCCESS;
+            }
+        };
+        if output::format() == OutputFormat::Table {
+            screen.show(&output::render_table_with(builder, align));
+        } else {
+            output::print_table(builder);
+        }
+        previous = Some((stats, now));
+        tokio::time::sleep(Duration::from_secs_f64(interval)).await;
+        if interrupted.load(Ordering::SeqCst) {
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+    }
+}
+
+/// Prints the shards of an index. For each unassigned shard, the reason is
+/// shown, along with the explanation given by the allocation explain API
+/// (for up to a fixed number of shards, as each needs a separate request).
+///
+async fn print_shards(es: &SimpleClient, index: &str) -> ExitCode {
+    const COLUMNS: [&str; 7] = [
+        "index",
+        "shard",
+        "prirep",
+        "state",
+        "node",
+        "store",
+        "unassigned.reason",
+    ];
+    const MAX_EXPLAINED: usize = 10;
+    let shards = match es.get_shard_list(&[index], &COLUMNS).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    if shards.is_empty() {
+        output::print_empty("No shards");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record([
+        "index", "shard", "prirep", "state", "node", "size", "reason",
+    ]);
+    for shard in shards.iter() {
+        builder.push_record(COLUMNS.map(|column| {
+            let value = shard.get(column).unwrap_or(&Value::Null);
+            match (column, value.as_str().and_then(|x| x.parse::<u64>().ok())) {
+                ("store", Some(bytes)) => format_bytes(bytes),
+                _ => data::to_cell(value),
+            }
+        }));
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::single(5), Alignment::right());
+    });
+    let unassigned: Vec<&HashMap<String, Value>> = shards
+        .iter()
+        .filter(|x| x.get("state").and_then(|s| s.as_str()) == Some("UNASSIGNED"))
+        .collect();
+    for shard in unassigned.iter().take(MAX_EXPLAINED) {
+        let name = shard.get("index").and_then(|x| x.as_str()).unwrap_or("");
+        let number = shard
+            .get("shard")
+            .and_then(|x| x.as_str())
+            .and_then(|x| x.parse::<u32>().ok())
+            .unwrap_or(0);
+        let primary = shard.get("prirep").and_then(|x| x.as_str()) == Some("p");
+        println!();
+        println!(
+            "{name}[{number}] ({}):",
+            if primary { "primary" } else { "replica" }
+        );
+        match es.explain_allocation(Some((name, number, primary))).await {
+            Ok(explanation) => println!(
+                "  {}",
+                explanation["allocate_explanation"]
+                    .as_str()
+                    .unwrap_or("no explanation given")
+            ),
+            Err(e) => println!("  {e}"),
+        }
+    }
+    if unassigned.len() > MAX_EXPLAINED {
+        println!();
+        println!(
+            "{} more unassigned shards not explained; use allocation-explain for details",
+            unassigned.len() - MAX_EXPLAINED
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints an allocation explanation as a summary of the shard and its state,
+/// followed by the decision for each node and the deciders behind it.
+///
+fn print_allocation_explanation(explanation: &Value) {
+    let text = |value: &Value| value.as_str().unwrap_or("?").to_string();
+    println!(
+        "Shard: {}[{}] ({})",
+        text(&explanation["index"]),
+        explanation["shard"],
+        if explanation["primary"].as_bool().unwrap_or(false) {
+            "primary"
+        } else {
+            "replica"
+        }
+    );
+    println!(
+        "State: {}",
+        text(&explanation["current_state"]).to_lowercase()
+    );
+    let unassigned = &explanation["unassigned_info"];
+    if unassigned.is_object() {
+        println!(
+            "Unassigned: {} since {}",
+            text(&unassigned["reason"]),
+            text(&unassigned["at"])
+        );
+        if let Some(details) = unassigned["details"].as_str() {
+            println!("Details: {details}");
+        }
+    }
+    if let Some(node) = explanation["current_node"]["name"].as_str() {
+        println!("Node: {node}");
+    }
+    for (label, key) in [
+        ("Can allocate", "can_allocate"),
+        ("Can remain", "can_remain_on_current_node"),
+        ("Can rebalance", "can_rebalance_cluster"),
+    ] {
+        if let Some(decision) = explanation[key].as_str() {
+            println!("{label}: {decision}");
+        }
+    }
+    for key in ["allocate_explanation", "rebalance_explanation"] {
+        if let Some(x) = explanation[key].as_str() {
+            println!();
+            println!("{x}");
+        }
+    }
+    let decisions = explanation["node_allocation_decisions"]
+        .as_array()
+        .map(|x| x.as_slice())
+        .unwrap_or_default();
+    if !decisions.is_empty() {
+        println!();
+        println!("Node decisions:");
+    }
+    for decision in decisions.iter() {
+        println!(
+            "  {}: {}",
+            text(&decision["node_name"]),
+            text(&decision["node_decision"])
+        );
+        for decider in decision["deciders"].as_array().into_iter().flatten() {
+            println!(
+                "    {} ({}): {}",
+                text(&decider["decider"]),
+                text(&decider["decision"]),
+                text(&decider["explanation"])
+            );
+        }
+    }
+}
+
+async fn print_segments(es: &SimpleClient, index: &str) -> ExitCode {
+    const COLUMNS: [&str; 9] = [
+        "index",
+        "shard",
+        "prirep",
+        "segment",
+        "docs.count",
+        "docs.deleted",
+        "size",
+        "committed",
+        "searchable",
+    ];
+    let segments = match es.get_segment_list(&[index], &COLUMNS).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    if segments.is_empty() {
+        output::print_empty("No segments");
+        return ExitCode::SUCCESS;
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(COLUMNS);
+    for segment in segments.iter() {
+        builder.push_record(COLUMNS.map(|column| {
+            let value = segment.get(column).unwrap_or(&Value::Null);
+            match (column, value.as_str().and_then(|x| x.parse::<u64>().ok())) {
+                ("size", Some(bytes)) => format_bytes(bytes),
+                _ => data::to_cell(value),
+            }
+        }));
+    }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::new(4..7), Alignment::right());
+    });
+    output::print_note(&format!("{} segments", segments.len()));
+    ExitCode::SUCCESS
+}
+
+/// Prints the progress of each shard recovery, as a percentage of files,
+/// bytes and translog operations recovered. With `watch`, the table is
+/// printed again at each interval until interrupted.
+///
+async fn print_recovery(
+    es: &SimpleClient,
+    index: &str,
+    active_only: bool,
+    watch: &Option<f64>,
+) -> ExitCode {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    if watch.is_some() {
+        tokio::spawn(watch_for_interrupt(interrupted.clone()));
+    }
+    loop {
+        let recovery = match es.get_recovery(&[index], active_only).await {
+            Ok(it) => it,
+            Err(e) => {
+                return fail(&e);
+            }
+        };
+        let mut builder = tabled::builder::Builder::default();
+        builder.push_record([
+            "Index", "Shard", "Type", "Stage", "Source", "Target", "Files", "Bytes", "Translog",
+            "Time",
+        ]);
+        let mut rows = 0;
+        let mut names: Vec<&String> = recovery
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|x| x.0)
+            .collect();
+        names.sort();
+        for name in names {
+            for shard in recovery[name]["shards"].as_array().into_iter().flatten() {
+                rows += 1;
+                builder.push_record([
+                    name.to_string(),
+                    format!(
+                        "{}{}",
+                        shard["id"],
+                        if shard["primary"].as_bool().unwrap_or(false) {
+                            "p"
+                        } else {
+                            "r"
+                        }
+                    ),
+                    data::to_cell(&shard["type"]).to_lowercase(),
+                    data::to_cell(&shard["stage"]).to_lowercase(),
+                    data::to_cell(&shard["source"]["name"]),
+                    data::to_cell(&shard["target"]["name"]),
+                    data::to_cell(&shard["index"]["files"]["percent"]),
+                    data::to_cell(&shard["index"]["size"]["percent"]),
+                    data::to_cell(&shard["translog"]["percent"]),
+                    format!(
+                        "{:.1?}",
+                        Duration::from_millis(shard["total_time_in_millis"].as_u64().unwrap_or(0))
+                    ),
+                ]);
+            }
+        }
+        if rows == 0 {
+            println!("No {}recoveries", if active_only { "active " } else { "" });
+        } else {
+            output::print_table_with(builder, |table| {
+                table.modify(Columns::new(6..10), Alignment::right());
+            });
+        }
+        let interval = match watch {
+            Some(x) if !(active_only && rows == 0) => *x,
+            _ => return ExitCode::SUCCESS,
+        };
+        tokio::time::sleep(Duration::from_secs_f64(interval)).await;
+        if interrupted.load(Ordering::SeqCst) {
+            return ExitCode::from(exit_status::INTERRUPTED);
+        }
+        println!();
+    }
+}
+
+async fn print_usage_report(es: &SimpleClient, index: &Option<String>, interval: f64) -> ExitCode {
+    let patterns = [index.as_deref().unwrap_or("*")];
+    let metrics = ["indexing", "search"];
+    let before = match es.get_index_stats(&patterns, &metrics).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    eprintln!("Sampling activity for {interval}s...");
+    tokio::time::sleep(Duration::from_secs_f64(interval)).await;
+    let after = match es.get_index_stats(&patterns, &metrics).await {
+        Ok(it) => it,
+        Err(e) => {
+            return fail(&e);
+        }
+    };
+    let mut activity: Vec<(&String, u64, u64)> = after
+        .indices
+        .iter()
+        .map(|(name, stats)| {
+            let (queries, writes) = match before.indices.get(name) {
+                Some(earlier) => (
+                    stats
+                        .total
+                        .search
+                        .query_total
+                        .saturating_sub(earlier.total.search.query_total),
+                    stats
+                        .total
+                        .indexing
+                        .index_total
+                        .saturating_sub(earlier.total.indexing.index_total),
+                ),
+                None => (0, 0),
+            };
+            (name, queries, writes)
+        })
+        .collect();
+    activity.sort_by(|a, b| (b.1 + b.2).cmp(&(a.1 + a.2)).then(a.0.cmp(b.0)));
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Index", "Queries", "Queries/s", "Writes", "Writes/s", ""]);
+    let mut idle = 0;
+    for (name, queries, writes) in activity.iter() {
+        if queries + writes == 0 {
+            idle += 1;
         }
+        builder.push_record([
+            name.to_string(),
+            queries.to_string(),
+            format!("{:.2}", *queries as f64 / interval),
+            writes.to_string(),
+            format!("{:.2}", *writes as f64 / interval),
+            if queries + writes == 0 { "idle" } else { "" }.to_string(),
+        ]);
     }
+    output::print_table_with(builder, |table| {
+        table.modify(Columns::new(1..5), Alignment::right());
+    });
+    output::print_note(&format!(
+        "{} of {} indexes had no search or write activity",
+        idle,
+        activity.len()
+    ));
+    ExitCode::SUCCESS
 }
 
-fn print_bulk_summary(summary: &RawBulkSummary) {
+/// Prints counts of the documents loaded, skipped and failed, returning a
+/// partial failure status if any documents failed.
+///
+fn print_bulk_summary(summary: &LoadSummary) -> ExitCode {
     let mut results: HashMap<String, usize> = HashMap::new();
+    let mut failures: HashMap<String, usize> = HashMap::new();
+    let mut skipped: usize = 0;
     for item in summary.items.iter() {
-        for (_key, value) in item.iter() {
-            *results.entry(value.result.to_string()).or_insert(0) += 1;
+        for (key, value) in item.iter() {
+            if key == "create" && value.status == 409 {
+                skipped += 1;
+                continue;
+            }
+            match (&value.result, &value.error) {
+                (Some(result), None) => *results.entry(result.to_string()).or_insert(0) += 1,
+                (_, Some(error)) => *failures.entry(error.type_code.to_string()).or_insert(0) += 1,
+                (None, None) => *failures.entry(value.status.to_string()).or_insert(0) += 1,
+            }
         }
     }
+    if output::format() == OutputFormat::Json {
+        let summary = json!({
+            "results": results,
+            "skipped": skipped,
+            "failures": failures,
+            "interrupted": summary.interrupted,
+            "last_position": summary.last_position,
+        });
+        output::print_json(&summary, false);
+    } else {
+        print_bulk_counts(results, skipped, &failures, summary);
+    }
+    if summary.interrupted {
+        ExitCode::from(exit_status::INTERRUPTED)
+    } else if !failures.is_empty() {
+        ExitCode::from(exit_status::PARTIAL)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn print_bulk_counts(
+    results: HashMap<String, usize>,
+    skipped: usize,
+    failures: &HashMap<String, usize>,
+    summary: &LoadSummary,
+) {
     for (actioned, count) in results.into_iter() {
         println!("Successfully {} {} documents", actioned, count);
     }
+    if skipped > 0 {
+        println!("Skipped {} documents that were already loaded", skipped);
+    }
+    for (reason, count) in failures.iter() {
+        println!("Failed to load {} documents ({})", count, reason);
+    }
+    if summary.interrupted {
+        match &summary.last_position {
+            Some(position) => println!("Interrupted, last record sent was at {}", position),
+            None => println!("Interrupted before any records were sent"),
+        }
+    }
 }
 
-fn print_search_result(result: &RawSearchResult, format: &SearchResultFormat) {
+fn print_search_result(
+    result: &RawSearchResult,
+    format: &SearchResultFormat,
+    compact: bool,
+    nested: NestedDisplay,
+    with_meta: bool,
+) {
+    let documents: Vec<HashMap<String, Value>> = result
+        .hits
+        .hits
+        .iter()
+        .map(|hit| hit_document(hit, with_meta))
+        .collect();
     match format {
         SearchResultFormat::Raw => {
             for hit in result.hits.hits.iter() {
-                println!("{:?}", hit);
+                match serde_json::to_value(hit) {
+                    Ok(value) => output::print_json(&value, compact),
+                    Err(e) => output::print_error(&e),
+                }
             }
         }
-        SearchResultFormat::Table => {
-            let mut table = Table::new();
+        SearchResultFormat::Debug => {
             for hit in result.hits.hits.iter() {
-                table.push_document(&hit._source);
+                println!("{:?}", hit);
             }
+        }
+        SearchResultFormat::Json => {
+            output::print_json(&json!(documents), compact);
+        }
+        SearchResultFormat::Ndjson => {
+            for document in documents.iter() {
+                output::print_json(&json!(document), true);
+            }
+        }
+        SearchResultFormat::Csv | SearchResultFormat::Tsv => {
+            let delimiter = if *format == SearchResultFormat::Csv {
+                b','
+            } else {
+                b'\t'
+            };
+            if let Err(e) = hits_table(&documents, nested, with_meta).write_delimited(delimiter) {
+                output::print_error(&e);
+            }
+        }
+        SearchResultFormat::Yaml => {
+            // Converted to a JSON value first, so that keys are sorted
+            match serde_yaml::to_string(&json!(documents)) {
+                Ok(yaml) => print!("{yaml}"),
+                Err(e) => output::print_error(&e),
+            }
+        }
+        SearchResultFormat::Vertical => {
+            let table = hits_table(&documents, nested, with_meta);
+            if table.count_rows() == 0 {
+                println!("No rows")
+            } else {
+                table.print_vertical();
+            }
+        }
+        SearchResultFormat::Table => {
+            let table = hits_table(&documents, nested, with_meta);
             if table.count_rows() == 0 {
                 println!("No rows")
             } else {
@@ -345,3 +8022,95 @@ fn print_search_result(result: &RawSearchResult, format: &SearchResultFormat) {
         }
     }
 }
+
+/// Returns the source of a search hit, optionally with its `_index`, `_id`
+/// and `_score` metadata added alongside the source fields.
+///
+fn hit_document(hit: &RawSearchResultHitsHit, with_meta: bool) -> HashMap<String, Value> {
+    let mut document = hit._source.clone();
+    if with_meta {
+        document.insert("_index".to_string(), json!(hit._index));
+        document.insert("_id".to_string(), json!(hit._id));
+        document.insert("_score".to_string(), json!(hit._score));
+    }
+    document
+}
+
+/// Builds a table of search hit documents, with any metadata columns first.
+///
+fn hits_table(
+    documents: &[HashMap<String, Value>],
+    nested: NestedDisplay,
+    with_meta: bool,
+) -> Table {
+    let mut table = if with_meta {
+        Table::with_column_names(vec![
+            "_index".to_string(),
+            "_id".to_string(),
+            "_score".to_string(),
+        ])
+    } else {
+        Table::new()
+    };
+    table.set_nested(nested);
+    for document in documents.iter() {
+        table.push_document(document);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deprecation_warnings_name_what_each_applies_to() {
+        let deprecations = json!({
+            "cluster_settings": [{"level": "critical", "message": "a"}],
+            "node_settings": [],
+            "index_settings": {"logs": [{"level": "warning", "message": "b"}]},
+            "templates": {"old": [{"level": "info", "message": "c"}]},
+            "ilm_policies": {},
+        });
+        let warnings: Vec<(String, &str)> = deprecation_warnings(&deprecations)
+            .into_iter()
+            .map(|(subject, warning)| (subject, warning["message"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                ("cluster".to_string(), "a"),
+                ("logs".to_string(), "b"),
+                ("template old".to_string(), "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn deprecation_warnings_tolerate_missing_sections() {
+        assert!(deprecation_warnings(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn format_thousands_groups_digits_in_threes() {
+        assert_eq!(format_thousands(0), "0");
+        assert_eq!(format_thousands(999), "999");
+        assert_eq!(format_thousands(1_000), "1,000");
+        assert_eq!(format_thousands(123_456), "123,456");
+        assert_eq!(format_thousands(1_234_567), "1,234,567");
+        assert_eq!(format_thousands(u64::MAX), "18,446,744,073,709,551,615");
+    }
+
+    #[test]
+    fn format_count_uses_one_suffix_per_thousand() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1_000), "1.0k");
+        assert_eq!(format_count(12_345), "12.3k");
+        assert_eq!(format_count(999_949), "999.9k");
+        assert_eq!(format_count(999_999), "1.0M");
+        assert_eq!(format_count(4_560_000), "4.6M");
+        assert_eq!(format_count(999_999_999), "1.0B");
+        assert_eq!(format_count(12_000_000_000), "12.0B");
+    }
+}
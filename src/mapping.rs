@@ -0,0 +1,463 @@
+use std::{
+    error::Error,
+    fs::read_to_string,
+    io::{stdin, stdout, Read, Write},
+    net::IpAddr,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+use serde_json::{json, Map, Value};
+
+/// Field data types offered by the interactive mapping builder.
+///
+pub const FIELD_TYPES: &[&str] = &[
+    "text",
+    "keyword",
+    "match_only_text",
+    "wildcard",
+    "constant_keyword",
+    "search_as_you_type",
+    "long",
+    "integer",
+    "short",
+    "byte",
+    "double",
+    "float",
+    "half_float",
+    "scaled_float",
+    "unsigned_long",
+    "date",
+    "date_nanos",
+    "boolean",
+    "binary",
+    "ip",
+    "version",
+    "geo_point",
+    "geo_shape",
+    "object",
+    "nested",
+    "flattened",
+    "dense_vector",
+    "completion",
+];
+
+/// A single field definition given on the command line, in the form
+/// `name:type[:option=value...]`. The name may be a dotted path, denoting a
+/// field within an object, and options are added to the field definition,
+/// with numeric and boolean values converted accordingly (e.g.
+/// `title:text:analyzer=english` or `embedding:dense_vector:dims=384`).
+///
+#[derive(Clone, Debug)]
+pub struct FieldSpec {
+    pub path: Vec<String>,
+    pub field: Value,
+}
+
+impl FromStr for FieldSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            format!(
+                "invalid field mapping '{spec}': {reason} (expected NAME:TYPE[:OPTION=VALUE...])"
+            )
+        };
+        let mut bits = spec.split(':');
+        let name = bits.next().unwrap_or("").trim();
+        if name.is_empty() {
+            return Err(invalid("missing field name"));
+        }
+        let path: Vec<String> = name.split('.').map(|x| x.to_string()).collect();
+        if path.iter().any(|x| x.is_empty()) {
+            return Err(invalid("empty segment in field name"));
+        }
+        let field_type = match bits.next().map(|x| x.trim()) {
+            Some(field_type) if !field_type.is_empty() => field_type,
+            _ => return Err(invalid("missing field type")),
+        };
+        let mut field = json!({"type": field_type});
+        for option in bits.flat_map(|x| x.split(',')) {
+            match option.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    field[key.trim()] = option_value(value.trim());
+                }
+                _ => return Err(invalid(&format!("option '{option}' is not OPTION=VALUE"))),
+            }
+        }
+        Ok(FieldSpec { path, field })
+    }
+}
+
+fn option_value(value: &str) -> Value {
+    if let Ok(x) = value.parse::<i64>() {
+        json!(x)
+    } else if let Ok(x) = value.parse::<f64>() {
+        json!(x)
+    } else if value == "true" || value == "false" {
+        json!(value == "true")
+    } else {
+        json!(value)
+    }
+}
+
+/// Builds a mapping body from a sequence of field specifications.
+///
+pub fn from_specs(specs: &[FieldSpec]) -> Value {
+    let mut body = json!({
+        "mappings": {
+            "properties": {
+            }
+        }
+    });
+    add_specs(&mut body, specs);
+    body
+}
+
+/// Adds fields from a sequence of field specifications to a mapping body,
+/// replacing any existing fields of the same names. Fields with dotted
+/// names are placed within the `properties` of their parent objects.
+///
+pub fn add_specs(body: &mut Value, specs: &[FieldSpec]) {
+    for spec in specs.iter() {
+        let mut node = &mut body["mappings"];
+        for (i, name) in spec.path.iter().enumerate() {
+            node = &mut node["properties"][name];
+            if i + 1 == spec.path.len() {
+                *node = spec.field.clone();
+            }
+        }
+    }
+}
+
+/// Reads a mapping body from a JSON or YAML file (chosen by extension). The
+/// file may contain a `mappings` section as for index creation, a mapping
+/// with `properties` and other top-level options, or just the `properties`
+/// object itself.
+///
+pub fn from_file(path: &str) -> Result<Value, Box<dyn Error>> {
+    let text = read_to_string(path)?;
+    let value: Value = if path.ends_with(".yaml") || path.ends_with(".yml") {
+        serde_yaml::from_str(&text)?
+    } else {
+        serde_json::from_str(&text)?
+    };
+    if !value.is_object() {
+        return Err(format!("{path} does not contain a mapping object").into());
+    }
+    Ok(if value.get("mappings").is_some() {
+        json!({"mappings": value["mappings"]})
+    } else if value.get("properties").is_some() {
+        json!({"mappings": value})
+    } else {
+        json!({"mappings": {"properties": value}})
+    })
+}
+
+/// Lists the full dotted names of all fields defined in a mapping, including
+/// sub-fields of objects and multi-fields.
+///
+pub fn field_names(mappings: &Value) -> Vec<String> {
+    field_types(mappings)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Lists the full dotted names and types of all fields defined in a
+/// mapping, including sub-fields of objects and multi-fields.
+///
+pub fn field_types(mappings: &Value) -> Vec<(String, String)> {
+    let mut fields = vec![];
+    visit_fields("", &mappings["properties"], &mut |name, field| {
+        let field_type = field["type"].as_str().unwrap_or("object");
+        fields.push((name, field_type.to_string()));
+    });
+    fields
+}
+
+/// Lists the full dotted names and `format` settings of all fields in a
+/// mapping which declare a custom format, such as date fields.
+///
+pub fn field_formats(mappings: &Value) -> Vec<(String, String)> {
+    let mut fields = vec![];
+    visit_fields("", &mappings["properties"], &mut |name, field| {
+        if let Some(format) = field["format"].as_str() {
+            fields.push((name, format.to_string()));
+        }
+    });
+    fields
+}
+
+fn visit_fields(prefix: &str, properties: &Value, visit: &mut impl FnMut(String, &Value)) {
+    if let Some(properties) = properties.as_object() {
+        for (name, field) in properties.iter() {
+            let full_name = format!("{prefix}{name}");
+            if field.get("properties").is_none() {
+                visit(full_name.clone(), field);
+            }
+            visit_fields(&format!("{full_name}."), &field["properties"], visit);
+            visit_fields(&format!("{full_name}."), &field["fields"], visit);
+        }
+    }
+}
+
+/// Lists the full dotted names of the fields in a mapping that hold values
+/// in the source of documents, or only the top-level fields if `nested` is
+/// false. Multi-fields are left out, as they are not part of the source.
+///
+pub fn source_fields(mappings: &Value, nested: bool) -> Vec<String> {
+    let mut fields = vec![];
+    collect_source_fields("", &mappings["properties"], nested, &mut fields);
+    fields
+}
+
+fn collect_source_fields(prefix: &str, properties: &Value, nested: bool, fields: &mut Vec<String>) {
+    if let Some(properties) = properties.as_object() {
+        for (name, field) in properties.iter() {
+            let full_name = format!("{prefix}{name}");
+            if nested && field.get("properties").is_some() {
+                collect_source_fields(
+                    &format!("{full_name}."),
+                    &field["properties"],
+                    nested,
+                    fields,
+                );
+            } else {
+                fields.push(full_name);
+            }
+        }
+    }
+}
+
+/// Checks whether a textual value could be indexed into a field of the
+/// given type, and with the given `format` if the mapping sets one. Types
+/// and formats which are not checked are assumed to accept anything.
+///
+pub fn can_coerce(value: &str, field_type: &str, format: Option<&str>) -> bool {
+    match field_type {
+        "long" | "integer" | "short" | "byte" => value.parse::<i64>().is_ok(),
+        "unsigned_long" => value.parse::<u64>().is_ok(),
+        "double" | "float" | "half_float" | "scaled_float" => value.parse::<f64>().is_ok(),
+        "boolean" => value == "true" || value == "false",
+        "date" | "date_nanos" => match format {
+            None => is_date(value) || value.parse::<i64>().is_ok(),
+            Some(format) => format.split("||").any(|format| {
+                match format.trim() {
+                    "strict_date_optional_time"
+                    | "date_optional_time"
+                    | "strict_date_optional_time_nanos" => is_date(value),
+                    "epoch_millis" | "epoch_second" => value.parse::<f64>().is_ok(),
+                    // Other built-in and custom formats are not checked
+                    _ => true,
+                }
+            }),
+        },
+        "ip" => value.parse::<IpAddr>().is_ok(),
+        _ => true,
+    }
+}
+
+/// Walks the user through adding fields to a mapping body on the terminal,
+/// then shows a preview of the resulting JSON. Returns `None` if the user
+/// declines to proceed after seeing the preview.
+///
+pub fn build_interactively(index: &str, mut body: Value) -> Option<Value> {
+    println!("Building mapping for index {index}");
+    println!("Leave the field name blank to finish");
+    loop {
+        println!();
+        let name = prompt("Field name: ");
+        if name.is_empty() {
+            break;
+        }
+        if body["mappings"]["properties"].get(&name).is_some() {
+            println!("Field {name} is already defined");
+            continue;
+        }
+        let field_type = prompt_field_type("Field type [text]: ", "text");
+        let mut field = json!({"type": field_type});
+        if field_type == "text" {
+            let analyzer = prompt("Analyzer (blank for default): ");
+            if !analyzer.is_empty() {
+                field["analyzer"] = json!(analyzer);
+            }
+        }
+        let sub_fields = prompt_sub_fields();
+        if !sub_fields.is_empty() {
+            field["fields"] = Value::Object(sub_fields);
+        }
+        body["mappings"]["properties"][&name] = field;
+    }
+    println!();
+    println!("Mapping preview:");
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&body).unwrap_or_else(|_| body.to_string())
+    );
+    println!();
+    if confirm(
+        &format!("Create index {index} with this mapping? [Y/n] "),
+        true,
+    ) {
+        Some(body)
+    } else {
+        None
+    }
+}
+
+fn prompt_field_type(text: &str, default: &str) -> String {
+    loop {
+        let answer = prompt(text);
+        let field_type = if answer.is_empty() {
+            default.to_string()
+        } else {
+            answer
+        };
+        if FIELD_TYPES.contains(&field_type.as_str()) {
+            return field_type;
+        }
+        println!(
+            "Unknown field type {field_type} (expected one of: {})",
+            FIELD_TYPES.join(", ")
+        );
+    }
+}
+
+fn prompt_sub_fields() -> Map<String, Value> {
+    loop {
+        let answer = prompt("Multi-fields as NAME:TYPE pairs, comma-separated (blank for none): ");
+        let mut sub_fields = Map::new();
+        let mut valid = true;
+        for pair in answer.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            match pair.split_once(':') {
+                Some((name, field_type))
+                    if !name.is_empty() && FIELD_TYPES.contains(&field_type) =>
+                {
+                    sub_fields.insert(name.to_string(), json!({"type": field_type}));
+                }
+                _ => {
+                    println!("Invalid multi-field {pair} (expected NAME:TYPE, e.g. raw:keyword)");
+                    valid = false;
+                }
+            }
+        }
+        if valid {
+            return sub_fields;
+        }
+    }
+}
+
+/// Prints a prompt and reads a single trimmed line from standard input.
+///
+pub fn prompt(text: &str) -> String {
+    print!("{text}");
+    let _ = stdout().flush();
+    let mut line = String::new();
+    match stdin().read_line(&mut line) {
+        Ok(_) => line.trim().to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Prints a prompt and reads a single trimmed line from standard input
+/// without echoing it, for passwords. Echo is turned off using `stty`, so
+/// the answer is visible where that is not available.
+///
+pub fn prompt_hidden(text: &str) -> String {
+    let stty = |arg: &str| {
+        Command::new("stty")
+            .arg(arg)
+            .stdin(Stdio::inherit())
+            .status()
+            .is_ok_and(|x| x.success())
+    };
+    let hidden = stty("-echo");
+    let line = prompt(text);
+    if hidden {
+        stty("echo");
+        println!();
+    }
+    line
+}
+
+/// Asks a yes/no question, returning `default` if the answer is blank.
+///
+pub fn confirm(text: &str, default: bool) -> bool {
+    match prompt(text).to_lowercase().as_str() {
+        "" => default,
+        "y" | "yes" => true,
+        _ => false,
+    }
+}
+
+/// Samples up to `sample_size` records from CSV data and infers a field
+/// type for each column, returning a mapping body suitable for index
+/// creation.
+///
+pub fn infer_from_csv<R: Read>(input: R, sample_size: usize) -> Result<Value, csv::Error> {
+    let mut reader = csv::Reader::from_reader(input);
+    let headers = reader.headers()?.clone();
+    let mut columns: Vec<Vec<String>> = vec![vec![]; headers.len()];
+    for result in reader.records().take(sample_size) {
+        let record = result?;
+        for (i, value) in record.iter().enumerate() {
+            if i < columns.len() && !value.is_empty() {
+                columns[i].push(value.to_string());
+            }
+        }
+    }
+    let mut body = from_specs(&[]);
+    for (name, values) in headers.iter().zip(columns.iter()) {
+        body["mappings"]["properties"][name] = json!({"type": infer_type(values)});
+    }
+    Ok(body)
+}
+
+/// Infers the most specific field type that fits all of the given sample
+/// values. Strings containing whitespace are taken to be free text, while
+/// other strings are treated as keywords.
+///
+pub fn infer_type(values: &[String]) -> &'static str {
+    if values.is_empty() {
+        "keyword"
+    } else if values.iter().all(|x| x == "true" || x == "false") {
+        "boolean"
+    } else if values.iter().all(|x| x.parse::<i64>().is_ok()) {
+        "long"
+    } else if values
+        .iter()
+        .all(|x| x.parse::<f64>().is_ok_and(|f| f.is_finite()))
+    {
+        "double"
+    } else if values.iter().all(|x| is_date(x)) {
+        "date"
+    } else if values.iter().any(|x| x.contains(char::is_whitespace)) {
+        "text"
+    } else {
+        "keyword"
+    }
+}
+
+/// Checks whether a value looks like an ISO 8601 date or date-time, as
+/// accepted by the default `strict_date_optional_time` date format.
+///
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let digits = |range: std::ops::Range<usize>| {
+        bytes.len() >= range.end && bytes[range].iter().all(u8::is_ascii_digit)
+    };
+    if !(digits(0..4) && bytes.get(4) == Some(&b'-') && digits(5..7)) {
+        return false;
+    }
+    if bytes.len() == 7 {
+        return true;
+    }
+    if !(bytes.get(7) == Some(&b'-') && digits(8..10)) {
+        return false;
+    }
+    if bytes.len() == 10 {
+        return true;
+    }
+    bytes.get(10) == Some(&b'T') && digits(11..13) && bytes.get(13) == Some(&b':') && digits(14..16)
+}
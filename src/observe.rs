@@ -0,0 +1,259 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use elasticsearch::http::{response::Response, Method, Url};
+use serde_json::Value;
+
+static TIMINGS: AtomicBool = AtomicBool::new(false);
+static HEADERS: AtomicBool = AtomicBool::new(false);
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// Options standing in for credentials in curl commands, if curl commands
+/// are to be printed.
+static CURL: Mutex<Option<String>> = Mutex::new(None);
+
+/// Parts of field names that mark a value as secret, so that it is
+/// redacted when response bodies are traced.
+const SECRET_FIELDS: [&str; 6] = [
+    "password",
+    "secret",
+    "token",
+    "api_key",
+    "credentials",
+    "authorization",
+];
+
+/// Deprecation warnings received so far, each with the number of times it
+/// was seen, in the order first received.
+static WARNINGS: Mutex<Vec<(String, usize)>> = Mutex::new(vec![]);
+
+/// Turns on reporting of request timings, which are written to stderr.
+///
+pub fn enable_timings() {
+    TIMINGS.store(true, Ordering::Relaxed);
+}
+
+/// Turns on printing of response headers, which are written to stderr.
+///
+pub fn enable_headers() {
+    HEADERS.store(true, Ordering::Relaxed);
+}
+
+/// Sets the level of request tracing, written to stderr: 1 for the method,
+/// URL, status and time taken by each request, or 2 to also include the
+/// body of each response.
+///
+pub fn set_verbosity(level: u8) {
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
+
+/// Turns on printing of an equivalent curl command for each request, to
+/// stderr, with the given options standing in for credentials.
+///
+pub fn enable_curl(credentials: &str) {
+    if let Ok(mut curl) = CURL.lock() {
+        *curl = Some(credentials.to_string());
+    }
+}
+
+pub fn tracing_bodies() -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= 2
+}
+
+/// Extends request futures so that each response can be reported on as it
+/// arrives, with the time taken to receive it and/or its headers, depending
+/// on which kinds of reporting are enabled.
+///
+pub(crate) trait Observe:
+    Future<Output = Result<Response, elasticsearch::Error>> + Sized
+{
+    fn observed(self) -> impl Future<Output = Self::Output> {
+        async move {
+            let start = Instant::now();
+            let result = self.await;
+            if let Ok(response) = &result {
+                collect_warnings(response);
+                print_curl(response);
+            }
+            let timings = TIMINGS.load(Ordering::Relaxed);
+            let headers = HEADERS.load(Ordering::Relaxed);
+            let verbose = VERBOSITY.load(Ordering::Relaxed) >= 1;
+            match &result {
+                Ok(response) if timings || headers || verbose => {
+                    eprint!(
+                        "{} {} -> {}",
+                        method_name(response.method()),
+                        if verbose {
+                            redact_url(response.url())
+                        } else {
+                            response.url().path().to_string()
+                        },
+                        response.status_code().as_u16()
+                    );
+                    if timings || verbose {
+                        eprint!(": first byte after {:.1?}", start.elapsed());
+                    }
+                    eprintln!();
+                    if headers {
+                        print_headers(response);
+                    }
+                }
+                Err(e) if timings || verbose => {
+                    eprintln!("Request failed after {:.1?} ({e})", start.elapsed())
+                }
+                _ => {}
+            }
+            result
+        }
+    }
+}
+
+impl<F: Future<Output = Result<Response, elasticsearch::Error>>> Observe for F {}
+
+/// Gives the name of an HTTP method as it appears in a request line.
+///
+fn method_name(method: Method) -> &'static str {
+    match method {
+        Method::Get => "GET",
+        Method::Put => "PUT",
+        Method::Post => "POST",
+        Method::Delete => "DELETE",
+        Method::Head => "HEAD",
+    }
+}
+
+/// Prints a curl command equivalent to the request that produced a
+/// response. Request bodies are built inside the client, so cannot be
+/// shown, and are replaced with a placeholder file name.
+///
+fn print_curl(response: &Response) {
+    let credentials = match CURL.lock() {
+        Ok(curl) => match curl.as_ref() {
+            Some(it) => it.clone(),
+            None => return,
+        },
+        Err(_) => return,
+    };
+    let method = method_name(response.method());
+    let mut command = format!("curl -X {method} {credentials}");
+    if matches!(method, "POST" | "PUT") {
+        command.push_str(" -H 'Content-Type: application/json' -d @body.json");
+    }
+    eprintln!("{command} '{}'", redact_url(response.url()));
+}
+
+fn collect_warnings(response: &Response) {
+    if let Ok(mut warnings) = WARNINGS.lock() {
+        for header in response.warning_headers() {
+            let message = warning_message(header);
+            match warnings.iter_mut().find(|(x, _)| x == message) {
+                Some((_, count)) => *count += 1,
+                None => warnings.push((message.to_string(), 1)),
+            }
+        }
+    }
+}
+
+/// Extracts the quoted text from a `Warning` header value, which takes the
+/// form `299 Elasticsearch-VERSION "TEXT"`, falling back to the whole value.
+///
+fn warning_message(header: &str) -> &str {
+    match (header.find('"'), header.rfind('"')) {
+        (Some(start), Some(end)) if end > start => &header[start + 1..end],
+        _ => header,
+    }
+}
+
+/// Checks whether any deprecation warnings were received during the
+/// session.
+///
+pub fn warned() -> bool {
+    WARNINGS.lock().is_ok_and(|x| !x.is_empty())
+}
+
+/// Prints a summary of all distinct deprecation warnings received during
+/// the session, if there were any.
+///
+pub fn print_warning_summary() {
+    if let Ok(warnings) = WARNINGS.lock() {
+        if warnings.is_empty() {
+            return;
+        }
+        eprintln!();
+        eprintln!("Deprecation warnings ({}):", warnings.len());
+        for (message, count) in warnings.iter() {
+            if *count > 1 {
+                eprintln!("  {message} (x{count})");
+            } else {
+                eprintln!("  {message}");
+            }
+        }
+    }
+}
+
+fn print_headers(response: &Response) {
+    for (name, value) in response.headers().iter() {
+        eprintln!("  {}: {}", name, value.to_str().unwrap_or("<binary>"));
+    }
+    for warning in response.warning_headers() {
+        eprintln!("  Deprecation warning: {warning}");
+    }
+}
+
+/// Prints a response body, if bodies are being traced, with the values of
+/// any secret-looking fields redacted.
+///
+pub fn trace_body(text: &str) {
+    if !tracing_bodies() {
+        return;
+    }
+    match serde_json::from_str::<Value>(text) {
+        Ok(mut value) => {
+            redact(&mut value);
+            eprintln!(
+                "{}",
+                serde_json::to_string_pretty(&value).unwrap_or_default()
+            );
+        }
+        Err(_) => eprintln!("{text}"),
+    }
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if !item.is_null() && SECRET_FIELDS.iter().any(|x| key.contains(x)) {
+                    *item = Value::String("[REDACTED]".to_string());
+                } else {
+                    redact(item);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn redact_url(url: &Url) -> String {
+    let mut url = url.clone();
+    if url.password().is_some() {
+        let _ = url.set_password(Some("[REDACTED]"));
+    }
+    url.to_string()
+}
+
+/// Reports the time taken to download and decode a response body.
+///
+pub fn report_body(started: Instant) {
+    if TIMINGS.load(Ordering::Relaxed) {
+        eprintln!("  body downloaded after {:.1?}", started.elapsed());
+    }
+}
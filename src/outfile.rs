@@ -0,0 +1,143 @@
+use std::{
+    fs::{self, File},
+    io::{self, stdout, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+#[cfg(unix)]
+mod fd {
+    use std::os::raw::c_int;
+
+    pub const STDOUT: c_int = 1;
+
+    extern "C" {
+        pub fn dup(fd: c_int) -> c_int;
+        pub fn dup2(src: c_int, dst: c_int) -> c_int;
+        pub fn close(fd: c_int) -> c_int;
+    }
+}
+
+#[cfg(windows)]
+mod handle {
+    use std::ffi::c_void;
+
+    pub const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+
+    extern "system" {
+        pub fn GetStdHandle(id: u32) -> *mut c_void;
+        pub fn SetStdHandle(id: u32, handle: *mut c_void) -> i32;
+    }
+}
+
+/// A file into which stdout is redirected, for the global `--out` option.
+/// Output is written to a temporary file alongside the target, which
+/// replaces the target in a single rename once the command has finished,
+/// so that readers never see a partly written file. Names ending in `.gz`
+/// are compressed with gzip.
+///
+pub struct OutputFile {
+    path: PathBuf,
+    part: PathBuf,
+    #[cfg(unix)]
+    saved_stdout: i32,
+    #[cfg(windows)]
+    saved_stdout: usize,
+    _file: File,
+}
+
+impl OutputFile {
+    pub fn start(path: &str) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        let part = part_path(&path, "part");
+        let file = File::create(&part)?;
+        stdout().flush()?;
+        #[cfg(unix)]
+        // SAFETY: these calls only duplicate and replace file descriptors
+        // owned by this process
+        let saved_stdout = unsafe {
+            use std::os::fd::AsRawFd;
+
+            let saved = fd::dup(fd::STDOUT);
+            if saved < 0 || fd::dup2(file.as_raw_fd(), fd::STDOUT) < 0 {
+                let _ = fs::remove_file(&part);
+                return Err(io::Error::last_os_error());
+            }
+            saved
+        };
+        #[cfg(windows)]
+        // SAFETY: the standard output handle is replaced by that of a file
+        // which is kept open until it is restored
+        let saved_stdout = unsafe {
+            use std::os::windows::io::AsRawHandle;
+
+            let saved = handle::GetStdHandle(handle::STD_OUTPUT_HANDLE);
+            if handle::SetStdHandle(handle::STD_OUTPUT_HANDLE, file.as_raw_handle()) == 0 {
+                let _ = fs::remove_file(&part);
+                return Err(io::Error::last_os_error());
+            }
+            saved as usize
+        };
+        Ok(Self {
+            path,
+            part,
+            saved_stdout,
+            _file: file,
+        })
+    }
+
+    /// Flushes stdout and restores it, then moves the output into place,
+    /// compressing it first if required.
+    ///
+    pub fn finish(self) -> io::Result<()> {
+        let _ = stdout().flush();
+        #[cfg(unix)]
+        // SAFETY: the saved descriptor was duplicated from stdout in
+        // `start`, and is closed once it has been restored
+        unsafe {
+            fd::dup2(self.saved_stdout, fd::STDOUT);
+            fd::close(self.saved_stdout);
+        }
+        #[cfg(windows)]
+        // SAFETY: the saved handle is the one that was replaced in `start`
+        unsafe {
+            handle::SetStdHandle(handle::STD_OUTPUT_HANDLE, self.saved_stdout as _);
+        }
+        let (result, leftover) = if self.path.extension().is_some_and(|x| x == "gz") {
+            let compressed = part_path(&self.path, "gz.part");
+            let result =
+                compress(&self.part, &compressed).and_then(|_| fs::rename(&compressed, &self.path));
+            let _ = fs::remove_file(&self.part);
+            (result, compressed)
+        } else {
+            (fs::rename(&self.part, &self.path), self.part)
+        };
+        if result.is_err() {
+            let _ = fs::remove_file(leftover);
+        }
+        result
+    }
+}
+
+/// Returns the path of a hidden temporary file in the same directory as
+/// the target, so that it can be renamed over the target.
+///
+fn part_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.{suffix}", process::id()))
+}
+
+fn compress(source: &Path, target: &Path) -> io::Result<()> {
+    let mut reader = BufReader::new(File::open(source)?);
+    let mut encoder = GzEncoder::new(
+        BufWriter::new(File::create(target)?),
+        Compression::default(),
+    );
+    io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?.flush()
+}
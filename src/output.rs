@@ -0,0 +1,382 @@
+use std::{
+    env,
+    fmt::Display,
+    io::{stderr, stdout, IsTerminal},
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        RwLock, RwLockReadGuard,
+    },
+};
+
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use tabled::{
+    builder::Builder,
+    settings::{object::Segment, Style, Width},
+    Table,
+};
+
+use crate::{data, filter::Filter};
+
+/// Format in which results are written to stdout, chosen once for the whole
+/// run by the global `--output` option.
+///
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Aligned columns and labelled values, for reading
+    Table,
+    /// JSON, with raw values where available, for jq and scripts
+    Json,
+    /// Tab-separated values without a header row, for cut and awk
+    Plain,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// When to use ANSI colour codes, as chosen by the global `--color` option.
+///
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColourChoice {
+    /// Use colour when writing to a terminal, unless NO_COLOR is set
+    Auto,
+    Always,
+    Never,
+}
+
+pub const RED: &str = "\x1b[31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const YELLOW: &str = "\x1b[33m";
+pub const RESET: &str = "\x1b[0m";
+
+/// Columns to show, and the width to limit each column to, when printing
+/// tables, as chosen by the global `--columns`, `--max-col-width` and
+/// `--wrap` options.
+///
+#[derive(Default)]
+pub struct TableLayout {
+    pub columns: Vec<String>,
+    pub max_col_width: Option<usize>,
+    pub wrap: bool,
+}
+
+static FORMAT: AtomicU8 = AtomicU8::new(OutputFormat::Table as u8);
+static LAYOUT: RwLock<TableLayout> = RwLock::new(TableLayout {
+    columns: vec![],
+    max_col_width: None,
+    wrap: false,
+});
+static COLOUR: AtomicU8 = AtomicU8::new(ColourChoice::Auto as u8);
+static ASCII: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+static FILTER: RwLock<Option<Filter>> = RwLock::new(None);
+static FILTER_FAILED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_format(format: OutputFormat) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn set_layout(layout: TableLayout) {
+    *LAYOUT.write().unwrap() = layout;
+}
+
+pub fn set_colour(choice: ColourChoice) {
+    COLOUR.store(choice as u8, Ordering::Relaxed);
+}
+
+/// Chooses plain text markers over emoji, for terminals and logs that
+/// cannot render them.
+///
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+/// Suppresses non-essential output, such as notes accompanying tables.
+///
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Sets a filter through which all JSON output is passed, or removes it.
+///
+pub fn set_filter(filter: Option<Filter>) {
+    *FILTER.write().unwrap() = filter;
+}
+
+/// Checks whether the filter failed to apply to any output, so that the
+/// run can end with a failure.
+///
+pub fn filter_failed() -> bool {
+    FILTER_FAILED.load(Ordering::Relaxed)
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// Checks whether output to stdout should be coloured.
+///
+pub fn colour_stdout() -> bool {
+    use_colour(stdout().is_terminal())
+}
+
+/// Checks whether output to stderr should be coloured.
+///
+pub fn colour_stderr() -> bool {
+    use_colour(stderr().is_terminal())
+}
+
+fn use_colour(terminal: bool) -> bool {
+    match COLOUR.load(Ordering::Relaxed) {
+        x if x == ColourChoice::Always as u8 => true,
+        x if x == ColourChoice::Never as u8 => false,
+        _ => terminal && env::var_os("NO_COLOR").is_none_or(|x| x.is_empty()),
+    }
+}
+
+/// Wraps text in an ANSI style if `colour` is set.
+///
+pub fn paint(style: &str, text: &str, colour: bool) -> String {
+    if colour {
+        format!("{style}{text}{RESET}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Returns the style in which to show a cluster or index health status.
+///
+pub fn health_style(health: &str) -> &'static str {
+    match health {
+        "green" => GREEN,
+        "yellow" => YELLOW,
+        "red" => RED,
+        _ => "",
+    }
+}
+
+pub fn format() -> OutputFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        x if x == OutputFormat::Json as u8 => OutputFormat::Json,
+        x if x == OutputFormat::Plain as u8 => OutputFormat::Plain,
+        x if x == OutputFormat::Csv as u8 => OutputFormat::Csv,
+        _ => OutputFormat::Table,
+    }
+}
+
+/// Prints rows of values under the given column names. As JSON, each row
+/// becomes an object keyed by column name.
+///
+pub fn print_rows<S: AsRef<str>>(columns: &[S], rows: &[Vec<Value>]) {
+    let (columns, rows) = match selection(columns) {
+        Some(indexes) => (
+            pick(
+                &indexes,
+                columns.iter().map(|x| x.as_ref().to_string()).collect(),
+            ),
+            rows.iter()
+                .map(|row| pick(&indexes, row.clone()))
+                .collect::<Vec<Vec<Value>>>(),
+        ),
+        None => (
+            columns.iter().map(|x| x.as_ref().to_string()).collect(),
+            rows.to_vec(),
+        ),
+    };
+    match format() {
+        OutputFormat::Table => {
+            let mut builder = Builder::default();
+            builder.push_record(columns);
+            for row in rows.iter() {
+                builder.push_record(row.iter().map(data::to_cell));
+            }
+            println!("{}", build_table(builder).with(Style::empty()));
+        }
+        OutputFormat::Json => {
+            let objects: Vec<Map<String, Value>> = rows
+                .iter()
+                .map(|row| columns.iter().cloned().zip(row.iter().cloned()).collect())
+                .collect();
+            print_json(&json!(objects), false);
+        }
+        OutputFormat::Plain => {
+            for row in rows.iter() {
+                let cells: Vec<String> = row.iter().map(data::to_cell).collect();
+                println!("{}", cells.join("\t"));
+            }
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout());
+            let mut result = writer.write_record(&columns);
+            for row in rows.iter() {
+                result = result.and_then(|_| writer.write_record(row.iter().map(data::to_cell)));
+            }
+            if let Err(e) = result
+                .map_err(std::io::Error::from)
+                .and_then(|_| writer.flush())
+            {
+                print_error(&e);
+            }
+        }
+    }
+}
+
+/// Prints a table whose first record holds the column names. Tables are
+/// built for display, so cells are written as strings in every format.
+///
+pub fn print_table(builder: Builder) {
+    if format() == OutputFormat::Table {
+        println!("{}", build_table(builder).with(Style::empty()));
+        return;
+    }
+    let mut records: Vec<Vec<String>> = builder.into();
+    if records.is_empty() {
+        return print_rows::<String>(&[], &[]);
+    }
+    let columns = records.remove(0);
+    let rows: Vec<Vec<Value>> = records
+        .into_iter()
+        .map(|row| row.into_iter().map(Value::String).collect())
+        .collect();
+    print_rows(&columns, &rows);
+}
+
+/// Prints a message in place of an empty list, or an empty JSON array.
+///
+pub fn print_empty(message: &str) {
+    match format() {
+        OutputFormat::Table => println!("{message}"),
+        OutputFormat::Json => print_json(&json!([]), false),
+        OutputFormat::Plain | OutputFormat::Csv => {}
+    }
+}
+
+/// Prints a JSON value, or the values produced from it by the filter, if
+/// one is set. Each filtered value is printed on a line of its own, with
+/// strings printed without quotes, as `jq -r` does.
+///
+pub fn print_json(value: &Value, compact: bool) {
+    let filter = FILTER.read().unwrap();
+    let Some(filter) = filter.as_ref() else {
+        return println!("{}", crate::json::render(value, compact));
+    };
+    match filter.apply(value) {
+        Ok(values) => {
+            for value in values.iter() {
+                match value {
+                    Value::String(text) => println!("{text}"),
+                    _ => println!("{}", crate::json::render(value, compact)),
+                }
+            }
+        }
+        Err(e) => {
+            FILTER_FAILED.store(true, Ordering::Relaxed);
+            print_error(&e);
+        }
+    }
+}
+
+/// Prints an error to stderr, as `{"error": "..."}` when writing JSON.
+///
+pub fn print_error<E: Display + ?Sized>(e: &E) {
+    match format() {
+        OutputFormat::Json => eprintln!("{}", json!({"error": e.to_string()})),
+        _ => eprintln!("{}", paint(RED, &e.to_string(), colour_stderr())),
+    }
+}
+
+/// Prints a table as `print_table` does, applying further settings, such as
+/// column alignment, when printed as a table. The settings refer to columns
+/// by position, so are skipped if a selection of columns has been made.
+///
+pub fn print_table_with<F: FnOnce(&mut Table)>(builder: Builder, settings: F) {
+    if format() != OutputFormat::Table {
+        return print_table(builder);
+    }
+    println!("{}", render_table_with(builder, settings));
+}
+
+/// Renders a table as `print_table_with` prints it in table format, for
+/// views that are redrawn in place.
+///
+pub fn render_table_with<F: FnOnce(&mut Table)>(builder: Builder, settings: F) -> String {
+    let mut table = build_table(builder);
+    table.with(Style::empty());
+    if layout().columns.is_empty() {
+        settings(&mut table);
+    }
+    table.to_string()
+}
+
+/// Builds a table from a builder whose first record holds the column
+/// names, keeping only the selected columns (in the order selected) and
+/// truncating or wrapping cells to the maximum column width.
+///
+pub fn build_table(builder: Builder) -> Table {
+    let mut records: Vec<Vec<String>> = builder.into();
+    if let Some(indexes) = records.first().and_then(|x| selection(x)) {
+        records = records.into_iter().map(|x| pick(&indexes, x)).collect();
+    }
+    let mut table = Builder::from(records).build();
+    let layout = layout();
+    match layout.max_col_width {
+        Some(width) if layout.wrap => {
+            table.modify(Segment::all(), Width::wrap(width).keep_words(true));
+        }
+        Some(width) => {
+            table.modify(Segment::all(), Width::truncate(width).suffix("…"));
+        }
+        None => {}
+    }
+    table
+}
+
+/// Returns the columns selected with `--columns`, if any.
+///
+pub fn selected_columns() -> Vec<String> {
+    layout().columns.clone()
+}
+
+fn layout() -> RwLockReadGuard<'static, TableLayout> {
+    LAYOUT.read().unwrap()
+}
+
+/// Finds the positions of the selected columns among those given, in the
+/// order selected, or `None` if no selection has been made. Selected names
+/// that match no column are reported and skipped.
+///
+fn selection<S: AsRef<str>>(columns: &[S]) -> Option<Vec<usize>> {
+    let layout = layout();
+    let selected = &layout.columns;
+    if selected.is_empty() {
+        return None;
+    }
+    let mut indexes = vec![];
+    for name in selected.iter() {
+        match columns.iter().position(|x| x.as_ref() == name) {
+            Some(i) => indexes.push(i),
+            None => eprintln!("No such column: {name}"),
+        }
+    }
+    Some(indexes)
+}
+
+fn pick<T: Clone + Default>(indexes: &[usize], row: Vec<T>) -> Vec<T> {
+    indexes
+        .iter()
+        .map(|i| row.get(*i).cloned().unwrap_or_default())
+        .collect()
+}
+
+/// Prints a note that accompanies a table, such as a count of its rows.
+/// Notes are left out of other output formats, so as not to get in the way
+/// of parsing, and in quiet mode.
+///
+pub fn print_note(note: &str) {
+    if format() == OutputFormat::Table && !quiet() {
+        println!("{note}");
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    env,
+    io::{stdout, IsTerminal, Write},
+    process::{Child, Command, Stdio},
+};
+
+#[cfg(unix)]
+mod fd {
+    use std::os::raw::c_int;
+
+    pub const STDOUT: c_int = 1;
+    pub const SIGPIPE: c_int = 13;
+    pub const SIG_DFL: usize = 0;
+
+    extern "C" {
+        pub fn dup(fd: c_int) -> c_int;
+        pub fn dup2(src: c_int, dst: c_int) -> c_int;
+        pub fn close(fd: c_int) -> c_int;
+        pub fn signal(signum: c_int, handler: usize) -> usize;
+    }
+}
+
+/// A pager process into which stdout is redirected while it runs, as `git`
+/// does for long output.
+///
+pub struct Pager {
+    child: Child,
+    saved_stdout: i32,
+}
+
+impl Pager {
+    /// Starts the pager named by `$PAGER` (default `less`) and redirects
+    /// stdout into it, if stdout is a terminal. Unless `$LESS` is set, less
+    /// is told to exit straight away if the output fits on one screen, and
+    /// to pass colour codes through. An empty `$PAGER`, or `cat`, turns
+    /// paging off.
+    ///
+    #[cfg(unix)]
+    pub fn start() -> Option<Self> {
+        use std::os::fd::AsRawFd;
+
+        if !stdout().is_terminal() {
+            return None;
+        }
+        let command = match env::var("PAGER") {
+            Ok(x) if x.trim().is_empty() || x.trim() == "cat" => return None,
+            Ok(x) => x,
+            Err(_) => "less".to_string(),
+        };
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .env("LESS", env::var("LESS").unwrap_or("FRX".to_string()))
+            .stdin(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let stdin = child.stdin.take()?;
+        // SAFETY: these calls only duplicate and replace file descriptors
+        // owned by this process, and restore the default handling of
+        // SIGPIPE so that quitting the pager early ends escli quietly
+        let saved_stdout = unsafe {
+            let saved = fd::dup(fd::STDOUT);
+            if saved < 0 || fd::dup2(stdin.as_raw_fd(), fd::STDOUT) < 0 {
+                let _ = child.kill();
+                return None;
+            }
+            fd::signal(fd::SIGPIPE, fd::SIG_DFL);
+            saved
+        };
+        Some(Self {
+            child,
+            saved_stdout,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn start() -> Option<Self> {
+        None
+    }
+
+    /// Flushes stdout and restores it, then waits for the user to quit the
+    /// pager.
+    ///
+    pub fn finish(mut self) {
+        let _ = stdout().flush();
+        #[cfg(unix)]
+        // SAFETY: the saved descriptor was duplicated from stdout in
+        // `start`, and is closed once it has been restored
+        unsafe {
+            fd::dup2(self.saved_stdout, fd::STDOUT);
+            fd::close(self.saved_stdout);
+        }
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,211 @@
+use std::{error::Error, fs, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Options controlling a run of pings.
+///
+pub struct PingOptions {
+    pub count: Option<usize>,
+    pub interval: Duration,
+    /// Maximum number of requests in flight at once
+    pub concurrent: usize,
+    pub request_timeout: Option<Duration>,
+    /// Whether to stop at the first successful response
+    pub until_available: bool,
+    /// Time after which to give up waiting for a successful response
+    pub timeout: Option<Duration>,
+}
+
+/// The outcome of a run of pings, as saved to and loaded from a JSON file
+/// for comparison between runs. Latencies are kept for successful requests
+/// only, in milliseconds.
+///
+#[derive(Serialize, Deserialize, Default)]
+pub struct PingRun {
+    pub url: String,
+    pub sent: usize,
+    pub latencies_ms: Vec<f64>,
+}
+
+impl PingRun {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn record(&mut self, latency: Option<Duration>) {
+        self.sent += 1;
+        if let Some(x) = latency {
+            self.latencies_ms.push(x.as_secs_f64() * 1000.0);
+        }
+    }
+
+    pub fn received(&self) -> usize {
+        self.latencies_ms.len()
+    }
+
+    /// Percentage of requests sent that did not receive a response.
+    ///
+    pub fn loss(&self) -> f64 {
+        if self.sent == 0 {
+            0.0
+        } else {
+            100.0 * (self.sent - self.received()) as f64 / self.sent as f64
+        }
+    }
+
+    /// Latency at the given percentile (0-100), using the nearest-rank
+    /// method, or `None` if no responses were received.
+    ///
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.latencies_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies_ms.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+
+    /// Minimum, mean, maximum and standard deviation of the latencies, or
+    /// `None` if no responses were received.
+    ///
+    pub fn spread(&self) -> Option<(f64, f64, f64, f64)> {
+        if self.latencies_ms.is_empty() {
+            return None;
+        }
+        let n = self.latencies_ms.len() as f64;
+        let mean = self.latencies_ms.iter().sum::<f64>() / n;
+        let variance = self
+            .latencies_ms
+            .iter()
+            .map(|x| (x - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        Some((
+            self.latencies_ms
+                .iter()
+                .copied()
+                .fold(f64::INFINITY, f64::min),
+            mean,
+            self.latencies_ms
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max),
+            variance.sqrt(),
+        ))
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Prints a summary of a run, in the style of the `ping` command.
+///
+pub fn print_summary(run: &PingRun, elapsed: Duration) {
+    println!("--- {} ping statistics ---", run.url);
+    println!(
+        "{} requests sent, {} responses received, {:.1}% loss, time {}ms",
+        run.sent,
+        run.received(),
+        run.loss(),
+        elapsed.as_millis()
+    );
+    if let Some((min, avg, max, stddev)) = run.spread() {
+        println!("rtt min/avg/max/stddev = {min:.3}/{avg:.3}/{max:.3}/{stddev:.3} ms");
+    }
+}
+
+/// Prints the median and 95th percentile latencies and the loss of a run
+/// alongside those of a baseline run, with the change in each.
+///
+pub fn print_comparison(baseline: &PingRun, current: &PingRun) {
+    if baseline.url != current.url {
+        println!(
+            "Note: baseline was taken against {}, not {}",
+            baseline.url, current.url
+        );
+    }
+    println!(
+        "{:<8} {:>12} {:>12} {:>12}",
+        "", "baseline", "current", "change"
+    );
+    for (label, p) in [("p50", 50.0), ("p95", 95.0)] {
+        let before = baseline.percentile(p);
+        let after = current.percentile(p);
+        let change = match (before, after) {
+            (Some(a), Some(b)) if a > 0.0 => format!("{:+.1}%", 100.0 * (b - a) / a),
+            (Some(a), Some(b)) => format!("{:+.3}ms", b - a),
+            _ => "-".to_string(),
+        };
+        println!(
+            "{label:<8} {:>12} {:>12} {change:>12}",
+            format_latency(before),
+            format_latency(after)
+        );
+    }
+    println!(
+        "{:<8} {:>11.1}% {:>11.1}% {:>+11.1}pp",
+        "loss",
+        baseline.loss(),
+        current.loss(),
+        current.loss() - baseline.loss()
+    );
+    println!("{:<8} {:>12} {:>12}", "sent", baseline.sent, current.sent);
+}
+
+fn format_latency(latency: Option<f64>) -> String {
+    match latency {
+        Some(x) => format!("{x:.3}ms"),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_of(latencies_ms: &[f64]) -> PingRun {
+        PingRun {
+            url: "http://localhost:9200/".to_string(),
+            sent: latencies_ms.len(),
+            latencies_ms: latencies_ms.to_vec(),
+        }
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let run = run_of(&[40.0, 10.0, 30.0, 20.0, 50.0]);
+        assert_eq!(run.percentile(0.0), Some(10.0));
+        assert_eq!(run.percentile(50.0), Some(30.0));
+        assert_eq!(run.percentile(90.0), Some(50.0));
+        assert_eq!(run.percentile(100.0), Some(50.0));
+    }
+
+    #[test]
+    fn percentile_is_none_without_responses() {
+        assert_eq!(run_of(&[]).percentile(50.0), None);
+    }
+
+    #[test]
+    fn spread_gives_min_mean_max_and_deviation() {
+        let (min, mean, max, deviation) = run_of(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0])
+            .spread()
+            .unwrap();
+        assert_eq!((min, mean, max), (2.0, 5.0, 9.0));
+        assert!((deviation - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn spread_is_none_without_responses() {
+        assert_eq!(run_of(&[]).spread(), None);
+    }
+}
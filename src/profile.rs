@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The escli configuration file, holding named connection profiles, one of
+/// which may be chosen as the default.
+///
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// The details needed to connect to one cluster, with the same meaning as
+/// the corresponding `ESCLI_*` environment variables, plus TLS options and
+/// a default output format.
+///
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_cmd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_url: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub production: bool,
+    /// PEM file holding the CA certificate with which to verify the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    /// Whether to skip verification of the server certificate
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub insecure: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl ConfigFile {
+    /// Reads the configuration file, if there is one.
+    ///
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match path() {
+            Some(path) if path.exists() => Ok(toml::from_str(&fs::read_to_string(&path)?)?),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Writes the configuration file. As it may hold passwords and API
+    /// keys, it is made readable by its owner only.
+    ///
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = path().ok_or("cannot locate configuration file without HOME")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+            options.mode(0o600);
+            // The mode only applies on creation, so tighten existing files
+            if path.exists() {
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        options
+            .open(&path)?
+            .write_all(toml::to_string(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Returns the location of the configuration file,
+/// `$XDG_CONFIG_HOME/escli/config.toml` (or `~/.config/escli/config.toml`).
+///
+pub fn path() -> Option<PathBuf> {
+    let config_home = match env::var("XDG_CONFIG_HOME") {
+        Ok(it) if !it.is_empty() => PathBuf::from(it),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_home.join("escli").join("config.toml"))
+}
+
+/// Returns the location of the file holding the current context, as set by
+/// `context use`, under `$XDG_STATE_HOME/escli` (or `~/.local/state/escli`).
+/// This is kept apart from the configuration file, so that switching
+/// clusters never rewrites it.
+///
+pub fn context_path() -> Option<PathBuf> {
+    let state_home = match env::var("XDG_STATE_HOME") {
+        Ok(it) if !it.is_empty() => PathBuf::from(it),
+        _ => PathBuf::from(env::var("HOME").ok()?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(state_home.join("escli").join("context"))
+}
+
+/// Returns the name of the current context, if one has been set.
+///
+pub fn current_context() -> Option<String> {
+    let name = fs::read_to_string(context_path()?).ok()?;
+    Some(name.trim().to_string()).filter(|x| !x.is_empty())
+}
+
+/// Sets the current context, or clears it if `None` is given.
+///
+pub fn set_current_context(name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let path = context_path().ok_or("cannot locate state directory without HOME")?;
+    match name {
+        Some(name) => {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, format!("{name}\n"))?;
+        }
+        None if path.exists() => fs::remove_file(&path)?,
+        None => {}
+    }
+    Ok(())
+}
+
+static ACTIVE: OnceLock<Option<(String, Profile)>> = OnceLock::new();
+
+/// Chooses the profile to connect with for this run, in order of
+/// precedence:
+///
+/// 1. A profile named by `--profile` or `ESCLI_PROFILE`
+/// 2. None, if `ESCLI_URL` is set, so that environment variables are used
+/// 3. The current context, as set by `context use`
+/// 4. The default profile
+///
+/// A profile named explicitly or as the current context must exist.
+///
+pub fn select(name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let name = name
+        .map(|x| x.to_string())
+        .or_else(|| env::var("ESCLI_PROFILE").ok().filter(|x| !x.is_empty()));
+    let config = ConfigFile::load()?;
+    let active = match name {
+        Some(name) => match config.profiles.get(&name) {
+            Some(profile) => Some((name, profile.clone())),
+            None => return Err(format!("no such profile: {name}").into()),
+        },
+        None if env::var("ESCLI_URL").is_ok() => None,
+        None => match current_context() {
+            Some(name) => match config.profiles.get(&name) {
+                Some(profile) => Some((name, profile.clone())),
+                None => {
+                    return Err(format!(
+                        "current context {name} is not a profile (switch with `escli context use`)"
+                    )
+                    .into())
+                }
+            },
+            None => config
+                .default
+                .and_then(|name| Some((name.clone(), config.profiles.get(&name)?.clone()))),
+        },
+    };
+    let _ = ACTIVE.set(active);
+    Ok(())
+}
+
+/// Returns the name and details of the profile in use, if any.
+///
+pub fn active() -> Option<&'static (String, Profile)> {
+    ACTIVE.get_or_init(|| None).as_ref()
+}
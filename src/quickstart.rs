@@ -0,0 +1,304 @@
+use std::{collections::HashMap, process::ExitCode};
+
+use serde_json::json;
+
+use crate::{
+    client::{BulkDocument, SimpleClient},
+    data::Table,
+    mapping::{confirm, prompt},
+};
+
+/// Index name offered by default for the demo data.
+///
+const DEFAULT_INDEX: &str = "quickstart-films";
+
+/// Field mappings for the demo index, as they would be given to `mk -m`.
+///
+const FIELDS: &[(&str, &str)] = &[
+    ("title", "text"),
+    ("director", "text"),
+    ("genre", "keyword"),
+    ("year", "integer"),
+    ("rating", "float"),
+];
+
+/// A small bundled dataset of films: title, director, genre, year, rating.
+///
+const FILMS: &[(&str, &str, &str, u16, f32)] = &[
+    (
+        "Night of the Hunter",
+        "Charles Laughton",
+        "thriller",
+        1955,
+        8.0,
+    ),
+    ("Vertigo", "Alfred Hitchcock", "thriller", 1958, 8.3),
+    (
+        "North by Northwest",
+        "Alfred Hitchcock",
+        "thriller",
+        1959,
+        8.3,
+    ),
+    ("Lawrence of Arabia", "David Lean", "drama", 1962, 8.3),
+    ("Dr. Strangelove", "Stanley Kubrick", "comedy", 1964, 8.4),
+    (
+        "2001: A Space Odyssey",
+        "Stanley Kubrick",
+        "science fiction",
+        1968,
+        8.3,
+    ),
+    ("The Godfather", "Francis Ford Coppola", "drama", 1972, 9.2),
+    ("Annie Hall", "Woody Allen", "comedy", 1977, 8.0),
+    ("Alien", "Ridley Scott", "science fiction", 1979, 8.5),
+    ("Blade Runner", "Ridley Scott", "science fiction", 1982, 8.1),
+    ("Back to the Future", "Robert Zemeckis", "comedy", 1985, 8.5),
+    ("Groundhog Day", "Harold Ramis", "comedy", 1993, 8.0),
+    ("Pulp Fiction", "Quentin Tarantino", "thriller", 1994, 8.9),
+    ("Heat", "Michael Mann", "thriller", 1995, 8.3),
+    ("Fargo", "Joel Coen", "thriller", 1996, 8.1),
+    ("The Matrix", "Lana Wachowski", "science fiction", 1999, 8.7),
+    ("Spirited Away", "Hayao Miyazaki", "animation", 2001, 8.6),
+    ("Lost in Translation", "Sofia Coppola", "drama", 2003, 7.7),
+    (
+        "Eternal Sunshine of the Spotless Mind",
+        "Michel Gondry",
+        "drama",
+        2004,
+        8.3,
+    ),
+    ("No Country for Old Men", "Joel Coen", "thriller", 2007, 8.2),
+    ("Up", "Pete Docter", "animation", 2009, 8.3),
+    (
+        "Inception",
+        "Christopher Nolan",
+        "science fiction",
+        2010,
+        8.8,
+    ),
+    (
+        "The Grand Budapest Hotel",
+        "Wes Anderson",
+        "comedy",
+        2014,
+        8.1,
+    ),
+    ("Arrival", "Denis Villeneuve", "science fiction", 2016, 7.9),
+    ("Parasite", "Bong Joon Ho", "thriller", 2019, 8.5),
+    (
+        "Everything Everywhere All at Once",
+        "Daniel Kwan",
+        "science fiction",
+        2022,
+        7.8,
+    ),
+];
+
+/// Walks a new user through creating an index, loading a small dataset and
+/// querying it, pausing between steps to explain what is happening and to
+/// show the equivalent escli commands.
+///
+pub async fn run(es: &SimpleClient) -> ExitCode {
+    println!("Welcome to the escli quickstart!");
+    println!();
+    println!("This creates a small demo index of films, then runs some example");
+    println!("searches and aggregations against it, explaining each step.");
+    println!();
+    let answer = prompt(&format!("Index name [{DEFAULT_INDEX}]: "));
+    let index = if answer.is_empty() {
+        DEFAULT_INDEX.to_string()
+    } else {
+        answer
+    };
+    if let Err(e) = provision(es, &index).await {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+    if let Err(e) = explore(es, &index).await {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+    println!();
+    if confirm(&format!("Delete the demo index {index}? [y/N] "), false) {
+        match es.delete_index(&index).await {
+            Ok(_) => {
+                println!("Deleted {index}");
+                equivalent(&format!("escli rm {index}"));
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        println!("Keeping {index}; remove it later with: escli rm {index}");
+    }
+    ExitCode::SUCCESS
+}
+
+async fn provision(es: &SimpleClient, index: &str) -> Result<(), Box<dyn std::error::Error>> {
+    step("Create an index");
+    println!("An index holds documents, and its mapping declares the type of each");
+    println!("field: 'text' fields are analysed for full-text search, 'keyword'");
+    println!("fields are matched exactly, and numeric fields support ranges.");
+    if es.index_exists(index).await? {
+        if !confirm(
+            &format!("Index {index} already exists. Delete and recreate it? [y/N] "),
+            false,
+        ) {
+            return Err(format!("index {index} already exists").into());
+        }
+        es.delete_index(index).await?;
+    }
+    let mut properties = json!({});
+    for (name, field_type) in FIELDS.iter() {
+        properties[name] = json!({"type": field_type});
+    }
+    es.create_index(
+        index,
+        &json!({"mappings": {"properties": properties}}),
+        &None,
+        &None,
+    )
+    .await?;
+    println!("Created {index}");
+    let specs: Vec<String> = FIELDS
+        .iter()
+        .map(|(name, field_type)| format!("-m {name}:{field_type}"))
+        .collect();
+    equivalent(&format!("escli mk {index} {}", specs.join(" ")));
+    pause();
+
+    step("Load some documents");
+    println!("Documents are JSON objects, sent in batches using the bulk API.");
+    let documents: Vec<BulkDocument> = FILMS
+        .iter()
+        .enumerate()
+        .map(|(i, (title, director, genre, year, rating))| BulkDocument {
+            id: Some((i + 1).to_string()),
+            routing: None,
+            create: false,
+            source: HashMap::from([
+                ("title".to_string(), json!(title)),
+                ("director".to_string(), json!(director)),
+                ("genre".to_string(), json!(genre)),
+                ("year".to_string(), json!(year)),
+                ("rating".to_string(), json!(rating)),
+            ]),
+        })
+        .collect();
+    es.bulk_index(index, &documents).await?;
+    println!("Loaded {} films into {index}", documents.len());
+    equivalent(&format!("escli load {index} films.csv"));
+    pause();
+    Ok(())
+}
+
+async fn explore(es: &SimpleClient, index: &str) -> Result<(), Box<dyn std::error::Error>> {
+    step("Full-text search");
+    println!("A query on a text field matches individual words, regardless of case,");
+    println!("and ranks the results by relevance.");
+    search(es, index, "title:night", None).await?;
+    pause();
+
+    step("Filter and sort");
+    println!("Queries can combine exact matches on keyword fields with ranges on");
+    println!("numeric fields, and the results can be sorted by any field.");
+    search(
+        es,
+        index,
+        "genre:thriller AND year:>=1990",
+        Some("rating:desc"),
+    )
+    .await?;
+    pause();
+
+    step("Aggregate");
+    println!("Aggregations summarise documents rather than returning them. Here, a");
+    println!("terms aggregation groups films by genre, and a nested avg aggregation");
+    println!("works out the average rating within each group.");
+    let body = json!({
+        "size": 0,
+        "aggs": {
+            "genres": {
+                "terms": {"field": "genre"},
+                "aggs": {"avg_rating": {"avg": {"field": "rating"}}}
+            }
+        }
+    });
+    let result = es.search_body(index, &body).await?;
+    let mut table = Table::with_column_names(vec![
+        "genre".to_string(),
+        "films".to_string(),
+        "avg_rating".to_string(),
+    ]);
+    for bucket in result["aggregations"]["genres"]["buckets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        table.push_row(vec![
+            bucket["key"].as_str().unwrap_or("").to_string(),
+            bucket["doc_count"].to_string(),
+            format!(
+                "{:.2}",
+                bucket["avg_rating"]["value"].as_f64().unwrap_or(0.0)
+            ),
+        ]);
+    }
+    table.print();
+    println!();
+    println!("escli has no aggregation command yet; the equivalent request is:");
+    println!("  GET /{index}/_search");
+    println!("  {body}");
+    Ok(())
+}
+
+async fn search(
+    es: &SimpleClient,
+    index: &str,
+    query: &str,
+    order_by: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let result = es
+        .search(
+            index,
+            &Some(query.to_string()),
+            &order_by.map(|x| x.to_string()),
+            &Some(10),
+            &None,
+        )
+        .await?;
+    let mut table = Table::new();
+    for hit in result.hits.hits.iter() {
+        table.push_document(&hit._source);
+    }
+    if table.count_rows() == 0 {
+        println!("No rows");
+    } else {
+        table.print();
+    }
+    equivalent(&match order_by {
+        Some(x) => format!("escli search {index} '{query}' -o {x}"),
+        None => format!("escli search {index} '{query}'"),
+    });
+    Ok(())
+}
+
+fn step(title: &str) {
+    println!();
+    println!("== {title}");
+    println!();
+}
+
+fn equivalent(command: &str) {
+    println!();
+    println!("Equivalent command:");
+    println!("  {command}");
+}
+
+fn pause() {
+    println!();
+    prompt("Press Enter to continue...");
+}
@@ -0,0 +1,75 @@
+use crate::output;
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const CHANGED: &str = "\x1b[7m";
+
+/// A view that is redrawn in place on each refresh, for watch modes. When
+/// colour is enabled, words that differ from the previous refresh are shown
+/// in reverse video. Otherwise, each refresh is simply printed in turn.
+///
+pub struct Screen {
+    previous: Vec<String>,
+    colour: bool,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Self {
+            previous: vec![],
+            colour: output::colour_stdout(),
+        }
+    }
+
+    pub fn show(&mut self, text: &str) {
+        let lines: Vec<String> = text.lines().map(|x| x.to_string()).collect();
+        if self.colour {
+            print!("{CLEAR}");
+            for (i, line) in lines.iter().enumerate() {
+                match self.previous.get(i) {
+                    Some(before) if !self.previous.is_empty() => {
+                        println!("{}", highlight_changes(before, line))
+                    }
+                    _ => println!("{line}"),
+                }
+            }
+        } else {
+            if !self.previous.is_empty() {
+                println!();
+            }
+            println!("{text}");
+        }
+        self.previous = lines;
+    }
+}
+
+/// Highlights each word of a line that differs from the text in the same
+/// position of the line before it. Tables are aligned, so position is a
+/// good guide to which value a word belongs to.
+///
+fn highlight_changes(before: &str, after: &str) -> String {
+    let before: Vec<char> = before.chars().collect();
+    let mut out = String::new();
+    let mut word = String::new();
+    let mut changed = false;
+    for (i, c) in after.chars().enumerate() {
+        if c.is_whitespace() {
+            push_word(&mut out, &mut word, changed);
+            changed = false;
+            out.push(c);
+        } else {
+            changed |= before.get(i) != Some(&c);
+            word.push(c);
+        }
+    }
+    push_word(&mut out, &mut word, changed);
+    out
+}
+
+fn push_word(out: &mut String, word: &mut String, changed: bool) {
+    if changed {
+        out.push_str(&output::paint(CHANGED, word, true));
+    } else {
+        out.push_str(word);
+    }
+    word.clear();
+}
@@ -0,0 +1,158 @@
+use std::{collections::BTreeMap, env, path::PathBuf};
+
+use clap::Command;
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Context,
+    Helper,
+};
+
+/// Words understood by the shell itself, rather than passed on as commands.
+///
+pub const BUILTINS: [&str; 6] = ["set", "unset", "vars", "help", "exit", "quit"];
+
+/// The state of an interactive session: variables set with `set`, which
+/// are substituted for `$NAME` in later commands. The `output` variable
+/// also sets the default output format.
+///
+#[derive(Default)]
+pub struct Session {
+    pub variables: BTreeMap<String, String>,
+}
+
+impl Session {
+    /// Replaces each `$NAME` word with the value of that variable.
+    ///
+    pub fn expand(&self, words: Vec<String>) -> Result<Vec<String>, String> {
+        words
+            .into_iter()
+            .map(|word| match word.strip_prefix('$') {
+                Some(name) if !name.is_empty() => match self.variables.get(name) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(format!("No such variable: {name}")),
+                },
+                _ => Ok(word),
+            })
+            .collect()
+    }
+}
+
+/// Splits a command line into words at whitespace, as a Unix shell would,
+/// honouring single and double quotes and backslash escapes.
+///
+pub fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => match chars.next() {
+                Some(escaped) => word.get_or_insert_with(String::new).push(escaped),
+                None => return Err("Trailing backslash".to_string()),
+            },
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("Unterminated quote".to_string());
+    }
+    words.extend(word.take());
+    Ok(words)
+}
+
+/// Returns the file in which shell history is kept, under
+/// `$XDG_DATA_HOME/escli` (or `~/.local/share/escli`).
+///
+pub fn history_path() -> Option<PathBuf> {
+    let data_home = match env::var("XDG_DATA_HOME") {
+        Ok(it) if !it.is_empty() => PathBuf::from(it),
+        _ => PathBuf::from(env::var("HOME").ok()?)
+            .join(".local")
+            .join("share"),
+    };
+    Some(data_home.join("escli").join("shell-history"))
+}
+
+/// Completes command names, subcommand names and long options from the
+/// command line definition, and index names as fetched from the cluster.
+///
+pub struct ShellHelper {
+    command: Command,
+    pub index_names: Vec<String>,
+}
+
+impl ShellHelper {
+    pub fn new(command: Command) -> Self {
+        Self {
+            command,
+            index_names: vec![],
+        }
+    }
+
+    fn candidates(&self, previous: &[&str], partial: &str) -> Vec<String> {
+        let mut command = &self.command;
+        for word in previous.iter() {
+            match command.find_subcommand(word) {
+                Some(it) => command = it,
+                None => break,
+            }
+        }
+        let mut names: Vec<String> = if partial.starts_with('-') {
+            command
+                .get_arguments()
+                .chain(self.command.get_arguments().filter(|x| x.is_global_set()))
+                .filter_map(|x| x.get_long())
+                .map(|x| format!("--{x}"))
+                .collect()
+        } else {
+            let mut names: Vec<String> = command
+                .get_subcommands()
+                .filter(|x| !x.is_hide_set())
+                .map(|x| x.get_name().to_string())
+                .collect();
+            if previous.is_empty() {
+                names.extend(BUILTINS.iter().map(|x| x.to_string()));
+            } else {
+                names.extend(self.index_names.iter().cloned());
+            }
+            names
+        };
+        names.retain(|x| x.starts_with(partial));
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+        Ok((start, self.candidates(&previous, &line[start..])))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
@@ -0,0 +1,116 @@
+use std::{error::Error, fs::File, io::Cursor, io::Read};
+
+use calamine::{Data, DataType, Reader, Xlsx};
+use flate2::read::GzDecoder;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+
+use crate::client::Document;
+
+/// Opens an input file for reading, given either a local filename or an
+/// `http://` or `https://` URL. Remote files are fetched with any extra
+/// headers supplied (each in `Name: value` form), and files with a `.gz`
+/// extension are transparently decompressed.
+///
+pub async fn open(location: &str, headers: &[String]) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    let reader: Box<dyn Read> = if is_url(location) {
+        Box::new(Cursor::new(fetch(location, headers).await?))
+    } else {
+        Box::new(File::open(location)?)
+    };
+    if location_path(location).ends_with(".gz") {
+        Ok(Box::new(GzDecoder::new(reader)))
+    } else {
+        Ok(reader)
+    }
+}
+
+/// Reads the rows of an Excel worksheet as documents, using the first row
+/// as field names. The first worksheet in the workbook is used unless one is
+/// named. Empty cells are omitted from the documents, and numbers with no
+/// fractional part are treated as integers.
+///
+pub async fn read_xlsx(
+    location: &str,
+    sheet: Option<&str>,
+    headers: &[String],
+) -> Result<Vec<Document>, Box<dyn Error>> {
+    let mut data = vec![];
+    open(location, headers).await?.read_to_end(&mut data)?;
+    let mut workbook: Xlsx<_> = Xlsx::new(Cursor::new(data))?;
+    let sheet = match sheet {
+        Some(name) => name.to_string(),
+        None => match workbook.sheet_names().first() {
+            Some(name) => name.to_owned(),
+            None => return Err(format!("{location} contains no worksheets").into()),
+        },
+    };
+    let range = workbook.worksheet_range(&sheet)?;
+    let mut rows = range.rows();
+    let columns: Vec<String> = match rows.next() {
+        Some(row) => row.iter().map(|cell| cell.to_string()).collect(),
+        None => return Ok(vec![]),
+    };
+    Ok(rows
+        .map(|row| {
+            columns
+                .iter()
+                .zip(row.iter())
+                .filter(|(column, _)| !column.is_empty())
+                .filter_map(|(column, cell)| Some((column.to_owned(), cell_value(cell)?)))
+                .collect()
+        })
+        .collect())
+}
+
+fn cell_value(cell: &Data) -> Option<Value> {
+    match cell {
+        Data::Int(x) => Some(Value::from(*x)),
+        Data::Float(x) if x.fract() == 0.0 && x.abs() < 9.0e15 => Some(Value::from(*x as i64)),
+        Data::Float(x) => Some(Value::from(*x)),
+        Data::String(x) => Some(Value::from(x.as_str())),
+        Data::Bool(x) => Some(Value::from(*x)),
+        Data::DateTime(_) => cell
+            .as_datetime()
+            .map(|x| Value::from(x.to_string().replacen(' ', "T", 1))),
+        Data::DateTimeIso(x) | Data::DurationIso(x) => Some(Value::from(x.as_str())),
+        Data::Error(_) | Data::Empty => None,
+    }
+}
+
+fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Returns the path portion of a location, ignoring any URL query string or
+/// fragment, so that the file extension can be checked.
+///
+fn location_path(location: &str) -> &str {
+    if is_url(location) {
+        location.split(['?', '#']).next().unwrap_or(location)
+    } else {
+        location
+    }
+}
+
+async fn fetch(url: &str, headers: &[String]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut header_map = HeaderMap::new();
+    for header in headers.iter() {
+        match header.split_once(':') {
+            Some((name, value)) => {
+                header_map.insert(
+                    HeaderName::from_bytes(name.trim().as_bytes())?,
+                    HeaderValue::from_str(value.trim())?,
+                );
+            }
+            None => return Err(format!("invalid header {header} (expected NAME: VALUE)").into()),
+        }
+    }
+    let response = reqwest::Client::new()
+        .get(url)
+        .headers(header_map)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
@@ -0,0 +1,107 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use elasticsearch::http::Url;
+use serde_json::Value;
+
+/// A previous version of an index template, as saved before it was last
+/// replaced. Versions are numbered from 1 (the oldest) upwards.
+///
+pub struct SavedVersion {
+    pub number: usize,
+    pub saved_at: SystemTime,
+    pub path: PathBuf,
+}
+
+impl SavedVersion {
+    pub fn body(&self) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(&self.path)?)?)
+    }
+}
+
+/// Reads a template body, or other object such as a role definition, from a
+/// JSON or YAML file (chosen by extension).
+///
+pub fn read_body(path: &str) -> Result<Value, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let value: Value = if path.ends_with(".yaml") || path.ends_with(".yml") {
+        serde_yaml::from_str(&text)?
+    } else {
+        serde_json::from_str(&text)?
+    };
+    if !value.is_object() {
+        return Err(format!("{path} does not contain an object").into());
+    }
+    Ok(value)
+}
+
+/// Saves a template body to the local history for a cluster, before it is
+/// replaced. History is kept under `$XDG_DATA_HOME/escli/template-history`
+/// (or `~/.local/share/escli/template-history`), with one directory per
+/// cluster and template, and one file per version.
+///
+pub fn save(url: &Url, name: &str, body: &Value) -> Result<(), Box<dyn Error>> {
+    let dir = history_dir(url, name)?;
+    fs::create_dir_all(&dir)?;
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    fs::write(
+        dir.join(format!("{millis}.json")),
+        serde_json::to_string_pretty(body)?,
+    )?;
+    Ok(())
+}
+
+/// Lists the saved versions of a template, oldest first.
+///
+pub fn history(url: &Url, name: &str) -> Result<Vec<SavedVersion>, Box<dyn Error>> {
+    let dir = history_dir(url, name)?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut saved: Vec<(u64, PathBuf)> = vec![];
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let millis = path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.parse::<u64>().ok());
+        if let Some(millis) = millis {
+            saved.push((millis, path));
+        }
+    }
+    saved.sort();
+    Ok(saved
+        .into_iter()
+        .enumerate()
+        .map(|(i, (millis, path))| SavedVersion {
+            number: i + 1,
+            saved_at: UNIX_EPOCH + Duration::from_millis(millis),
+            path,
+        })
+        .collect())
+}
+
+fn history_dir(url: &Url, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let data_home = match env::var("XDG_DATA_HOME") {
+        Ok(it) if !it.is_empty() => PathBuf::from(it),
+        _ => match env::var("HOME") {
+            Ok(home) => PathBuf::from(home).join(".local").join("share"),
+            Err(_) => return Err("cannot locate template history without HOME".into()),
+        },
+    };
+    let cluster = format!(
+        "{}_{}",
+        url.host_str().unwrap_or("localhost"),
+        url.port_or_known_default().unwrap_or(9200)
+    );
+    Ok(data_home
+        .join("escli")
+        .join("template-history")
+        .join(cluster)
+        .join(name))
+}
@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// A token bucket, used to limit the average rate at which some quantity
+/// (such as documents or bytes) is consumed. Up to one second's worth of
+/// tokens may accumulate while idle, allowing short bursts.
+///
+pub struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes the given number of tokens from the bucket, waiting as long as
+    /// necessary for the bucket to cover them. Requests larger than the
+    /// bucket capacity are allowed, but are paid for by a longer wait.
+    ///
+    pub async fn acquire(&mut self, amount: f64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+        self.tokens -= amount;
+        if self.tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-self.tokens / self.rate)).await;
+            self.tokens = 0.0;
+            self.last_refill = Instant::now();
+        }
+    }
+}
+
+/// Limits the rate of bulk submissions by document count and/or by size.
+///
+#[derive(Default)]
+pub struct Throttle {
+    docs: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl Throttle {
+    pub fn new(max_docs_per_sec: Option<f64>, max_bytes_per_sec: Option<f64>) -> Self {
+        Self {
+            docs: max_docs_per_sec.map(TokenBucket::new),
+            bytes: max_bytes_per_sec.map(TokenBucket::new),
+        }
+    }
+
+    /// Waits until a batch of the given number of documents and bytes can be
+    /// sent without exceeding the configured rates.
+    ///
+    pub async fn wait(&mut self, docs: usize, bytes: usize) {
+        if let Some(bucket) = &mut self.docs {
+            bucket.acquire(docs as f64).await;
+        }
+        if let Some(bucket) = &mut self.bytes {
+            bucket.acquire(bytes as f64).await;
+        }
+    }
+}
+
+/// The longest wait between retry attempts, however many have been made.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Attempts beyond this many no longer double the delay, which by then is
+/// capped by `MAX_BACKOFF` for any sensible base delay.
+const MAX_DOUBLINGS: u32 = 20;
+
+/// Calculates how long to wait before making a retry attempt, doubling a
+/// base delay (in seconds) for each attempt already made, up to a maximum
+/// of five minutes. A random jitter of up to 50% either way is applied, so
+/// that concurrent clients do not all retry in lockstep.
+///
+pub fn backoff_delay(base: f64, attempt: u32) -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.subsec_nanos());
+    let jitter = 0.5 + nanos as f64 / 1e9;
+    let seconds = base * 2f64.powi(attempt.min(MAX_DOUBLINGS) as i32) * jitter;
+    Duration::try_from_secs_f64(seconds).map_or(MAX_BACKOFF, |x| x.min(MAX_BACKOFF))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn token_bucket_allows_a_burst_then_limits_the_rate() {
+        let mut bucket = TokenBucket::new(1000.0);
+        let start = Instant::now();
+        bucket.acquire(1000.0).await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+        bucket.acquire(100.0).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(90) && elapsed < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn backoff_delay_doubles_with_jitter() {
+        for attempt in 0..4 {
+            let delay = backoff_delay(1.0, attempt).as_secs_f64();
+            let doubled = 2f64.powi(attempt as i32);
+            assert!(delay >= 0.5 * doubled && delay <= 1.5 * doubled);
+        }
+    }
+
+    #[test]
+    fn backoff_delay_is_capped() {
+        assert!(backoff_delay(1.0, 30) <= MAX_BACKOFF);
+        assert!(backoff_delay(1.0, u32::MAX) <= MAX_BACKOFF);
+        assert!(backoff_delay(f64::MAX, 1) <= MAX_BACKOFF);
+        assert_eq!(backoff_delay(0.0, 5), Duration::ZERO);
+    }
+}
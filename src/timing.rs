@@ -0,0 +1,59 @@
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use elasticsearch::http::Url;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{lookup_host, TcpStream},
+};
+use tokio_native_tls::{native_tls, TlsConnector};
+
+/// Makes a fresh connection to the host of a URL, timing each phase in
+/// turn: DNS lookup, TCP connect, TLS handshake (for HTTPS only) and the
+/// first byte of the response to a `HEAD /` request. This is separate from
+/// the connections made by the client itself, which are pooled and so do
+/// not expose these phases. Certificates are not verified, since no data is
+/// exchanged beyond the request line.
+///
+pub async fn probe(url: &Url) -> Result<Vec<(&'static str, Duration)>, Box<dyn Error>> {
+    let host = url.host_str().unwrap_or("localhost");
+    let port = url.port_or_known_default().unwrap_or(9200);
+    let mut phases = vec![];
+
+    let start = Instant::now();
+    let address = match lookup_host((host, port)).await?.next() {
+        Some(it) => it,
+        None => return Err(format!("no addresses found for {host}").into()),
+    };
+    phases.push(("DNS lookup", start.elapsed()));
+
+    let start = Instant::now();
+    let stream = TcpStream::connect(address).await?;
+    phases.push(("TCP connect", start.elapsed()));
+
+    let request = format!("HEAD / HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n");
+    let mut first_byte = [0u8; 1];
+    if url.scheme() == "https" {
+        let start = Instant::now();
+        let connector = TlsConnector::from(
+            native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(true)
+                .build()?,
+        );
+        let mut stream = connector.connect(host, stream).await?;
+        phases.push(("TLS handshake", start.elapsed()));
+        let start = Instant::now();
+        stream.write_all(request.as_bytes()).await?;
+        stream.read_exact(&mut first_byte).await?;
+        phases.push(("First byte", start.elapsed()));
+    } else {
+        let mut stream = stream;
+        let start = Instant::now();
+        stream.write_all(request.as_bytes()).await?;
+        stream.read_exact(&mut first_byte).await?;
+        phases.push(("First byte", start.elapsed()));
+    }
+    Ok(phases)
+}